@@ -0,0 +1,240 @@
+use crate::{quaternion::Quaternion, scene_node::SceneNode, vec3::Vector3};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnimationPath {
+    Translation,
+    Rotation,
+    Scale,
+    // Morph target weights, kept but not applied since meshes have no targets
+    Weights,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyInterpolation {
+    Step,
+    Linear,
+    // Hermite curve, each key stores in-tangent, value and out-tangent
+    CubicSpline,
+}
+
+// Keyframes for one property of one node
+#[derive(Clone, Debug)]
+pub struct AnimationChannel {
+    pub node: usize,
+    pub path: AnimationPath,
+    pub interpolation: KeyInterpolation,
+    // Seconds, ascending
+    pub times: Vec<f32>,
+    // Flat, `components()` values per key (three times that for CubicSpline)
+    pub values: Vec<f32>,
+}
+
+impl AnimationChannel {
+    pub fn components(&self) -> usize {
+        let per_key = self.values.len() / self.times.len().max(1);
+        match self.interpolation {
+            KeyInterpolation::CubicSpline => per_key / 3,
+            _ => per_key,
+        }
+    }
+
+    fn key_value(&self, key: usize, part: usize) -> &[f32] {
+        let n = self.components();
+        let start = match self.interpolation {
+            // in-tangent, value, out-tangent
+            KeyInterpolation::CubicSpline => (key * 3 + part) * n,
+            _ => key * n,
+        };
+        &self.values[start..start + n]
+    }
+
+    // Value at a time, held at the first and last keys outside their range
+    pub fn sample(&self, time: f32) -> Vec<f32> {
+        let n = self.components();
+        if self.times.is_empty() || n == 0 {
+            return vec![];
+        }
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return self.key_value(0, 1).to_vec();
+        }
+        if time >= self.times[last] {
+            return self.key_value(last, 1).to_vec();
+        }
+
+        let k = self.times.partition_point(|&t| t <= time) - 1;
+        let dt = self.times[k + 1] - self.times[k];
+        let t = if dt > 0.0 { (time - self.times[k]) / dt } else { 0.0 };
+
+        let mut out = match self.interpolation {
+            KeyInterpolation::Step => self.key_value(k, 1).to_vec(),
+            KeyInterpolation::Linear if self.path == AnimationPath::Rotation && n == 4 => {
+                let a = self.key_value(k, 1);
+                let b = self.key_value(k + 1, 1);
+                let q = Quaternion::new(a[0], a[1], a[2], a[3])
+                    .slerp(&Quaternion::new(b[0], b[1], b[2], b[3]), t);
+                return vec![q.x, q.y, q.z, q.w];
+            }
+            KeyInterpolation::Linear => {
+                let a = self.key_value(k, 1);
+                let b = self.key_value(k + 1, 1);
+                a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect()
+            }
+            KeyInterpolation::CubicSpline => {
+                let p0 = self.key_value(k, 1);
+                let m0 = self.key_value(k, 2);
+                let p1 = self.key_value(k + 1, 1);
+                let m1 = self.key_value(k + 1, 0);
+                let t2 = t * t;
+                let t3 = t2 * t;
+                (0..n)
+                    .map(|i| {
+                        (2.0 * t3 - 3.0 * t2 + 1.0) * p0[i]
+                            + (t3 - 2.0 * t2 + t) * dt * m0[i]
+                            + (-2.0 * t3 + 3.0 * t2) * p1[i]
+                            + (t3 - t2) * dt * m1[i]
+                    })
+                    .collect()
+            }
+        };
+        if self.path == AnimationPath::Rotation && n == 4 {
+            let q = Quaternion::new(out[0], out[1], out[2], out[3]).normalize();
+            out = vec![q.x, q.y, q.z, q.w];
+        }
+        out
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Animation {
+    #[allow(dead_code)]
+    pub name: String,
+    pub channels: Vec<AnimationChannel>,
+}
+
+impl Animation {
+    pub fn duration(&self) -> f32 {
+        self.channels
+            .iter()
+            .filter_map(|c| c.times.last())
+            .fold(0.0, |a, &b| a.max(b))
+    }
+
+    // Poses the nodes at a time, looping if asked to
+    #[allow(dead_code)]
+    pub fn apply(&self, time: f32, looping: bool, nodes: &mut [SceneNode]) {
+        let duration = self.duration();
+        let time = if looping && duration > 0.0 { time.rem_euclid(duration) } else { time };
+        for channel in &self.channels {
+            let Some(node) = nodes.get_mut(channel.node) else {
+                continue;
+            };
+            let v = channel.sample(time);
+            match channel.path {
+                AnimationPath::Translation if v.len() == 3 => {
+                    node.translation = Vector3::new(v[0], v[1], v[2]);
+                }
+                AnimationPath::Rotation if v.len() == 4 => {
+                    node.rotation = Quaternion::new(v[0], v[1], v[2], v[3]);
+                }
+                AnimationPath::Scale if v.len() == 3 => {
+                    node.scale = Vector3::new(v[0], v[1], v[2]);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(
+        path: AnimationPath,
+        interpolation: KeyInterpolation,
+        times: &[f32],
+        values: &[f32],
+    ) -> AnimationChannel {
+        AnimationChannel {
+            node: 0,
+            path,
+            interpolation,
+            times: times.to_vec(),
+            values: values.to_vec(),
+        }
+    }
+
+    fn close(a: &[f32], b: &[f32]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5)
+    }
+
+    #[test]
+    fn step_holds_each_key() {
+        let values = [0.0, 0.0, 0.0, 4.0, 2.0, 0.0];
+        let c = channel(AnimationPath::Translation, KeyInterpolation::Step, &[0.0, 1.0], &values);
+        assert_eq!(c.components(), 3);
+        assert_eq!(c.sample(0.99), [0.0, 0.0, 0.0]);
+        assert_eq!(c.sample(1.0), [4.0, 2.0, 0.0]);
+        // Held outside the keys
+        assert_eq!(c.sample(-1.0), [0.0, 0.0, 0.0]);
+        assert_eq!(c.sample(5.0), [4.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn linear_lerps_and_slerps() {
+        let c = channel(AnimationPath::Scale, KeyInterpolation::Linear, &[1.0, 3.0], &[1.0, 1.0, 1.0, 3.0, 5.0, 1.0]);
+        assert!(close(&c.sample(1.5), &[1.5, 2.0, 1.0]));
+
+        // Quarter turn about Y, halfway is an eighth of a turn rather than
+        // the shorter, unnormalized lerp
+        let start = Quaternion::identity();
+        let end = Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_2);
+        let c = channel(
+            AnimationPath::Rotation,
+            KeyInterpolation::Linear,
+            &[0.0, 2.0],
+            &[start.x, start.y, start.z, start.w, end.x, end.y, end.z, end.w],
+        );
+        let half = Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_4);
+        assert!(close(&c.sample(1.0), &[half.x, half.y, half.z, half.w]));
+    }
+
+    #[test]
+    fn cubic_spline_follows_the_tangents() {
+        // In-tangent, value, out-tangent per key
+        let flat = [9.0, 0.0, 0.0, 0.0, 2.0, 9.0];
+        let c = channel(AnimationPath::Weights, KeyInterpolation::CubicSpline, &[0.0, 2.0], &flat);
+        assert_eq!(c.components(), 1);
+        // Level ends make a smoothstep, and the outer tangents play no part
+        assert!(close(&c.sample(1.0), &[1.0]));
+        assert!(close(&c.sample(0.5), &[2.0 * (3.0 * 0.0625 - 2.0 * 0.015625)]));
+        assert!(close(&c.sample(2.0), &[2.0]));
+
+        // Tangents of 1 per second on a straight line of slope 1 give the line
+        let sloped = [0.0, 0.0, 1.0, 1.0, 2.0, 0.0];
+        let c = channel(AnimationPath::Weights, KeyInterpolation::CubicSpline, &[0.0, 2.0], &sloped);
+        for t in [0.25, 0.5, 1.0, 1.75] {
+            assert!(close(&c.sample(t), &[t]));
+        }
+    }
+
+    #[test]
+    fn apply_poses_nodes() {
+        let animation = Animation {
+            name: String::new(),
+            channels: vec![channel(
+                AnimationPath::Translation,
+                KeyInterpolation::Linear,
+                &[0.0, 2.0],
+                &[0.0, 0.0, 0.0, 2.0, 0.0, 0.0],
+            )],
+        };
+        assert_eq!(animation.duration(), 2.0);
+        let mut nodes = vec![SceneNode::new("moved")];
+        animation.apply(3.0, true, &mut nodes);
+        assert!((nodes[0].translation.x - 1.0).abs() < 1e-5);
+        animation.apply(3.0, false, &mut nodes);
+        assert!((nodes[0].translation.x - 2.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{
+    camera::Camera, color::Color, sprite_sheet::SpriteSheet, vec2::Vector2, vec3::Vector3, vertex::Vertex,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BillboardMode {
+    // Parallel to the view, for particles and labels
+    Spherical,
+    // Only turns around the Y axis and stays upright, for foliage and
+    // characters
+    Cylindrical,
+}
+
+// A quad turned to face the camera, showing one frame of a sprite sheet
+#[derive(Clone, Copy, Debug)]
+pub struct Billboard {
+    pub position: Vector3,
+    // World units
+    pub size: Vector2,
+    // Where `position` is on the quad, 0..1 from the left and from the
+    // bottom. (0.5, 0.0) stands the sprite on it.
+    pub pivot: Vector2,
+    pub mode: BillboardMode,
+    pub frame: usize,
+    // Multiplies the texture
+    pub color: Color,
+}
+
+impl Billboard {
+    pub fn new(position: Vector3, size: Vector2) -> Self {
+        Billboard {
+            position,
+            size,
+            pivot: Vector2::new(0.5, 0.5),
+            mode: BillboardMode::Spherical,
+            frame: 0,
+            color: Color::new(255, 255, 255, 255),
+        }
+    }
+
+    pub fn with_pivot(mut self, pivot: Vector2) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: BillboardMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_frame(mut self, frame: usize) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    // Right and up of the quad. Both modes take the camera's right rather
+    // than aiming at its position, so neighbouring sprites line up.
+    pub fn axes(&self, camera: &Camera) -> (Vector3, Vector3) {
+        let (forward, right, up) = camera.basis();
+        match self.mode {
+            BillboardMode::Spherical => (right, up),
+            BillboardMode::Cylindrical => {
+                let mut flat = Vector3::new(right.x, 0.0, right.z);
+                if flat.length() < 0.001 {
+                    // Camera rolled onto its side, square up to the view instead
+                    flat = Vector3::new(-forward.z, 0.0, forward.x);
+                }
+                if flat.length() < 0.001 {
+                    flat = Vector3::new(1.0, 0.0, 0.0);
+                }
+                (flat.normalize_v(), Vector3::new(0.0, 1.0, 0.0))
+            }
+        }
+    }
+
+    // Bottom left, bottom right, top right and top left, counter-clockwise
+    // seen from the camera and with normals pointing at it
+    pub fn corners(&self, camera: &Camera, sheet: &SpriteSheet) -> [Vertex; 4] {
+        let (right, up) = self.axes(camera);
+        let (width, height) = (right * self.size.x, up * self.size.y);
+        let bottom_left = self.position - width * self.pivot.x - height * self.pivot.y;
+        let normal = right.cross(&up).normalize_v();
+
+        let (uv_min, uv_max) = sheet.frame_uv(self.frame);
+        let corner = |pos: Vector3, u: f32, v: f32| {
+            let mut vertex = Vertex::new(&pos, &Vector2::new(u, v), &self.color);
+            vertex.normal = normal;
+            vertex.tangent = right;
+            vertex
+        };
+        [
+            corner(bottom_left, uv_min.x, uv_max.y),
+            corner(bottom_left + width, uv_max.x, uv_max.y),
+            corner(bottom_left + width + height, uv_max.x, uv_min.y),
+            corner(bottom_left + height, uv_min.x, uv_min.y),
+        ]
+    }
+}
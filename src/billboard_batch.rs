@@ -0,0 +1,62 @@
+use crate::{
+    billboard::Billboard, camera::Camera, mesh::Mesh, pixel_shader::AlphaTestShader, screen::Screen,
+    sprite_sheet::SpriteSheet,
+};
+
+// Billboards sharing a sprite sheet, turned to the camera and drawn as one
+// mesh. Edges are alpha tested instead of blended, so they're depth tested
+// and written like any other triangle and can go in any order.
+pub struct BillboardBatch {
+    pub sheet: SpriteSheet,
+    pub billboards: Vec<Billboard>,
+    // Texels with less alpha than this aren't drawn
+    pub alpha_cutoff: u8,
+}
+
+impl BillboardBatch {
+    pub fn new(sheet: SpriteSheet) -> Self {
+        BillboardBatch {
+            sheet,
+            billboards: vec![],
+            alpha_cutoff: 128,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_alpha_cutoff(mut self, alpha_cutoff: u8) -> Self {
+        self.alpha_cutoff = alpha_cutoff;
+        self
+    }
+
+    // Index of the billboard, for changing it later
+    pub fn add(&mut self, billboard: Billboard) -> usize {
+        self.billboards.push(billboard);
+        self.billboards.len() - 1
+    }
+
+    #[allow(dead_code)]
+    pub fn clear(&mut self) {
+        self.billboards.clear();
+    }
+
+    // Two triangles a billboard, facing `camera`
+    pub fn mesh(&self, camera: &Camera) -> Mesh {
+        let mut mesh = Mesh::new();
+        mesh.vertices.reserve(self.billboards.len() * 4);
+        mesh.indices.reserve(self.billboards.len() * 6);
+        for billboard in &self.billboards {
+            let base = mesh.vertices.len() as u32;
+            mesh.vertices.extend(billboard.corners(camera, &self.sheet));
+            mesh.indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+        mesh
+    }
+
+    pub fn draw(&self, screen: &mut Screen, camera: &Camera) {
+        if self.billboards.is_empty() {
+            return;
+        }
+        let shader = AlphaTestShader::new(&self.sheet.texture, self.alpha_cutoff);
+        self.mesh(camera).draw(screen, camera, &shader, &self.sheet.texture);
+    }
+}
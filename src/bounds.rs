@@ -0,0 +1,228 @@
+use crate::{triangle::Triangle, vec3::Vector3};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    #[allow(dead_code)]
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Aabb { min, max }
+    }
+
+    // Starts inverted so the first point grows it to a real box
+    pub fn empty() -> Self {
+        Aabb {
+            min: Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn grow(&mut self, p: &Vector3) {
+        self.min = Vector3::new(self.min.x.min(p.x), self.min.y.min(p.y), self.min.z.min(p.z));
+        self.max = Vector3::new(self.max.x.max(p.x), self.max.y.max(p.y), self.max.z.max(p.z));
+    }
+
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vector3>) -> Self {
+        let mut aabb = Aabb::empty();
+        for p in points {
+            aabb.grow(p);
+        }
+        aabb
+    }
+
+    pub fn from_triangles(triangles: &[Triangle]) -> Self {
+        Self::from_points(
+            triangles
+                .iter()
+                .flat_map(|t| [&t.v1.pos, &t.v2.pos, &t.v3.pos]),
+        )
+    }
+
+    pub fn center(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_size(&self) -> Vector3 {
+        (self.max - self.min) * 0.5
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingSphere {
+    pub center: Vector3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vector3, radius: f32) -> Self {
+        BoundingSphere { center, radius }
+    }
+
+    // Centered on the box, so not the tightest fit but cheap and never too small
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        if aabb.is_empty() {
+            return BoundingSphere::new(Vector3::new(0.0, 0.0, 0.0), 0.0);
+        }
+        BoundingSphere::new(aabb.center(), aabb.half_size().length())
+    }
+
+    #[allow(dead_code)]
+    pub fn from_triangles(triangles: &[Triangle]) -> Self {
+        Self::from_aabb(&Aabb::from_triangles(triangles))
+    }
+
+    // Ritter's method: start from two far apart points, then grow to take
+    // in anything left outside. Within a few percent of the smallest sphere.
+    pub fn from_points(points: &[Vector3]) -> Self {
+        let Some(&first) = points.first() else {
+            return BoundingSphere::new(Vector3::new(0.0, 0.0, 0.0), 0.0);
+        };
+        let farthest_from = |from: Vector3| {
+            points
+                .iter()
+                .copied()
+                .max_by(|a, b| (*a - from).length().total_cmp(&(*b - from).length()))
+                .unwrap_or(from)
+        };
+        let a = farthest_from(first);
+        let b = farthest_from(a);
+        let mut center = (a + b) * 0.5;
+        let mut radius = (b - a).length() * 0.5;
+        for p in points {
+            let distance = (*p - center).length();
+            if distance > radius {
+                let grown = (radius + distance) * 0.5;
+                center = center + (*p - center) * ((grown - radius) / distance);
+                radius = grown;
+            }
+        }
+        BoundingSphere::new(center, radius)
+    }
+}
+
+// Box turned to fit the points, along their principal axes
+#[derive(Clone, Copy, Debug)]
+pub struct Obb {
+    pub center: Vector3,
+    // Unit length and perpendicular to each other
+    pub axes: [Vector3; 3],
+    pub half_extents: Vector3,
+}
+
+impl Obb {
+    // Axes are the eigenvectors of the points' covariance, which fits
+    // elongated shapes well but isn't always the smallest box
+    pub fn from_points(points: &[Vector3]) -> Self {
+        if points.is_empty() {
+            let zero = Vector3::new(0.0, 0.0, 0.0);
+            return Obb {
+                center: zero,
+                axes: [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)],
+                half_extents: zero,
+            };
+        }
+        let n = points.len() as f32;
+        let mean = points.iter().fold(Vector3::new(0.0, 0.0, 0.0), |sum, p| sum + *p) * (1.0 / n);
+        let mut covariance = [[0.0f32; 3]; 3];
+        for p in points {
+            let d = [p.x - mean.x, p.y - mean.y, p.z - mean.z];
+            for (i, row) in covariance.iter_mut().enumerate() {
+                for (j, c) in row.iter_mut().enumerate() {
+                    *c += d[i] * d[j] / n;
+                }
+            }
+        }
+        let eigenvectors = symmetric_eigenvectors(covariance);
+        let mut axes = eigenvectors.map(|v| Vector3::new(v[0], v[1], v[2]).normalize_v());
+        // Keep it right-handed
+        axes[2] = axes[0].cross(&axes[1]).normalize_v();
+
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for p in points {
+            for (k, axis) in axes.iter().enumerate() {
+                let d = Vector3::dot(p, axis);
+                min[k] = min[k].min(d);
+                max[k] = max[k].max(d);
+            }
+        }
+        let mid = [0, 1, 2].map(|k| (min[k] + max[k]) * 0.5);
+        Obb {
+            center: axes[0] * mid[0] + axes[1] * mid[1] + axes[2] * mid[2],
+            axes,
+            half_extents: Vector3::new((max[0] - min[0]) * 0.5, (max[1] - min[1]) * 0.5, (max[2] - min[2]) * 0.5),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn volume(&self) -> f32 {
+        8.0 * self.half_extents.x * self.half_extents.y * self.half_extents.z
+    }
+
+    pub fn corners(&self) -> [Vector3; 8] {
+        let [ax, ay, az] = self.axes;
+        let h = self.half_extents;
+        std::array::from_fn(|i| {
+            let sx = if i & 1 == 0 { -h.x } else { h.x };
+            let sy = if i & 2 == 0 { -h.y } else { h.y };
+            let sz = if i & 4 == 0 { -h.z } else { h.z };
+            self.center + ax * sx + ay * sy + az * sz
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn contains(&self, p: &Vector3) -> bool {
+        let d = *p - self.center;
+        let h = [self.half_extents.x, self.half_extents.y, self.half_extents.z];
+        self.axes
+            .iter()
+            .zip(h)
+            .all(|(axis, half)| Vector3::dot(&d, axis).abs() <= half + 1e-5)
+    }
+}
+
+// Jacobi rotations until the off-diagonal part is gone. Returns the
+// eigenvectors, largest eigenvalue first.
+fn symmetric_eigenvectors(mut a: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..32 {
+        // Largest off-diagonal element
+        let (p, q) = [(0, 1), (0, 2), (1, 2)]
+            .into_iter()
+            .max_by(|&(i, j), &(k, l)| a[i][j].abs().total_cmp(&a[k][l].abs()))
+            .unwrap();
+        if a[p][q].abs() < 1e-9 {
+            break;
+        }
+        let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+        let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+        let c = 1.0 / (t * t + 1.0).sqrt();
+        let s = t * c;
+        for row in a.iter_mut() {
+            let (akp, akq) = (row[p], row[q]);
+            row[p] = c * akp - s * akq;
+            row[q] = s * akp + c * akq;
+        }
+        let (row_p, row_q) = (a[p], a[q]);
+        for (k, (apk, aqk)) in row_p.into_iter().zip(row_q).enumerate() {
+            a[p][k] = c * apk - s * aqk;
+            a[q][k] = s * apk + c * aqk;
+        }
+        for row in &mut v {
+            let (vp, vq) = (row[p], row[q]);
+            row[p] = c * vp - s * vq;
+            row[q] = s * vp + c * vq;
+        }
+    }
+    // Eigenvectors are the columns of v
+    let mut order = [0, 1, 2];
+    order.sort_by(|&i, &j| a[j][j].total_cmp(&a[i][i]));
+    order.map(|col| [v[0][col], v[1][col], v[2][col]])
+}
@@ -1,9 +1,32 @@
-use crate::{vec2::Vector2, vec3::Vector3};
+use crate::{ray::Ray, screen::{Screen, SCREEN_HEIGHT, SCREEN_WIDTH}, vec2::Vector2, vec3::Vector3};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+}
+
+#[derive(Clone)]
 pub struct Camera {
     pub fov: f32,
     pub pos: Vector3,
     pub pointing_at: Vector3,
+    // Which way is up for the camera before roll, world Y by default
+    pub up: Vector3,
+    // Degrees of rotation around the view direction
+    pub roll: f32,
+    // Projection being switched to, see `projection_blend`
+    pub projection: Projection,
+    // Height of the visible area in world units when orthographic
+    pub ortho_height: f32,
+    // 0 = fully perspective, 1 = fully orthographic, in between while switching
+    pub projection_blend: f32,
+    // Blend covered per second when switching projections
+    pub projection_transition_speed: f32,
+    // View depth range that gets drawn, shared by projection, culling and
+    // the depth buffer encoding
+    pub near_plane: f32,
+    pub far_plane: f32,
 }
 
 impl Camera {
@@ -12,16 +35,163 @@ impl Camera {
             fov: 45.0,
             pos: Vector3::new(7.0, 5.0, 8.0),
             pointing_at: Vector3::new(0.0, 0.0, 0.0),
+            up: Vector3::new(0.0, 1.0, 0.0),
+            roll: 0.0,
+            projection: Projection::Perspective,
+            ortho_height: 10.0,
+            projection_blend: 0.0,
+            projection_transition_speed: 3.0,
+            near_plane: 0.01,
+            far_plane: 60.0,
+        }
+    }
+
+    // Forward, right and up unit vectors of the view. Everything that needs
+    // the camera's orientation goes through here. When looking along `up`
+    // the cross product collapses, so a second reference axis takes over
+    // instead of letting the basis (and the whole scene) degenerate to zero.
+    pub fn basis(&self) -> (Vector3, Vector3, Vector3) {
+        let mut forward = (self.pointing_at - self.pos).normalize_v();
+        if forward.length() == 0.0 {
+            forward = Vector3::new(0.0, 0.0, -1.0);
+        }
+
+        let mut reference = self.up.normalize_v();
+        if reference.length() == 0.0 {
+            reference = Vector3::new(0.0, 1.0, 0.0);
+        }
+        let mut right = forward.cross(&reference);
+        if right.length() < 0.001 {
+            // Looking straight along up: use whichever world axis is least
+            // parallel to the view to pick a right vector
+            let fallback = if forward.z.abs() < 0.9 {
+                Vector3::new(0.0, 0.0, -1.0)
+            } else {
+                Vector3::new(1.0, 0.0, 0.0)
+            };
+            right = forward.cross(&fallback);
+        }
+        let right = right.normalize_v();
+        let up = right.cross(&forward);
+
+        if self.roll == 0.0 {
+            return (forward, right, up);
         }
+        let (sin, cos) = self.roll.to_radians().sin_cos();
+        (forward, right * cos + up * sin, up * cos - right * sin)
     }
+
+    // Starts a smooth switch towards `projection`, finished by `update`
+    pub fn set_projection(&mut self, projection: Projection) {
+        if projection == Projection::Orthographic && self.projection == Projection::Perspective {
+            self.match_ortho_to_target();
+        }
+        self.projection = projection;
+    }
+
+    pub fn toggle_projection(&mut self) {
+        self.set_projection(match self.projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let goal = match self.projection {
+            Projection::Perspective => 0.0,
+            Projection::Orthographic => 1.0,
+        };
+        let step = self.projection_transition_speed * dt;
+        if self.projection_transition_speed <= 0.0 || (goal - self.projection_blend).abs() <= step {
+            self.projection_blend = goal;
+        } else {
+            self.projection_blend += step * (goal - self.projection_blend).signum();
+        }
+    }
+
+    // Sizes the orthographic view to what perspective shows at the target,
+    // so switching keeps the target the same size on screen
+    pub fn match_ortho_to_target(&mut self) {
+        let distance = (self.pointing_at - self.pos).length();
+        // fov is horizontal, the default 4:3 screen turns it into a height
+        let aspect = SCREEN_WIDTH as f32 / SCREEN_HEIGHT as f32;
+        self.ortho_height = 2.0 * (self.fov.to_radians() / 2.0).tan() * distance / aspect;
+    }
+
+    // Half width and height of the visible area, in camera space, at a view
+    // depth. Dividing camera x/y by them gives normalized device coordinates.
+    pub fn view_half_extents(&self, camera_z: f32, aspect_ratio: f32) -> (f32, f32) {
+        let tan_half_fov = (self.fov.to_radians() / 2.0).tan();
+        let persp_w = tan_half_fov * camera_z;
+        let persp_h = persp_w / aspect_ratio;
+        let ortho_h = self.ortho_height / 2.0;
+        let ortho_w = ortho_h * aspect_ratio;
+
+        // Ease in and out so the switch doesn't start or stop abruptly
+        let b = self.projection_blend.clamp(0.0, 1.0);
+        let t = b * b * (3.0 - 2.0 * b);
+        (persp_w + (ortho_w - persp_w) * t, persp_h + (ortho_h - persp_h) * t)
+    }
+
+    // Ray through a screen pixel as seen through the screen's current viewport,
+    // the inverse of the projection done in Triangle::with_applied_perspective.
+    // Starts at the camera when perspective and on the camera plane when orthographic.
+    #[allow(dead_code)]
+    pub fn screen_point_to_ray(&self, x: f32, y: f32, screen: &Screen) -> Ray {
+        let viewport = screen.viewport();
+        let (width, height) = (viewport.size.x.max(1.0), viewport.size.y.max(1.0));
+        let aspect_ratio = width / height;
+        let ndc_x = (x - viewport.pos.x) / width * 2.0 - 1.0;
+        let ndc_y = 1.0 - (y - viewport.pos.y) / height * 2.0;
+
+        // Half extents are a + b * depth, see view_half_extents
+        let (w0, h0) = self.view_half_extents(0.0, aspect_ratio);
+        let (w1, h1) = self.view_half_extents(1.0, aspect_ratio);
+        let (forward, right, up) = self.basis();
+
+        let origin = self.pos + right * (ndc_x * w0) + up * (ndc_y * h0);
+        let dir = forward + right * (ndc_x * (w1 - w0)) + up * (ndc_y * (h1 - h0));
+        Ray::new(origin, dir)
+    }
+
+    // Classic 2:1 game isometric isn't true isometric, see `dimetric_view`
+    #[allow(dead_code)]
+    pub fn isometric_view(&mut self, target: Vector3, distance: f32) {
+        // Equal foreshortening on all three axes: 45 degrees around, ~35.26 down
+        let pitch = (1.0 / 2.0f32.sqrt()).atan();
+        self.axonometric_view(target, distance, 45.0f32.to_radians(), pitch);
+    }
+
+    #[allow(dead_code)]
+    pub fn dimetric_view(&mut self, target: Vector3, distance: f32) {
+        // 2:1 pixel-art slope, ~26.57 degrees down
+        self.axonometric_view(target, distance, 45.0f32.to_radians(), 0.5f32.atan());
+    }
+
+    fn axonometric_view(&mut self, target: Vector3, distance: f32, yaw: f32, pitch: f32) {
+        let offset = Vector3::new(
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+            pitch.cos() * yaw.cos(),
+        );
+        self.pointing_at = target;
+        self.pos = target + offset * distance;
+        self.set_projection(Projection::Orthographic);
+    }
+
+    #[allow(dead_code)]
     pub fn zoom(&mut self, zoom_amount: f32) {
         // Get direction vector from camera to target
-        let direction = (self.pointing_at - self.pos).normalize_v();
+        let to_target = self.pointing_at - self.pos;
+        let direction = to_target.normalize_v();
         
         // Move camera position along direction vector
-        // Positive zoom_amount moves camera closer to target
-        self.pos = self.pos + direction * zoom_amount;
+        // Positive zoom_amount moves camera closer to target, but never onto or past it
+        const MIN_DISTANCE: f32 = 0.1;
+        let zoom_amount = zoom_amount.min(to_target.length() - MIN_DISTANCE);
+        self.pos += direction * zoom_amount;
     }        
+    #[allow(dead_code)]
     pub fn drag_move(&mut self, initial_screen_pos: &Vector2, current_screen_pos: &Vector2, screen_res: &Vector2) {
         // Calculate drag delta in screen coordinates
         let drag_delta = *current_screen_pos - *initial_screen_pos;
@@ -30,50 +200,14 @@ impl Camera {
         let movement_scale = 0.03 * (screen_res.x.max(screen_res.y) / 1000.0);
         
         // Get camera direction vectors
-        let forward = (self.pointing_at - self.pos).normalize_v();
-        let right = forward.cross(&Vector3::new(0.0, 1.0, 0.0)).normalize_v();
-        let up = right.cross(&forward);
+        let (_forward, right, up) = self.basis();
 
         // Calculate movement vector based on drag direction
         let movement = right * (-drag_delta.x * movement_scale) + 
                     up * (drag_delta.y * movement_scale);
 
         // Update camera position while maintaining look-at point
-        self.pos = self.pos + movement;
-        self.pointing_at = self.pointing_at + movement;
+        self.pos += movement;
+        self.pointing_at += movement;
     }
-    /*pub fn first_person_look(&mut self, mouse_delta: &Vector2, screen_res: &Vector2) {
-        // Simple first person camera control - rotate around current position based on mouse movement
-        let base_sensitivity = -0.003; // Base sensitivity value
-        let sensitivity = base_sensitivity * (screen_res.x.max(screen_res.y) / 1000.0); // Scale by screen resolution
-        
-        // Calculate rotation angles from mouse movement
-        let yaw = mouse_delta.x * sensitivity;
-        let pitch = -mouse_delta.y * sensitivity;
-        
-        // Get current direction vector
-        let mut direction = self.pointing_at - self.pos;
-        
-        // Rotate direction around Y axis for yaw
-        let cos_yaw = yaw.cos();
-        let sin_yaw = yaw.sin();
-        direction = Vector3::new(
-            direction.x * cos_yaw - direction.z * sin_yaw,
-            direction.y,
-            direction.x * sin_yaw + direction.z * cos_yaw
-        );
-        
-        // Rotate direction around right vector for pitch
-        //let right = direction.cross(&Vector3::new(0.0, 1.0, 0.0)).normalize_v();
-        let cos_pitch = pitch.cos();
-        let sin_pitch = pitch.sin();
-        direction = Vector3::new(
-            direction.x * cos_pitch + direction.y * sin_pitch,
-            -direction.x * sin_pitch + direction.y * cos_pitch,
-            direction.z
-        ).normalize_v();
-        
-        // Update pointing_at based on rotated direction
-        self.pointing_at = self.pos + direction;
-    }*/
 }
@@ -0,0 +1,349 @@
+use std::io::{Error, ErrorKind};
+
+use crate::{camera::Camera, game_clock::GameClock, vec3::Vector3};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathInterpolation {
+    Linear,
+    // Passes through every keyframe
+    CatmullRom,
+    // Cubic Bezier segments with handles pointing along the neighbouring
+    // keyframes, scaled by the path's tension
+    Bezier,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease_in",
+            Easing::EaseOut => "ease_out",
+            Easing::EaseInOut => "ease_in_out",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Easing::Linear),
+            "ease_in" => Some(Easing::EaseIn),
+            "ease_out" => Some(Easing::EaseOut),
+            "ease_in_out" => Some(Easing::EaseInOut),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CameraKeyframe {
+    // Seconds from the start of the path
+    pub time: f32,
+    pub pos: Vector3,
+    pub target: Vector3,
+    pub fov: f32,
+    pub roll: f32,
+    // Timing of the segment that starts at this keyframe
+    pub easing: Easing,
+}
+
+impl CameraKeyframe {
+    pub fn new(time: f32, pos: Vector3, target: Vector3, fov: f32, roll: f32) -> Self {
+        CameraKeyframe {
+            time,
+            pos,
+            target,
+            fov,
+            roll,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+    pub interpolation: PathInterpolation,
+    // Handle length for Bezier paths, relative to the neighbour distance
+    pub tension: f32,
+    pub looping: bool,
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (-p0 + p2) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t3)
+}
+
+fn bezier(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let u = 1.0 - t;
+    u * u * u * p0 + 3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t * p3
+}
+
+impl CameraPath {
+    pub fn new(interpolation: PathInterpolation) -> Self {
+        CameraPath {
+            keyframes: vec![],
+            interpolation,
+            tension: 1.0 / 3.0,
+            looping: false,
+        }
+    }
+
+    // Keyframes stay sorted by time whatever order they are added in
+    pub fn add_keyframe(&mut self, keyframe: CameraKeyframe) {
+        let index = self.keyframes.partition_point(|k| k.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    // Keyframe by index, clamped to the ends or wrapped when looping
+    fn key(&self, index: isize) -> &CameraKeyframe {
+        let len = self.keyframes.len() as isize;
+        let index = if self.looping {
+            index.rem_euclid(len)
+        } else {
+            index.clamp(0, len - 1)
+        };
+        &self.keyframes[index as usize]
+    }
+
+    // Camera pose at a time along the path, None for an empty path
+    pub fn sample(&self, time: f32) -> Option<CameraKeyframe> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        let duration = last.time - first.time;
+        if self.keyframes.len() == 1 || duration <= 0.0 {
+            return Some(*first);
+        }
+
+        let time = if self.looping {
+            first.time + (time - first.time).rem_euclid(duration)
+        } else {
+            time.clamp(first.time, last.time)
+        };
+
+        let segment = self
+            .keyframes
+            .windows(2)
+            .position(|w| time >= w[0].time && time <= w[1].time)
+            .unwrap_or(self.keyframes.len() - 2) as isize;
+        let k0 = self.key(segment - 1);
+        let k1 = self.key(segment);
+        let k2 = self.key(segment + 1);
+        let k3 = self.key(segment + 2);
+
+        let span = (k2.time - k1.time).max(0.0001);
+        let t = k1.easing.apply((time - k1.time) / span);
+
+        let curve = |a: f32, b: f32, c: f32, d: f32| -> f32 {
+            match self.interpolation {
+                PathInterpolation::Linear => b + (c - b) * t,
+                PathInterpolation::CatmullRom => catmull_rom(a, b, c, d, t),
+                PathInterpolation::Bezier => {
+                    let out_handle = b + (c - a) * 0.5 * self.tension;
+                    let in_handle = c - (d - b) * 0.5 * self.tension;
+                    bezier(b, out_handle, in_handle, c, t)
+                }
+            }
+        };
+        let curve_v = |a: &Vector3, b: &Vector3, c: &Vector3, d: &Vector3| -> Vector3 {
+            Vector3::new(
+                curve(a.x, b.x, c.x, d.x),
+                curve(a.y, b.y, c.y, d.y),
+                curve(a.z, b.z, c.z, d.z),
+            )
+        };
+
+        Some(CameraKeyframe {
+            time,
+            pos: curve_v(&k0.pos, &k1.pos, &k2.pos, &k3.pos),
+            target: curve_v(&k0.target, &k1.target, &k2.target, &k3.target),
+            fov: curve(k0.fov, k1.fov, k2.fov, k3.fov),
+            roll: curve(k0.roll, k1.roll, k2.roll, k3.roll),
+            easing: k1.easing,
+        })
+    }
+
+    pub fn apply(&self, time: f32, cam: &mut Camera) {
+        if let Some(pose) = self.sample(time) {
+            cam.pos = pose.pos;
+            cam.pointing_at = pose.target;
+            cam.fov = pose.fov;
+            cam.roll = pose.roll;
+        }
+    }
+
+    // Plain text, one keyframe per line:
+    //   interpolation catmull_rom
+    //   tension 0.333
+    //   loop false
+    //   key <time> <pos x y z> <target x y z> <fov> <roll> [easing]
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# rustsim camera path\n");
+        let interpolation = match self.interpolation {
+            PathInterpolation::Linear => "linear",
+            PathInterpolation::CatmullRom => "catmull_rom",
+            PathInterpolation::Bezier => "bezier",
+        };
+        text += &format!("interpolation {}\n", interpolation);
+        text += &format!("tension {}\n", self.tension);
+        text += &format!("loop {}\n", self.looping);
+        for k in &self.keyframes {
+            text += &format!(
+                "key {} {} {} {} {} {} {} {} {} {}\n",
+                k.time, k.pos.x, k.pos.y, k.pos.z, k.target.x, k.target.y, k.target.z,
+                k.fov, k.roll, k.easing.name()
+            );
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> std::io::Result<Self> {
+        let invalid = |line: usize, msg: &str| {
+            Error::new(ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+        };
+
+        let mut path = CameraPath::new(PathInterpolation::CatmullRom);
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts[0] {
+                "interpolation" => {
+                    path.interpolation = match parts.get(1) {
+                        Some(&"linear") => PathInterpolation::Linear,
+                        Some(&"catmull_rom") => PathInterpolation::CatmullRom,
+                        Some(&"bezier") => PathInterpolation::Bezier,
+                        _ => return Err(invalid(line_no, "unknown interpolation")),
+                    }
+                }
+                "tension" => {
+                    path.tension = parts
+                        .get(1)
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| invalid(line_no, "bad tension"))?;
+                }
+                "loop" => {
+                    path.looping = parts
+                        .get(1)
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| invalid(line_no, "expected true or false"))?;
+                }
+                "key" => {
+                    if parts.len() < 10 {
+                        return Err(invalid(line_no, "key needs time, position, target, fov and roll"));
+                    }
+                    let values: Vec<f32> = parts[1..10]
+                        .iter()
+                        .map(|s| s.parse::<f32>())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid(line_no, "bad number"))?;
+                    let easing = match parts.get(10) {
+                        Some(name) => Easing::from_name(name)
+                            .ok_or_else(|| invalid(line_no, "unknown easing"))?,
+                        None => Easing::Linear,
+                    };
+                    path.add_keyframe(
+                        CameraKeyframe::new(
+                            values[0],
+                            Vector3::new(values[1], values[2], values[3]),
+                            Vector3::new(values[4], values[5], values[6]),
+                            values[7],
+                            values[8],
+                        )
+                        .with_easing(easing),
+                    );
+                }
+                other => return Err(invalid(line_no, &format!("unknown entry '{}'", other))),
+            }
+        }
+        Ok(path)
+    }
+
+    #[allow(dead_code)]
+    pub fn save_to_file(&self, file_path: &str) -> std::io::Result<()> {
+        std::fs::write(file_path, self.to_text())
+    }
+
+    pub fn load_from_file(file_path: &str) -> std::io::Result<Self> {
+        Self::from_text(&std::fs::read_to_string(file_path)?)
+    }
+}
+
+// Plays a path against the game clock
+pub struct CameraPathPlayer {
+    pub path: CameraPath,
+    pub speed: f32,
+    start_time: Option<f32>,
+}
+
+impl CameraPathPlayer {
+    pub fn new(path: CameraPath) -> Self {
+        CameraPathPlayer {
+            path,
+            speed: 1.0,
+            start_time: None,
+        }
+    }
+
+    pub fn play(&mut self, clock: &GameClock) {
+        self.start_time = Some(clock.time);
+    }
+
+    pub fn stop(&mut self) {
+        self.start_time = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.start_time.is_some()
+    }
+
+    // Moves the camera along the path. Stops by itself at the end of a
+    // non-looping path and returns false once it is no longer playing.
+    pub fn update(&mut self, clock: &GameClock, cam: &mut Camera) -> bool {
+        let Some(start) = self.start_time else {
+            return false;
+        };
+        let first = self.path.keyframes().first().map_or(0.0, |k| k.time);
+        let time = first + (clock.time - start) * self.speed;
+        self.path.apply(time, cam);
+        if !self.path.looping && time >= self.path.duration() {
+            self.stop();
+        }
+        self.is_playing()
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{
+    camera::Camera, camera_shake::CameraShake, draw_list::DrawList, ray::Ray,
+    transform::Transform, vec3::Vector3,
+};
+
+// One step of a critically damped spring: reaches `target` in roughly
+// `smooth_time` seconds as fast as possible without overshooting. Uses the
+// usual polynomial approximation of exp(-omega * dt).
+pub fn smooth_damp(current: f32, target: f32, velocity: &mut f32, smooth_time: f32, dt: f32) -> f32 {
+    let omega = 2.0 / smooth_time.max(0.0001);
+    let x = omega * dt;
+    let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+    let change = current - target;
+    let temp = (*velocity + omega * change) * dt;
+    *velocity = (*velocity - omega * temp) * decay;
+    target + (change + temp) * decay
+}
+
+// `smooth_damp` on each axis, keeping its own velocity
+pub struct SpringVector3 {
+    pub value: Vector3,
+    pub velocity: Vector3,
+    pub smooth_time: f32,
+}
+
+impl SpringVector3 {
+    pub fn new(value: Vector3, smooth_time: f32) -> Self {
+        SpringVector3 {
+            value,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            smooth_time,
+        }
+    }
+
+    pub fn update(&mut self, target: &Vector3, dt: f32) -> Vector3 {
+        self.value = Vector3::new(
+            smooth_damp(self.value.x, target.x, &mut self.velocity.x, self.smooth_time, dt),
+            smooth_damp(self.value.y, target.y, &mut self.velocity.y, self.smooth_time, dt),
+            smooth_damp(self.value.z, target.z, &mut self.velocity.z, self.smooth_time, dt),
+        );
+        self.value
+    }
+
+    pub fn snap(&mut self, value: Vector3) {
+        self.value = value;
+        self.velocity = Vector3::new(0.0, 0.0, 0.0);
+    }
+}
+
+// Third-person camera on a spring arm behind a transform. The arm pulls in
+// straight away when scene geometry gets between the target and the camera
+// and eases back out once it's clear, so the view is never blocked.
+pub struct FollowRig {
+    // Point the camera looks at and the arm starts from, in the target's
+    // local space (x right, y up, z forward)
+    pub pivot_offset: Vector3,
+    // Radians the arm is raised above the target's back
+    pub arm_pitch: f32,
+    pub arm_length: f32,
+    pub min_arm_length: f32,
+    // Distance kept between the camera and whatever the arm hit
+    pub collision_margin: f32,
+    // Seconds for the arm to swing round and for the view to catch up
+    pub follow_smooth_time: f32,
+    pub look_smooth_time: f32,
+    // Seconds for the arm to grow back after a collision
+    pub extend_smooth_time: f32,
+    pub shake: CameraShake,
+
+    pivot: SpringVector3,
+    arm_dir: SpringVector3,
+    look: SpringVector3,
+    length: f32,
+    length_velocity: f32,
+}
+
+impl FollowRig {
+    pub fn new(target: &Transform) -> Self {
+        let mut rig = FollowRig {
+            pivot_offset: Vector3::new(0.0, 1.0, 0.0),
+            arm_pitch: 20.0f32.to_radians(),
+            arm_length: 5.0,
+            min_arm_length: 0.5,
+            collision_margin: 0.2,
+            follow_smooth_time: 0.3,
+            look_smooth_time: 0.1,
+            extend_smooth_time: 0.5,
+            shake: CameraShake::new(),
+            pivot: SpringVector3::new(Vector3::new(0.0, 0.0, 0.0), 0.0),
+            arm_dir: SpringVector3::new(Vector3::new(0.0, 0.0, 0.0), 0.0),
+            look: SpringVector3::new(Vector3::new(0.0, 0.0, 0.0), 0.0),
+            length: 0.0,
+            length_velocity: 0.0,
+        };
+        rig.snap(target);
+        rig
+    }
+
+    fn desired_arm_dir(&self, target: &Transform) -> Vector3 {
+        // Behind the target on the ground plane, then raised
+        let back = Vector3::new(-target.yaw.sin(), 0.0, target.yaw.cos());
+        back * self.arm_pitch.cos() + Vector3::new(0.0, self.arm_pitch.sin(), 0.0)
+    }
+
+    // Jumps straight to the resting position, e.g. after a teleport
+    pub fn snap(&mut self, target: &Transform) {
+        let pivot = target.transform_point(&self.pivot_offset);
+        self.pivot.snap(pivot);
+        self.arm_dir.snap(self.desired_arm_dir(target));
+        self.look.snap(pivot);
+        self.length = self.arm_length;
+        self.length_velocity = 0.0;
+    }
+
+    // `scene` is what the arm collides with, None to ignore geometry
+    pub fn update(&mut self, target: &Transform, scene: Option<&DrawList>, cam: &mut Camera, dt: f32) {
+        let goal_pivot = target.transform_point(&self.pivot_offset);
+        self.pivot.smooth_time = self.follow_smooth_time;
+        self.arm_dir.smooth_time = self.follow_smooth_time;
+        self.look.smooth_time = self.look_smooth_time;
+
+        let pivot = self.pivot.update(&goal_pivot, dt);
+        let mut arm_dir = self.arm_dir.update(&self.desired_arm_dir(target), dt).normalize_v();
+        if arm_dir.length() == 0.0 {
+            arm_dir = self.desired_arm_dir(target);
+        }
+
+        let mut wanted = self.arm_length;
+        if let Some(hit) = scene.and_then(|s| s.raycast(&Ray::new(pivot, arm_dir)))
+            && hit.distance < self.arm_length + self.collision_margin
+        {
+            wanted = hit.distance - self.collision_margin;
+        }
+        let wanted = wanted.max(self.min_arm_length);
+
+        if wanted < self.length {
+            self.length = wanted;
+            self.length_velocity = 0.0;
+        } else {
+            self.length = smooth_damp(self.length, wanted, &mut self.length_velocity, self.extend_smooth_time, dt);
+        }
+
+        cam.pos = pivot + arm_dir * self.length;
+        cam.pointing_at = self.look.update(&goal_pivot, dt);
+        cam.roll = 0.0;
+
+        self.shake.update(dt);
+        self.shake.apply(cam);
+    }
+}
+
+// Camera on a fixed spot that keeps a transform in view, turning towards
+// it with a critically damped spring instead of snapping
+pub struct LookAtRig {
+    pub position: Vector3,
+    // Point looked at, in the target's local space
+    pub look_offset: Vector3,
+    pub look_smooth_time: f32,
+    pub shake: CameraShake,
+    look: SpringVector3,
+}
+
+#[allow(dead_code)]
+impl LookAtRig {
+    pub fn new(position: Vector3, target: &Transform) -> Self {
+        let look_offset = Vector3::new(0.0, 0.5, 0.0);
+        LookAtRig {
+            position,
+            look_offset,
+            look_smooth_time: 0.2,
+            shake: CameraShake::new(),
+            look: SpringVector3::new(target.transform_point(&look_offset), 0.2),
+        }
+    }
+
+    pub fn update(&mut self, target: &Transform, cam: &mut Camera, dt: f32) {
+        self.look.smooth_time = self.look_smooth_time;
+        cam.pos = self.position;
+        cam.pointing_at = self.look.update(&target.transform_point(&self.look_offset), dt);
+        cam.roll = 0.0;
+
+        self.shake.update(dt);
+        self.shake.apply(cam);
+    }
+}
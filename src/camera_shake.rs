@@ -0,0 +1,66 @@
+use crate::camera::Camera;
+
+// Trauma-based shake: hits add trauma, which fades over time, and the shake
+// strength is trauma squared so small bumps stay subtle and big ones kick.
+// The offsets come from smooth noise rather than random jitter per frame.
+pub struct CameraShake {
+    // 0 to 1
+    pub trauma: f32,
+    // Trauma lost per second
+    pub decay: f32,
+    // World units at full trauma
+    pub max_offset: f32,
+    // Degrees at full trauma
+    pub max_roll: f32,
+    // How fast the shake wobbles
+    pub frequency: f32,
+    time: f32,
+}
+
+// Smooth noise in -1..1 out of a few incommensurate sines
+fn wobble(t: f32, seed: f32) -> f32 {
+    (t + seed).sin() * 0.5 + (t * 2.31 + seed * 1.7).sin() * 0.3 + (t * 4.79 + seed * 3.1).sin() * 0.2
+}
+
+impl CameraShake {
+    pub fn new() -> Self {
+        CameraShake {
+            trauma: 0.0,
+            decay: 0.8,
+            max_offset: 0.3,
+            max_roll: 6.0,
+            frequency: 25.0,
+            time: 0.0,
+        }
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+        self.trauma = (self.trauma - self.decay * dt).max(0.0);
+    }
+
+    // Shakes the camera as it is now, so call it after whatever placed the
+    // camera this tick, otherwise the offsets pile up
+    pub fn apply(&self, cam: &mut Camera) {
+        if self.trauma <= 0.0 {
+            return;
+        }
+        let shake = self.trauma * self.trauma;
+        let t = self.time * self.frequency;
+        let (_, right, up) = cam.basis();
+        let offset = right * (wobble(t, 0.0) * self.max_offset * shake)
+            + up * (wobble(t, 10.0) * self.max_offset * shake);
+        cam.pos += offset;
+        cam.pointing_at += offset;
+        cam.roll += wobble(t, 20.0) * self.max_roll * shake;
+    }
+
+    #[allow(dead_code)]
+    pub fn is_shaking(&self) -> bool {
+        self.trauma > 0.0
+    }
+}
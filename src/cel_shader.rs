@@ -0,0 +1,71 @@
+use crate::{color::Color, pixel_placement::PixelPlacement, pixel_shader::PixelShader, texture::Texture, triangle::Triangle, vec3::Vector3};
+
+// Toon shading: diffuse lighting snapped into a few flat bands. Pair with
+// `OutlinePass` for ink lines.
+pub struct CelShader {
+    // Direction the light travels in, world space
+    pub light_dir: Vector3,
+    pub bands: usize,
+    // Brightness of the darkest band, 0..1
+    pub ambient: f32,
+    // Optional 1D ramp, sampled left (dark) to right (lit) at each band's center
+    pub ramp: Option<Texture>,
+}
+
+impl CelShader {
+    #[allow(dead_code)]
+    pub fn new(light_dir: Vector3, bands: usize) -> Self {
+        CelShader {
+            light_dir: light_dir.normalize_v(),
+            bands: bands.max(1),
+            ambient: 0.3,
+            ramp: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_ramp(mut self, ramp: Texture) -> Self {
+        self.ramp = Some(ramp);
+        self
+    }
+
+    fn band_tint(&self, intensity: f32) -> (f32, f32, f32) {
+        let band = ((intensity * self.bands as f32) as usize).min(self.bands - 1);
+        let t = (band as f32 + 0.5) / self.bands as f32;
+
+        match &self.ramp {
+            Some(ramp) if ramp.width > 0 && ramp.height > 0 => {
+                let x = ((t * ramp.width as f32) as u32).min(ramp.width - 1);
+                let c = ramp.get_pixel(x, ramp.height / 2);
+                (c.r as f32 / 255.0, c.g as f32 / 255.0, c.b as f32 / 255.0)
+            }
+            _ => {
+                // Without a ramp, step evenly from ambient up to full brightness
+                let level = if self.bands == 1 {
+                    1.0
+                } else {
+                    band as f32 / (self.bands - 1) as f32
+                };
+                let v = self.ambient + (1.0 - self.ambient) * level;
+                (v, v, v)
+            }
+        }
+    }
+}
+
+impl PixelShader for CelShader {
+    fn process(&self, pp: &mut PixelPlacement, _triangle: &Triangle) {
+        if pp.color.a == 0 {
+            return;
+        }
+        let intensity = Vector3::dot(&pp.normal, &(self.light_dir * -1.0)).clamp(0.0, 1.0);
+        let (r, g, b) = self.band_tint(intensity);
+
+        pp.color = Color {
+            r: (pp.color.r as f32 * r) as u8,
+            g: (pp.color.g as f32 * g) as u8,
+            b: (pp.color.b as f32 * b) as u8,
+            a: pp.color.a,
+        }
+    }
+}
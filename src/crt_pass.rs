@@ -0,0 +1,166 @@
+use crate::{
+    post_process::{gaussian_blur, luminance, read_rgb, write_rgb, PostProcessPass, Rgb},
+    screen::{Screen, SCREEN_HEIGHT, SCREEN_PIXEL_COUNT, SCREEN_WIDTH},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CrtMask {
+    #[allow(dead_code)]
+    None,
+    // Staggered RGB triads, like a dot-mask tube
+    #[allow(dead_code)]
+    ShadowMask,
+    // Continuous vertical RGB stripes, like a Trinitron
+    ApertureGrille,
+}
+
+// Emulates a CRT display over the finished frame. All fields can be changed
+// between frames, e.g. through `PostProcessChain::get_mut`.
+pub struct CrtPass {
+    // How dark the gaps between scanlines get, 0..1
+    pub scanline_intensity: f32,
+    // Screen rows per scanline
+    pub scanline_period: usize,
+    pub mask: CrtMask,
+    // How much the inactive phosphors of the mask are dimmed, 0..1
+    pub mask_intensity: f32,
+    // Barrel distortion amount, 0 = flat glass
+    pub curvature: f32,
+    pub bloom_intensity: f32,
+    pub bloom_radius: usize,
+    // Fraction of the previous frame's glow that survives into this one, 0..1
+    pub persistence: f32,
+    // Amplitude of per-pixel static in 0-255 color steps
+    pub noise: f32,
+    previous: Vec<Rgb>,
+    frame: u32,
+}
+
+impl CrtPass {
+    pub fn new() -> Self {
+        CrtPass {
+            scanline_intensity: 0.4,
+            scanline_period: 2,
+            mask: CrtMask::ApertureGrille,
+            mask_intensity: 0.25,
+            curvature: 0.08,
+            bloom_intensity: 0.3,
+            bloom_radius: 4,
+            persistence: 0.3,
+            noise: 6.0,
+            previous: vec![],
+            frame: 0,
+        }
+    }
+
+    fn sample(src: &[Rgb], x: f32, y: f32) -> Rgb {
+        // Bilinear, since the distortion lands between pixels
+        let x = x.clamp(0.0, SCREEN_WIDTH as f32 - 1.0);
+        let y = y.clamp(0.0, SCREEN_HEIGHT as f32 - 1.0);
+        let x0 = x.floor() as usize;
+        let y0 = y.floor() as usize;
+        let x1 = (x0 + 1).min(SCREEN_WIDTH - 1);
+        let y1 = (y0 + 1).min(SCREEN_HEIGHT - 1);
+        let fx = x - x0 as f32;
+        let fy = y - y0 as f32;
+
+        let mut out = [0.0; 3];
+        for (ch, value) in out.iter_mut().enumerate() {
+            let top = src[y0 * SCREEN_WIDTH + x0][ch] * (1.0 - fx) + src[y0 * SCREEN_WIDTH + x1][ch] * fx;
+            let bottom = src[y1 * SCREEN_WIDTH + x0][ch] * (1.0 - fx) + src[y1 * SCREEN_WIDTH + x1][ch] * fx;
+            *value = top * (1.0 - fy) + bottom * fy;
+        }
+        out
+    }
+
+    fn mask_weights(&self, x: usize, y: usize) -> Rgb {
+        let dim = 1.0 - self.mask_intensity;
+        let phase = match self.mask {
+            CrtMask::None => return [1.0; 3],
+            CrtMask::ApertureGrille => x % 3,
+            // Every other pair of rows is shifted by half a triad
+            CrtMask::ShadowMask => (x + (y / 2 % 2) * 2) % 3,
+        };
+        let mut weights = [dim; 3];
+        weights[phase] = 1.0;
+        weights
+    }
+
+    fn noise_at(&self, x: usize, y: usize) -> f32 {
+        // Integer hash of position and frame, mapped to -0.5..0.5
+        let mut h = (x as u32).wrapping_mul(0x8da6b343)
+            ^ (y as u32).wrapping_mul(0xd8163841)
+            ^ self.frame.wrapping_mul(0xcb1ab31f);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0x5bd1e995);
+        h ^= h >> 15;
+        (h & 0xffff) as f32 / 65535.0 - 0.5
+    }
+}
+
+impl PostProcessPass for CrtPass {
+    fn process(&mut self, screen: &mut Screen) {
+        let mut src = read_rgb(screen);
+        self.frame = self.frame.wrapping_add(1);
+
+        // Phosphor persistence: last frame's light fades instead of vanishing
+        if self.previous.len() == SCREEN_PIXEL_COUNT && self.persistence > 0.0 {
+            for (current, old) in src.iter_mut().zip(&self.previous) {
+                for ch in 0..3 {
+                    current[ch] = current[ch].max(old[ch] * self.persistence);
+                }
+            }
+        }
+        self.previous = src.clone();
+
+        // Phosphor bloom: bright areas bleed into their surroundings
+        if self.bloom_intensity > 0.0 {
+            let bright: Vec<Rgb> = src
+                .iter()
+                .map(|rgb| if luminance(rgb) > 128.0 { *rgb } else { [0.0; 3] })
+                .collect();
+            let glow = gaussian_blur(&bright, self.bloom_radius, self.bloom_radius as f32 / 2.0);
+            for (s, g) in src.iter_mut().zip(&glow) {
+                for ch in 0..3 {
+                    s[ch] += g[ch] * self.bloom_intensity;
+                }
+            }
+        }
+
+        let half_w = SCREEN_WIDTH as f32 / 2.0;
+        let half_h = SCREEN_HEIGHT as f32 / 2.0;
+        let period = self.scanline_period.max(1) as f32;
+        let mut out = vec![[0.0; 3]; SCREEN_PIXEL_COUNT];
+
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                // Barrel distortion in -1..1 space around the screen center
+                let cx = (x as f32 + 0.5 - half_w) / half_w;
+                let cy = (y as f32 + 0.5 - half_h) / half_h;
+                let r2 = cx * cx + cy * cy;
+                let k = 1.0 + self.curvature * r2;
+                let dx = cx * k;
+                let dy = cy * k;
+                if dx.abs() > 1.0 || dy.abs() > 1.0 {
+                    // Outside the curved glass stays black
+                    continue;
+                }
+                let mut rgb = Self::sample(&src, dx * half_w + half_w - 0.5, dy * half_h + half_h - 0.5);
+
+                // Scanlines follow the distorted row so they curve with the glass
+                let row = (dy * half_h + half_h) / period;
+                let beam = 0.5 + 0.5 * (row * std::f32::consts::TAU).cos();
+                let scanline = 1.0 - self.scanline_intensity * (1.0 - beam);
+
+                let mask = self.mask_weights(x, y);
+                let noise = self.noise_at(x, y) * self.noise;
+                for ch in 0..3 {
+                    rgb[ch] = rgb[ch] * scanline * mask[ch] + noise;
+                }
+                out[y * SCREEN_WIDTH + x] = rgb;
+            }
+        }
+
+        write_rgb(screen, &out);
+    }
+}
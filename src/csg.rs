@@ -0,0 +1,485 @@
+use crate::{
+    color::Color, frustum::Plane, mesh::Mesh, triangle::Triangle, vec2::Vector2, vec3::Vector3, vertex::Vertex,
+};
+
+// Boolean operations on closed meshes, with BSP trees as in Evan Wallace's
+// csg.js. Inputs need to be closed and wound counter-clockwise seen from
+// outside, like the `TriangleGen` shapes; results are too.
+#[derive(Clone)]
+pub struct Csg {
+    polygons: Vec<CsgPolygon>,
+}
+
+// Points closer than this to a plane count as on it
+const EPSILON: f32 = 1e-5;
+
+impl Csg {
+    pub fn from_triangles(triangles: &[Triangle]) -> Self {
+        Csg {
+            polygons: triangles
+                .iter()
+                .filter_map(|t| CsgPolygon::new(vec![t.v1.clone(), t.v2.clone(), t.v3.clone()]))
+                .collect(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from_mesh(mesh: &Mesh) -> Self {
+        Self::from_triangles(&mesh.to_triangles())
+    }
+
+    // Everything in either
+    #[allow(dead_code)]
+    pub fn union(&self, other: &Csg) -> Csg {
+        let mut a = BspNode::new(self.polygons.clone());
+        let mut b = BspNode::new(other.polygons.clone());
+        a.clip_to(&b);
+        b.clip_to(&a);
+        // Drops faces of b that lie on faces of a and point the same way
+        b.invert();
+        b.clip_to(&a);
+        b.invert();
+        a.build(b.all_polygons());
+        Csg {
+            polygons: a.all_polygons(),
+        }
+    }
+
+    // What's in `self` but not in `other`
+    pub fn subtract(&self, other: &Csg) -> Csg {
+        let mut a = BspNode::new(self.polygons.clone());
+        let mut b = BspNode::new(other.polygons.clone());
+        a.invert();
+        a.clip_to(&b);
+        b.clip_to(&a);
+        b.invert();
+        b.clip_to(&a);
+        b.invert();
+        a.build(b.all_polygons());
+        a.invert();
+        Csg {
+            polygons: a.all_polygons(),
+        }
+    }
+
+    // What's in both
+    #[allow(dead_code)]
+    pub fn intersect(&self, other: &Csg) -> Csg {
+        let mut a = BspNode::new(self.polygons.clone());
+        let mut b = BspNode::new(other.polygons.clone());
+        a.invert();
+        b.clip_to(&a);
+        b.invert();
+        a.clip_to(&b);
+        b.clip_to(&a);
+        a.build(b.all_polygons());
+        a.invert();
+        Csg {
+            polygons: a.all_polygons(),
+        }
+    }
+
+    // Inside out: the solid becomes everything around it
+    #[allow(dead_code)]
+    pub fn inverse(&self) -> Csg {
+        let mut polygons = self.polygons.clone();
+        for polygon in &mut polygons {
+            polygon.flip();
+        }
+        Csg { polygons }
+    }
+
+    #[allow(dead_code)]
+    pub fn polygon_count(&self) -> usize {
+        self.polygons.len()
+    }
+
+    // Splitting leaves convex polygons, these are fanned into triangles
+    pub fn to_triangles(&self) -> Vec<Triangle> {
+        let mut triangles = vec![];
+        for polygon in &self.polygons {
+            let v = &polygon.vertices;
+            for k in 1..v.len() - 1 {
+                triangles.push(Triangle::new(v[0].clone(), v[k].clone(), v[k + 1].clone()));
+            }
+        }
+        triangles
+    }
+
+    #[allow(dead_code)]
+    pub fn to_mesh(&self) -> Mesh {
+        Mesh::from_triangles(&self.to_triangles())
+    }
+}
+
+// Convex and flat, at least three vertices
+#[derive(Clone)]
+struct CsgPolygon {
+    vertices: Vec<Vertex>,
+    plane: Plane,
+}
+
+impl CsgPolygon {
+    // None for polygons without area, which have no plane to sort by
+    fn new(vertices: Vec<Vertex>) -> Option<Self> {
+        let (a, b, c) = (&vertices[0].pos, &vertices[1].pos, &vertices[2].pos);
+        let normal = (*b - *a).cross(&(*c - *a));
+        if normal.length() < 1e-12 {
+            return None;
+        }
+        let plane = Plane::from_point_normal(a, &normal.normalize_v());
+        Some(CsgPolygon { vertices, plane })
+    }
+
+    fn flip(&mut self) {
+        self.vertices.reverse();
+        for vertex in &mut self.vertices {
+            vertex.normal = vertex.normal * -1.0;
+        }
+        self.plane = flipped(&self.plane);
+    }
+}
+
+fn flipped(plane: &Plane) -> Plane {
+    Plane {
+        normal: plane.normal * -1.0,
+        d: -plane.d,
+    }
+}
+
+const COPLANAR: u8 = 0;
+const FRONT: u8 = 1;
+const BACK: u8 = 2;
+const SPANNING: u8 = 3;
+
+// Where a polygon ends up relative to a plane. Coplanar ones are sorted by
+// which way they face.
+#[derive(Default)]
+struct Split {
+    coplanar_front: Vec<CsgPolygon>,
+    coplanar_back: Vec<CsgPolygon>,
+    front: Vec<CsgPolygon>,
+    back: Vec<CsgPolygon>,
+}
+
+fn split_polygon(plane: &Plane, polygon: CsgPolygon, split: &mut Split) {
+    let sides: Vec<u8> = polygon
+        .vertices
+        .iter()
+        .map(|v| {
+            let t = plane.distance(&v.pos);
+            if t < -EPSILON {
+                BACK
+            } else if t > EPSILON {
+                FRONT
+            } else {
+                COPLANAR
+            }
+        })
+        .collect();
+    let polygon_side = sides.iter().fold(COPLANAR, |acc, &s| acc | s);
+
+    match polygon_side {
+        COPLANAR => {
+            if Vector3::dot(&plane.normal, &polygon.plane.normal) > 0.0 {
+                split.coplanar_front.push(polygon);
+            } else {
+                split.coplanar_back.push(polygon);
+            }
+        }
+        FRONT => split.front.push(polygon),
+        BACK => split.back.push(polygon),
+        _ => {
+            let n = polygon.vertices.len();
+            let (mut front, mut back) = (vec![], vec![]);
+            for i in 0..n {
+                let j = (i + 1) % n;
+                let (vi, vj) = (&polygon.vertices[i], &polygon.vertices[j]);
+                let (si, sj) = (sides[i], sides[j]);
+                if si != BACK {
+                    front.push(vi.clone());
+                }
+                if si != FRONT {
+                    back.push(vi.clone());
+                }
+                if si | sj == SPANNING {
+                    let di = plane.distance(&vi.pos);
+                    let t = di / (di - plane.distance(&vj.pos));
+                    let v = lerp_vertex(vi, vj, t);
+                    front.push(v.clone());
+                    back.push(v);
+                }
+            }
+            // Pieces keep the original plane, recomputing it from slivers
+            // would only add error
+            if front.len() >= 3 {
+                split.front.push(CsgPolygon {
+                    vertices: front,
+                    plane: polygon.plane,
+                });
+            }
+            if back.len() >= 3 {
+                split.back.push(CsgPolygon {
+                    vertices: back,
+                    plane: polygon.plane,
+                });
+            }
+        }
+    }
+}
+
+fn lerp_vertex(a: &Vertex, b: &Vertex, t: f32) -> Vertex {
+    let mix = |x: f32, y: f32| x + (y - x) * t;
+    let mix_u8 = |x: u8, y: u8| mix(x as f32, y as f32).round() as u8;
+    let mut v = Vertex::new(
+        &(a.pos + (b.pos - a.pos) * t),
+        &Vector2::new(
+            mix(a.texture_coord.x, b.texture_coord.x),
+            mix(a.texture_coord.y, b.texture_coord.y),
+        ),
+        &Color::new(
+            mix_u8(a.color.r, b.color.r),
+            mix_u8(a.color.g, b.color.g),
+            mix_u8(a.color.b, b.color.b),
+            mix_u8(a.color.a, b.color.a),
+        ),
+    );
+    v.normal = (a.normal + (b.normal - a.normal) * t).normalize_v();
+    v.tangent = (a.tangent + (b.tangent - a.tangent) * t).normalize_v();
+    v.tangent_sign = a.tangent_sign;
+    v
+}
+
+// Polygons lying in `plane`, with what's in front of and behind it in the
+// subtrees. The front side of a closed mesh's tree is outside.
+struct BspNode {
+    plane: Option<Plane>,
+    front: Option<Box<BspNode>>,
+    back: Option<Box<BspNode>>,
+    polygons: Vec<CsgPolygon>,
+}
+
+impl BspNode {
+    fn new(polygons: Vec<CsgPolygon>) -> Self {
+        let mut node = BspNode {
+            plane: None,
+            front: None,
+            back: None,
+            polygons: vec![],
+        };
+        node.build(polygons);
+        node
+    }
+
+    // Solid and empty space swap
+    fn invert(&mut self) {
+        for polygon in &mut self.polygons {
+            polygon.flip();
+        }
+        self.plane = self.plane.as_ref().map(flipped);
+        if let Some(front) = &mut self.front {
+            front.invert();
+        }
+        if let Some(back) = &mut self.back {
+            back.invert();
+        }
+        std::mem::swap(&mut self.front, &mut self.back);
+    }
+
+    // The parts of `polygons` outside this tree's solid
+    fn clip_polygons(&self, polygons: Vec<CsgPolygon>) -> Vec<CsgPolygon> {
+        let Some(plane) = &self.plane else {
+            return polygons;
+        };
+        let mut split = Split::default();
+        for polygon in polygons {
+            split_polygon(plane, polygon, &mut split);
+        }
+        let mut front = split.front;
+        front.append(&mut split.coplanar_front);
+        let mut back = split.back;
+        back.append(&mut split.coplanar_back);
+
+        let mut kept = match &self.front {
+            Some(node) => node.clip_polygons(front),
+            None => front,
+        };
+        // Behind a leaf is inside the solid, so those go
+        if let Some(node) = &self.back {
+            kept.append(&mut node.clip_polygons(back));
+        }
+        kept
+    }
+
+    // Removes whatever of this tree is inside `other`
+    fn clip_to(&mut self, other: &BspNode) {
+        self.polygons = other.clip_polygons(std::mem::take(&mut self.polygons));
+        if let Some(front) = &mut self.front {
+            front.clip_to(other);
+        }
+        if let Some(back) = &mut self.back {
+            back.clip_to(other);
+        }
+    }
+
+    fn all_polygons(&self) -> Vec<CsgPolygon> {
+        let mut polygons = self.polygons.clone();
+        if let Some(front) = &self.front {
+            polygons.append(&mut front.all_polygons());
+        }
+        if let Some(back) = &self.back {
+            polygons.append(&mut back.all_polygons());
+        }
+        polygons
+    }
+
+    // Adds polygons to the tree, splitting them across existing planes.
+    // Each node's plane is the first polygon that reaches it.
+    fn build(&mut self, polygons: Vec<CsgPolygon>) {
+        if polygons.is_empty() {
+            return;
+        }
+        let plane = *self.plane.get_or_insert(polygons[0].plane);
+        let mut split = Split::default();
+        for polygon in polygons {
+            split_polygon(&plane, polygon, &mut split);
+        }
+        self.polygons.append(&mut split.coplanar_front);
+        self.polygons.append(&mut split.coplanar_back);
+        if !split.front.is_empty() {
+            self.front
+                .get_or_insert_with(|| Box::new(BspNode::new(vec![])))
+                .build(split.front);
+        }
+        if !split.back.is_empty() {
+            self.back
+                .get_or_insert_with(|| Box::new(BspNode::new(vec![])))
+                .build(split.back);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::triangle_gen::TriangleGen;
+
+    const WHITE: Color = Color { r: 255, g: 255, b: 255, a: 255 };
+
+    fn cube(center: Vector3, size: Vector3) -> Csg {
+        Csg::from_triangles(&TriangleGen::create_box(&center, &size, &WHITE))
+    }
+
+    fn inside_box(p: &Vector3, center: Vector3, size: Vector3) -> bool {
+        let d = *p - center;
+        d.x.abs() < size.x / 2.0 && d.y.abs() < size.y / 2.0 && d.z.abs() < size.z / 2.0
+    }
+
+    // Divergence theorem: the tetrahedra from the origin to each face sum to
+    // the volume when every face points out
+    fn volume(csg: &Csg) -> f32 {
+        csg.to_triangles()
+            .iter()
+            .map(|t| Vector3::dot(&t.v1.pos, &t.v2.pos.cross(&t.v3.pos)) / 6.0)
+            .sum()
+    }
+
+    // Just off each face is outside the solid, just behind it is inside
+    fn assert_facing_out(csg: &Csg, inside: impl Fn(&Vector3) -> bool) {
+        for t in csg.to_triangles() {
+            if (t.v2.pos - t.v1.pos).cross(&(t.v3.pos - t.v1.pos)).length() < 1e-6 {
+                continue;
+            }
+            let centroid = (t.v1.pos + t.v2.pos + t.v3.pos) * (1.0 / 3.0);
+            let offset = t.face_normal() * 1e-3;
+            assert!(!inside(&(centroid + offset)), "face at {:?} points in", centroid);
+            assert!(inside(&(centroid - offset)), "face at {:?} has nothing behind it", centroid);
+        }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn boxes_are_wound_outward() {
+        let (center, size) = (Vector3::new(1.0, 2.0, 3.0), Vector3::new(1.0, 2.0, 3.0));
+        let a = cube(center, size);
+        assert!(close(volume(&a), 6.0));
+        assert_facing_out(&a, |p| inside_box(p, center, size));
+        assert!(close(volume(&a.inverse()), -6.0));
+    }
+
+    #[test]
+    fn disjoint_boxes() {
+        let size = Vector3::new(1.0, 1.0, 1.0);
+        let (ca, cb) = (Vector3::new(0.0, 0.0, 0.0), Vector3::new(3.0, 0.0, 0.0));
+        let (a, b) = (cube(ca, size), cube(cb, size));
+
+        let union = a.union(&b);
+        // Nothing to split, so both come through whole
+        assert_eq!(union.polygon_count(), a.polygon_count() + b.polygon_count());
+        assert!(close(volume(&union), 2.0));
+        assert_facing_out(&union, |p| inside_box(p, ca, size) || inside_box(p, cb, size));
+
+        let difference = a.subtract(&b);
+        assert_eq!(difference.polygon_count(), a.polygon_count());
+        assert!(close(volume(&difference), 1.0));
+        assert_facing_out(&difference, |p| inside_box(p, ca, size));
+
+        assert_eq!(a.intersect(&b).polygon_count(), 0);
+    }
+
+    #[test]
+    fn boxes_touching_on_a_face() {
+        let size = Vector3::new(2.0, 2.0, 2.0);
+        let (ca, cb) = (Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0));
+        let (a, b) = (cube(ca, size), cube(cb, size));
+        let on_shared_face = |csg: &Csg| {
+            csg.to_triangles()
+                .iter()
+                .any(|t| [&t.v1, &t.v2, &t.v3].iter().all(|v| close(v.pos.x, 1.0)))
+        };
+
+        let union = a.union(&b);
+        assert!(close(volume(&union), 16.0));
+        assert!(!on_shared_face(&union));
+        assert_facing_out(&union, |p| inside_box(p, ca, size) || inside_box(p, cb, size));
+
+        let difference = a.subtract(&b);
+        assert!(close(volume(&difference), 8.0));
+        assert_facing_out(&difference, |p| inside_box(p, ca, size));
+
+        assert!(close(volume(&a.intersect(&b)), 0.0));
+    }
+
+    #[test]
+    fn overlapping_boxes() {
+        let size = Vector3::new(2.0, 2.0, 2.0);
+        let (ca, cb) = (Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
+        let (a, b) = (cube(ca, size), cube(cb, size));
+
+        let union = a.union(&b);
+        assert!(close(volume(&union), 15.0));
+        assert_facing_out(&union, |p| inside_box(p, ca, size) || inside_box(p, cb, size));
+
+        let intersection = a.intersect(&b);
+        assert!(close(volume(&intersection), 1.0));
+        assert_facing_out(&intersection, |p| inside_box(p, ca, size) && inside_box(p, cb, size));
+
+        let difference = a.subtract(&b);
+        assert!(close(volume(&difference), 7.0));
+        assert_facing_out(&difference, |p| inside_box(p, ca, size) && !inside_box(p, cb, size));
+    }
+
+    #[test]
+    fn subtract_a_hole_right_through() {
+        let (ca, size_a) = (Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 2.0, 2.0));
+        let (cb, size_b) = (Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 4.0));
+        let holed = cube(ca, size_a).subtract(&cube(cb, size_b));
+        assert!(close(volume(&holed), 6.0));
+        assert_facing_out(&holed, |p| inside_box(p, ca, size_a) && !inside_box(p, cb, size_b));
+        // The hole's walls are there, facing into it
+        assert!(holed.to_triangles().iter().any(|t| close(t.v1.pos.x, 0.5) && close(t.face_normal().x, -1.0)));
+    }
+}
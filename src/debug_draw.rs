@@ -0,0 +1,341 @@
+use std::cell::{Cell, RefCell};
+
+use crate::{
+    bounds::{Aabb, Obb},
+    camera::Camera,
+    color::Color,
+    dummy_passthru_shader::DummyPassthruShader,
+    fog::Fog,
+    screen::Screen,
+    texture::Texture,
+    transform::Transform,
+    triangle::{Triangle, VertexProjector},
+    vec2::Vector2,
+    vec3::Vector3,
+    vertex::Vertex,
+    viewport::Viewport,
+};
+
+// Lines queued from anywhere and drawn at render time, either into the
+// game's `debug_views` once the frame is rendered or pane by pane where the
+// game calls `draw`. They're forgotten once the frame is shown, so shapes
+// have to be queued every tick to stay up. Widths are in pixels whatever the
+// distance. Everything is depth tested against the scene but left out of fog.
+pub struct DebugDraw;
+
+#[derive(Clone, Copy)]
+struct DebugLine {
+    start: Vector3,
+    end: Vector3,
+    color: Color,
+    width: f32,
+}
+
+thread_local! {
+    static LINES: RefCell<Vec<DebugLine>> = const { RefCell::new(Vec::new()) };
+    // Whether `draw` ran since the last `clear`
+    static DRAWN: Cell<bool> = const { Cell::new(false) };
+}
+
+// Pulls lines slightly towards the camera, as a fraction of their depth, so
+// ones lying on a surface aren't hidden in it
+const DEPTH_BIAS: f32 = 0.002;
+
+const CIRCLE_SEGMENTS: usize = 32;
+
+impl DebugDraw {
+    pub fn line(start: &Vector3, end: &Vector3, color: &Color, width: f32) {
+        LINES.with_borrow_mut(|lines| {
+            lines.push(DebugLine {
+                start: *start,
+                end: *end,
+                color: *color,
+                width,
+            })
+        });
+    }
+
+    // A square `size` pixels across
+    #[allow(dead_code)]
+    pub fn point(p: &Vector3, color: &Color, size: f32) {
+        Self::line(p, p, color, size);
+    }
+
+    // Joins the points in order, and the last back to the first if `closed`
+    pub fn polyline(points: &[Vector3], closed: bool, color: &Color, width: f32) {
+        for pair in points.windows(2) {
+            Self::line(&pair[0], &pair[1], color, width);
+        }
+        if closed && points.len() > 2 {
+            Self::line(&points[points.len() - 1], &points[0], color, width);
+        }
+    }
+
+    pub fn wire_box(aabb: &Aabb, color: &Color, width: f32) {
+        let corners: [Vector3; 8] = std::array::from_fn(|i| {
+            Vector3::new(
+                if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            )
+        });
+        Self::box_edges(&corners, color, width);
+    }
+
+    #[allow(dead_code)]
+    pub fn wire_obb(obb: &Obb, color: &Color, width: f32) {
+        Self::box_edges(&obb.corners(), color, width);
+    }
+
+    // Corners numbered with bit 0 for x, 1 for y and 2 for z, as
+    // `Obb::corners` gives them. Edges join corners one bit apart.
+    fn box_edges(corners: &[Vector3; 8], color: &Color, width: f32) {
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    Self::line(&corners[i], &corners[i | bit], color, width);
+                }
+            }
+        }
+    }
+
+    // A circle in each of the XY, YZ and XZ planes
+    pub fn wire_sphere(center: &Vector3, radius: f32, color: &Color, width: f32) {
+        let axes = [
+            (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+        ];
+        for (u, v) in axes {
+            Self::circle(center, &u, &v, radius, color, width);
+        }
+    }
+
+    // Circle spanned by two perpendicular unit vectors
+    pub fn circle(center: &Vector3, u: &Vector3, v: &Vector3, radius: f32, color: &Color, width: f32) {
+        let points: Vec<Vector3> = (0..CIRCLE_SEGMENTS)
+            .map(|k| {
+                let (sin, cos) = (k as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU).sin_cos();
+                *center + *u * (cos * radius) + *v * (sin * radius)
+            })
+            .collect();
+        Self::polyline(&points, true, color, width);
+    }
+
+    // Head is a four-sided cone a fifth of the arrow long
+    pub fn arrow(start: &Vector3, end: &Vector3, color: &Color, width: f32) {
+        Self::line(start, end, color, width);
+        let shaft = *end - *start;
+        let length = shaft.length();
+        if length == 0.0 {
+            return;
+        }
+        let dir = shaft * (1.0 / length);
+        let helper = if dir.y.abs() < 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+        let side = dir.cross(&helper).normalize_v();
+        let other_side = dir.cross(&side);
+        let head = length * 0.2;
+        let base = *end - dir * head;
+        for offset in [side, side * -1.0, other_side, other_side * -1.0] {
+            Self::line(end, &(base + offset * (head * 0.4)), color, width);
+        }
+    }
+
+    // The transform's right, up and forward as red, green and blue arrows
+    pub fn axes(transform: &Transform, size: f32, width: f32) {
+        let origin = transform.position;
+        let scale = size * transform.scale;
+        Self::arrow(&origin, &(origin + transform.right() * scale), &Color::new(255, 0, 0, 255), width);
+        Self::arrow(&origin, &(origin + transform.up() * scale), &Color::new(0, 255, 0, 255), width);
+        Self::arrow(&origin, &(origin + transform.forward() * scale), &Color::new(0, 0, 255, 255), width);
+    }
+
+    // Square grid on the XZ plane through `center`, `divisions` cells a side
+    pub fn grid(center: &Vector3, size: f32, divisions: usize, color: &Color, width: f32) {
+        let divisions = divisions.max(1);
+        let half = size / 2.0;
+        for i in 0..=divisions {
+            let t = i as f32 / divisions as f32 * size - half;
+            Self::line(
+                &(*center + Vector3::new(t, 0.0, -half)),
+                &(*center + Vector3::new(t, 0.0, half)),
+                color,
+                width,
+            );
+            Self::line(
+                &(*center + Vector3::new(-half, 0.0, t)),
+                &(*center + Vector3::new(half, 0.0, t)),
+                color,
+                width,
+            );
+        }
+    }
+
+    // What a camera sees through a view of the given aspect ratio, out to
+    // `far` or its far plane, whichever is nearer
+    pub fn frustum(camera: &Camera, aspect_ratio: f32, far: f32, color: &Color, width: f32) {
+        let (forward, right, up) = camera.basis();
+        let slice = |z: f32| -> [Vector3; 4] {
+            let (half_w, half_h) = camera.view_half_extents(z, aspect_ratio);
+            let center = camera.pos + forward * z;
+            [
+                center - right * half_w - up * half_h,
+                center + right * half_w - up * half_h,
+                center + right * half_w + up * half_h,
+                center - right * half_w + up * half_h,
+            ]
+        };
+        let near = slice(camera.near_plane);
+        let far = slice(far.min(camera.far_plane));
+        Self::polyline(&near, true, color, width);
+        Self::polyline(&far, true, color, width);
+        for k in 0..4 {
+            Self::line(&near[k], &far[k], color, width);
+        }
+    }
+
+    // Drops everything queued without drawing it
+    pub fn clear() {
+        LINES.with_borrow_mut(|lines| lines.clear());
+        DRAWN.set(false);
+    }
+
+    // End of the frame: draws the queue into `views`, unless the game already
+    // drew it itself with `draw`, then drops it
+    pub fn flush(screen: &mut Screen, views: &[Viewport]) {
+        if !DRAWN.get() {
+            for view in views {
+                screen.set_viewport(view.rect);
+                Self::draw(screen, &view.camera);
+            }
+            screen.reset_viewport();
+        }
+        Self::clear();
+    }
+
+    // Draws the queue over whatever viewport is current, seen from
+    // `camera`. The queue stays until `clear`, so every view of a frame gets
+    // the same lines.
+    pub fn draw(screen: &mut Screen, camera: &Camera) {
+        DRAWN.set(true);
+        let lines = LINES.with_borrow(|lines| lines.clone());
+        let Some(projector) = VertexProjector::new(camera, &screen.viewport()) else {
+            return;
+        };
+        let (forward, _, _) = camera.basis();
+        let fog = screen.fog;
+        screen.fog = Fog::off();
+        screen.depth_range = (camera.near_plane, camera.far_plane);
+        let texture = Texture::new(1, 1);
+
+        'lines: for line in &lines {
+            // Cut to the part between the near and far planes
+            let depth = |p: &Vector3| Vector3::dot(&(*p - camera.pos), &forward);
+            let (mut a, mut b) = (line.start, line.end);
+            let near = camera.near_plane * 1.001;
+            let far = camera.far_plane * 0.999;
+            for (limit, keep_beyond) in [(near, true), (far, false)] {
+                let (da, db) = (depth(&a) - limit, depth(&b) - limit);
+                let inside = |d: f32| if keep_beyond { d >= 0.0 } else { d <= 0.0 };
+                match (inside(da), inside(db)) {
+                    (false, false) => continue 'lines,
+                    (true, false) => b = a + (b - a) * (da / (da - db)),
+                    (false, true) => a = a + (b - a) * (da / (da - db)),
+                    (true, true) => {}
+                }
+            }
+            let (Some((pa, wa)), Some((pb, wb))) = (
+                projector.project(&Vertex::new(&a, &Vector2::new(0.0, 0.0), &line.color)),
+                projector.project(&Vertex::new(&b, &Vector2::new(0.0, 0.0), &line.color)),
+            ) else {
+                continue;
+            };
+
+            // Quad around the projected line, capped half a width past
+            // each end so polylines join up
+            let half = line.width.max(1.0) * 0.5;
+            let (dx, dy) = (pb.pos.x - pa.pos.x, pb.pos.y - pa.pos.y);
+            let length = (dx * dx + dy * dy).sqrt();
+            let (ux, uy) = if length > 1e-6 { (dx / length, dy / length) } else { (1.0, 0.0) };
+            let along = Vector2::new(ux * half, uy * half);
+            let across = Vector2::new(-uy * half, ux * half);
+            let corner = |p: &Vertex, sign_along: f32, sign_across: f32| {
+                let mut v = p.clone();
+                v.pos.x += along.x * sign_along + across.x * sign_across;
+                v.pos.y += along.y * sign_along + across.y * sign_across;
+                v.pos.z *= 1.0 - DEPTH_BIAS;
+                v
+            };
+            let (a0, a1) = (corner(&pa, -1.0, 1.0), corner(&pa, -1.0, -1.0));
+            let (b0, b1) = (corner(&pb, 1.0, 1.0), corner(&pb, 1.0, -1.0));
+
+            let world_a = Vertex::new(&a, &Vector2::new(0.0, 0.0), &line.color);
+            let world_b = Vertex::new(&b, &Vector2::new(0.0, 0.0), &line.color);
+            Triangle::new(a0.clone(), a1, b1.clone()).fill(
+                &Triangle::new(world_a.clone(), world_a.clone(), world_b.clone()),
+                &[wa, wa, wb],
+                screen,
+                &DummyPassthruShader,
+                &texture,
+            );
+            Triangle::new(a0, b1, b0).fill(
+                &Triangle::new(world_a, world_b.clone(), world_b),
+                &[wa, wb, wb],
+                screen,
+                &DummyPassthruShader,
+                &texture,
+            );
+        }
+        screen.fog = fog;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color { r: 255, g: 0, b: 0, a: 255 };
+
+    fn red_pixels(screen: &Screen) -> usize {
+        screen.pixels.iter().filter(|&&c| c == RED).count()
+    }
+
+    // Across the middle of the default camera's view
+    fn queue_line() {
+        let camera = Camera::new();
+        let (_, right, _) = camera.basis();
+        let center = camera.pointing_at;
+        DebugDraw::line(&(center - right), &(center + right), &RED, 3.0);
+    }
+
+    #[test]
+    fn flush_draws_into_the_views_once() {
+        let views = [Viewport::full_screen(Camera::new())];
+        queue_line();
+        let mut screen = Screen::new();
+        DebugDraw::flush(&mut screen, &views);
+        assert!(red_pixels(&screen) > 0);
+
+        // Gone after the frame
+        let mut screen = Screen::new();
+        DebugDraw::flush(&mut screen, &views);
+        assert_eq!(red_pixels(&screen), 0);
+    }
+
+    #[test]
+    fn flush_leaves_lines_already_drawn() {
+        let views = [Viewport::full_screen(Camera::new())];
+        queue_line();
+        let mut pane = Screen::new();
+        DebugDraw::draw(&mut pane, &Camera::new());
+        assert!(red_pixels(&pane) > 0);
+
+        let mut screen = Screen::new();
+        DebugDraw::flush(&mut screen, &views);
+        assert_eq!(red_pixels(&screen), 0);
+        // And the next frame's lines go in automatically again
+        queue_line();
+        DebugDraw::flush(&mut screen, &views);
+        assert!(red_pixels(&screen) > 0);
+    }
+}
@@ -0,0 +1,51 @@
+// How view depth is stored in Screen::depth_buffer. Linear keeps raw camera
+// Z. The other two spend the float precision where it's needed, so large
+// near-to-far ranges don't z-fight in the distance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthEncoding {
+    Linear,
+    // near / z: 1 at the near plane falling towards 0, bigger is closer
+    #[allow(dead_code)]
+    ReversedInverse,
+    // ln(z + 1) / ln(far + 1), roughly constant relative precision
+    #[allow(dead_code)]
+    Logarithmic,
+}
+
+impl DepthEncoding {
+    pub fn encode(&self, depth: f32, near: f32, far: f32) -> f32 {
+        match self {
+            DepthEncoding::Linear => depth,
+            DepthEncoding::ReversedInverse => near / depth.max(near),
+            DepthEncoding::Logarithmic => (depth.max(0.0) + 1.0).ln() / (far + 1.0).ln(),
+        }
+    }
+
+    // Back to view depth, infinite for the cleared value
+    pub fn decode(&self, value: f32, near: f32, far: f32) -> f32 {
+        if value == self.clear_value() {
+            return f32::INFINITY;
+        }
+        match self {
+            DepthEncoding::Linear => value,
+            DepthEncoding::ReversedInverse => near / value,
+            DepthEncoding::Logarithmic => (value * (far + 1.0).ln()).exp() - 1.0,
+        }
+    }
+
+    // What an empty depth buffer holds, further than anything drawn
+    pub fn clear_value(&self) -> f32 {
+        match self {
+            DepthEncoding::ReversedInverse => 0.0,
+            DepthEncoding::Linear | DepthEncoding::Logarithmic => f32::INFINITY,
+        }
+    }
+
+    // Whether encoded depth `a` is in front of `b`
+    pub fn is_closer(&self, a: f32, b: f32) -> bool {
+        match self {
+            DepthEncoding::ReversedInverse => a > b,
+            DepthEncoding::Linear | DepthEncoding::Logarithmic => a < b,
+        }
+    }
+}
@@ -1,22 +1,114 @@
-use crate::{camera::Camera, pixel_shader::PixelShader, screen::Screen, texture::Texture, triangle::Triangle};
+use crate::{
+    bounds::BoundingSphere,
+    camera::Camera,
+    frustum::Frustum,
+    mesh::Mesh,
+    pixel_shader::PixelShader,
+    ray::Ray,
+    screen::Screen,
+    texture::Texture,
+    triangle::Triangle,
+};
+
+// One `add` call worth of triangles, culled as a unit
+struct DrawItem {
+    mesh: Mesh,
+    bounds: BoundingSphere,
+    id: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[allow(dead_code)]
+pub struct RaycastHit {
+    // Id given to the `add_with_id` call the triangle came from, 0 if none
+    pub id: u32,
+    // Index of the triangle counted across the whole list, in insertion order
+    pub triangle_index: usize,
+    pub distance: f32,
+    pub barycentrics: (f32, f32, f32),
+}
 
 pub struct DrawList {
-    triangles: Vec<Triangle>,
+    items: Vec<DrawItem>,
 }
 
 impl DrawList {
     pub fn new() -> Self {
-        Self { triangles: vec![] }
+        Self { items: vec![] }
+    }
+    pub fn add(&mut self, triangles: &[Triangle]) {
+        self.add_with_id(triangles, 0);
+    }
+
+    // Tags the triangles for picking, through `raycast` or the screen's id buffer
+    pub fn add_with_id(&mut self, triangles: &[Triangle], id: u32) {
+        self.add_mesh_with_id(Mesh::from_triangles(triangles), id);
+    }
+
+    pub fn add_mesh(&mut self, mesh: Mesh) {
+        self.add_mesh_with_id(mesh, 0);
+    }
+
+    pub fn add_mesh_with_id(&mut self, mesh: Mesh, id: u32) {
+        self.items.push(DrawItem {
+            bounds: mesh.bounds(),
+            mesh,
+            id,
+        });
+    }
+
+    // Nearest triangle the ray hits
+    pub fn raycast(&self, ray: &Ray) -> Option<RaycastHit> {
+        let mut nearest: Option<RaycastHit> = None;
+        let mut index_base = 0;
+        for item in &self.items {
+            let first_index = index_base;
+            index_base += item.mesh.triangle_count();
+
+            match ray.intersect_sphere(&item.bounds) {
+                Some(d) if nearest.is_none_or(|n| d <= n.distance) => {}
+                _ => continue,
+            }
+            if let Some((i, hit)) = item.mesh.raycast(ray)
+                && nearest.is_none_or(|n| hit.distance < n.distance)
+            {
+                nearest = Some(RaycastHit {
+                    id: item.id,
+                    triangle_index: first_index + i,
+                    distance: hit.distance,
+                    barycentrics: hit.barycentrics,
+                });
+            }
+        }
+        nearest
     }
-    pub fn add(&mut self, triangles: &Vec<Triangle>) {
-        for triangle in triangles {
-            self.triangles.push(triangle.clone());
+
+    #[allow(dead_code)]
+    pub fn triangle(&self, mut index: usize) -> Option<Triangle> {
+        for item in &self.items {
+            let count = item.mesh.triangle_count();
+            if index < count {
+                return item.mesh.triangle(index);
+            }
+            index -= count;
         }
+        None
     }
 
     pub fn draw(&self, screen: &mut Screen, cam: &Camera, shader: &dyn PixelShader, texture: &Texture) {
-        for triangle in &self.triangles {
-            screen.draw_triangle(&triangle, &cam, shader, texture);
+        let (width, height) = screen.size();
+        let frustum = Frustum::from_camera(cam, width as f32 / height.max(1) as f32);
+        for item in &self.items {
+            screen.cull_stats.objects_submitted += 1;
+            if !frustum.intersects_sphere(&item.bounds) {
+                // Skipped whole, its triangles still count as submitted
+                screen.cull_stats.objects_culled += 1;
+                screen.cull_stats.triangles_submitted += item.mesh.triangle_count();
+                continue;
+            }
+            screen.object_id = item.id;
+            item.mesh.draw(screen, cam, shader, texture);
         }
+        screen.object_id = 0;
     }
 }
@@ -0,0 +1,123 @@
+use crate::{camera::Camera, input_handler::InputHandler, vec3::Vector3};
+
+// First person / free-fly camera. Mouse looks around, W/A/S/D move relative
+// to where the camera faces, shift sprints and ctrl crouches.
+pub struct FlyController {
+    // Eye position while standing
+    pub pos: Vector3,
+    // Radians around the Y axis, 0 looks down -Z
+    pub yaw: f32,
+    // Radians above the horizon
+    pub pitch: f32,
+
+    // Radians per pixel of mouse movement
+    pub sensitivity: f32,
+    pub invert_y: bool,
+    // World units per second
+    pub speed: f32,
+    pub sprint_multiplier: f32,
+    pub crouch_multiplier: f32,
+    // How far the eye drops while crouching
+    pub crouch_height: f32,
+    // Free-fly moves along the full view direction, otherwise movement
+    // stays level like walking and space/ctrl do nothing vertical
+    pub fly: bool,
+    pub max_pitch: f32,
+
+    crouch_amount: f32,
+}
+
+impl FlyController {
+    pub fn new(pos: Vector3, yaw: f32, pitch: f32) -> Self {
+        FlyController {
+            pos,
+            yaw,
+            pitch,
+            sensitivity: 0.003,
+            invert_y: false,
+            speed: 4.0,
+            sprint_multiplier: 2.5,
+            crouch_multiplier: 0.4,
+            crouch_height: 0.5,
+            fly: true,
+            max_pitch: 89.0f32.to_radians(),
+            crouch_amount: 0.0,
+        }
+    }
+
+    // Takes over from wherever the camera currently is
+    pub fn from_camera(cam: &Camera) -> Self {
+        let dir = (cam.pointing_at - cam.pos).normalize_v();
+        let yaw = dir.x.atan2(-dir.z);
+        let pitch = dir.y.clamp(-1.0, 1.0).asin();
+        Self::new(cam.pos, yaw, pitch)
+    }
+
+    pub fn forward(&self) -> Vector3 {
+        Vector3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+
+    pub fn right(&self) -> Vector3 {
+        Vector3::new(self.yaw.cos(), 0.0, self.yaw.sin())
+    }
+
+    pub fn look(&mut self, dx: f32, dy: f32) {
+        let dy = if self.invert_y { -dy } else { dy };
+        self.yaw += dx * self.sensitivity;
+        self.pitch = (self.pitch - dy * self.sensitivity).clamp(-self.max_pitch, self.max_pitch);
+    }
+
+    pub fn update(&mut self, input: &InputHandler, cam: &mut Camera, dt: f32) {
+        self.look(input.mouse_delta.x, input.mouse_delta.y);
+
+        let forward = if self.fly {
+            self.forward()
+        } else {
+            Vector3::new(self.yaw.sin(), 0.0, -self.yaw.cos())
+        };
+        let right = self.right();
+
+        let mut movement = Vector3::new(0.0, 0.0, 0.0);
+        if input.w.pressed {
+            movement += forward;
+        }
+        if input.s.pressed {
+            movement -= forward;
+        }
+        if input.d.pressed {
+            movement += right;
+        }
+        if input.a.pressed {
+            movement -= right;
+        }
+        if self.fly && input.space.pressed {
+            movement.y += 1.0;
+        }
+
+        let crouching = input.ctrl.pressed;
+        let mut speed = self.speed;
+        if input.shift.pressed && !crouching {
+            speed *= self.sprint_multiplier;
+        }
+        if crouching {
+            speed *= self.crouch_multiplier;
+        }
+        self.pos += movement.normalize_v() * (speed * dt);
+
+        // Ease the eye height down and back up instead of snapping
+        let crouch_goal = if crouching { self.crouch_height } else { 0.0 };
+        let t = 1.0 - (-12.0 * dt).exp();
+        self.crouch_amount += (crouch_goal - self.crouch_amount) * t;
+
+        self.apply(cam);
+    }
+
+    pub fn apply(&self, cam: &mut Camera) {
+        cam.pos = self.pos - Vector3::new(0.0, self.crouch_amount, 0.0);
+        cam.pointing_at = cam.pos + self.forward();
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{color::Color, pixel_placement::PixelPlacement};
+
+#[derive(Clone, Copy, Debug)]
+pub enum FogMode {
+    Off,
+    // Fades in between two view depths
+    Linear { start: f32, end: f32 },
+    Exp { density: f32 },
+    Exp2 { density: f32 },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum FogColor {
+    Fixed(Color),
+    // Follows whatever the screen was last cleared to
+    ClearColor,
+}
+
+// Ground fog that is thickest at `base_y` and thins out going up
+#[derive(Clone, Copy, Debug)]
+pub struct HeightFog {
+    pub base_y: f32,
+    pub falloff: f32,
+    pub density: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    pub mode: FogMode,
+    pub color: FogColor,
+    pub height: Option<HeightFog>,
+}
+
+impl Fog {
+    pub fn off() -> Self {
+        Fog {
+            mode: FogMode::Off,
+            color: FogColor::ClearColor,
+            height: None,
+        }
+    }
+
+    pub fn linear(start: f32, end: f32, color: FogColor) -> Self {
+        Fog {
+            mode: FogMode::Linear { start, end },
+            color,
+            height: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn exp(density: f32, color: FogColor) -> Self {
+        Fog {
+            mode: FogMode::Exp { density },
+            color,
+            height: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn exp2(density: f32, color: FogColor) -> Self {
+        Fog {
+            mode: FogMode::Exp2 { density },
+            color,
+            height: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_height(mut self, base_y: f32, falloff: f32, density: f32) -> Self {
+        self.height = Some(HeightFog {
+            base_y,
+            falloff,
+            density,
+        });
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self.mode, FogMode::Off) || self.height.is_some()
+    }
+
+    // How much of the fog color ends up in a pixel, 0 = none, 1 = only fog
+    pub fn factor(&self, depth: f32, world_y: f32) -> f32 {
+        let distance_fog = match self.mode {
+            FogMode::Off => 0.0,
+            FogMode::Linear { start, end } => {
+                ((depth - start) / (end - start).max(0.0001)).clamp(0.0, 1.0)
+            }
+            FogMode::Exp { density } => 1.0 - (-density * depth).exp(),
+            FogMode::Exp2 { density } => {
+                let d = density * depth;
+                1.0 - (-d * d).exp()
+            }
+        };
+
+        let height_fog = match &self.height {
+            Some(h) => {
+                // Density decays exponentially with height above the base
+                let above = (world_y - h.base_y).max(0.0);
+                let local_density = h.density * (-h.falloff * above).exp();
+                1.0 - (-local_density * depth).exp()
+            }
+            None => 0.0,
+        };
+
+        1.0 - (1.0 - distance_fog) * (1.0 - height_fog)
+    }
+
+    pub fn apply(&self, pp: &mut PixelPlacement, clear_color: &Color) {
+        if pp.color.a == 0 || !self.is_enabled() {
+            return;
+        }
+
+        let fog_color = match self.color {
+            FogColor::Fixed(color) => color,
+            FogColor::ClearColor => *clear_color,
+        };
+        let fog_factor = self.factor(pp.depth, pp.world_pos.y);
+
+        // Linearly interpolate between pixel color and fog color
+        pp.color = Color {
+            r: ((1.0 - fog_factor) * pp.color.r as f32 + fog_factor * fog_color.r as f32) as u8,
+            g: ((1.0 - fog_factor) * pp.color.g as f32 + fog_factor * fog_color.g as f32) as u8,
+            b: ((1.0 - fog_factor) * pp.color.b as f32 + fog_factor * fog_color.b as f32) as u8,
+            a: ((1.0 - fog_factor) * pp.color.a as f32 + fog_factor * fog_color.a as f32) as u8,
+        };
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    bounds::{Aabb, BoundingSphere},
+    camera::Camera,
+    vec3::Vector3,
+};
+
+// Points with normal . p + d >= 0 are on the inside
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    pub normal: Vector3,
+    pub d: f32,
+}
+
+impl Plane {
+    // Normalizes so distances come out in world units
+    pub fn new(normal: Vector3, d: f32) -> Self {
+        let len = normal.length();
+        if len > 0.0 {
+            Plane {
+                normal: normal * (1.0 / len),
+                d: d / len,
+            }
+        } else {
+            Plane { normal, d }
+        }
+    }
+
+    pub fn from_point_normal(point: &Vector3, normal: &Vector3) -> Self {
+        Plane::new(*normal, -Vector3::dot(normal, point))
+    }
+
+    pub fn distance(&self, p: &Vector3) -> f32 {
+        Vector3::dot(&self.normal, p) + self.d
+    }
+}
+
+pub struct Frustum {
+    // Near, far, left, right, bottom, top
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    pub fn from_camera(camera: &Camera, aspect_ratio: f32) -> Self {
+        let (forward, right, up) = camera.basis();
+
+        // The visible half extents grow linearly with depth (or stay constant
+        // when orthographic), so each side is a plane x = a + b * z in camera space
+        let (w0, h0) = camera.view_half_extents(0.0, aspect_ratio);
+        let (w1, h1) = camera.view_half_extents(1.0, aspect_ratio);
+        let (bw, bh) = (w1 - w0, h1 - h0);
+
+        // Side plane in camera space with its inside where sign * axis <= a + b * z
+        let side = |axis: &Vector3, sign: f32, a: f32, b: f32| -> Plane {
+            let normal = *axis * -sign + forward * b;
+            Plane::new(normal, a - Vector3::dot(&normal, &camera.pos))
+        };
+
+        Frustum {
+            planes: [
+                Plane::from_point_normal(&(camera.pos + forward * camera.near_plane), &forward),
+                Plane::from_point_normal(&(camera.pos + forward * camera.far_plane), &(forward * -1.0)),
+                side(&right, -1.0, w0, bw),
+                side(&right, 1.0, w0, bw),
+                side(&up, -1.0, h0, bh),
+                side(&up, 1.0, h0, bh),
+            ],
+        }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance(&sphere.center) >= -sphere.radius)
+    }
+
+    #[allow(dead_code)]
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // Corner furthest along the plane normal
+            let p = Vector3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            plane.distance(&p) >= 0.0
+        })
+    }
+}
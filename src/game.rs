@@ -1,8 +1,16 @@
-use crate::{key_event::KeyEvent, mouse_event::MouseEvent, screen::Screen};
+use crate::{key_event::KeyEvent, mouse_event::MouseEvent, screen::Screen, viewport::Viewport};
 
 pub trait Game {
     fn update_tick(&mut self);
     fn render_tick(&self, screen: &mut Screen);
     fn key_event(&mut self, key_ev: &KeyEvent);
     fn mouse_event(&mut self, mouse_ev: &MouseEvent);
+    fn wants_relative_mouse(&self) -> bool {
+        false
+    }
+    // Where queued `DebugDraw` lines go once `render_tick` is done. Games that
+    // draw them pane by pane with `DebugDraw::draw` instead can leave this.
+    fn debug_views(&self) -> Vec<Viewport> {
+        vec![]
+    }
 }
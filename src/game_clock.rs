@@ -0,0 +1,32 @@
+// Fixed update rate the window loop aims for
+pub const TICK_SECONDS: f32 = 1.0 / 60.0;
+
+// Game time, advanced once per update tick rather than read from the wall
+// clock, so anything driven by it replays the same way every run.
+pub struct GameClock {
+    // Seconds of game time since start
+    pub time: f32,
+    pub ticks: u64,
+    // Game seconds that passed during the last tick
+    pub dt: f32,
+    pub time_scale: f32,
+    pub paused: bool,
+}
+
+impl GameClock {
+    pub fn new() -> Self {
+        GameClock {
+            time: 0.0,
+            ticks: 0,
+            dt: 0.0,
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.dt = if self.paused { 0.0 } else { TICK_SECONDS * self.time_scale };
+        self.time += self.dt;
+        self.ticks += 1;
+    }
+}
@@ -0,0 +1,952 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    animation::{Animation, AnimationChannel, AnimationPath, KeyInterpolation},
+    color::Color,
+    json::JsonValue,
+    material::Material,
+    matrix4::Matrix4,
+    quaternion::Quaternion,
+    scene_node::{SceneNode, Skin},
+    texture::Texture,
+    triangle::Triangle,
+    vec2::Vector2,
+    vec3::Vector3,
+    vertex::Vertex,
+};
+
+// Extensions that can be ignored without drawing the scene wrong
+const SUPPORTED_EXTENSIONS: &[&str] = &["KHR_materials_unlit"];
+
+// Triangles of one glTF primitive, in the mesh's own space
+pub struct GltfPrimitive {
+    pub triangles: Vec<Triangle>,
+    pub material: Option<usize>,
+    // Per triangle corner in triangle order, empty unless the mesh is skinned
+    pub joints: Vec<[usize; 4]>,
+    pub weights: Vec<[f32; 4]>,
+}
+
+pub struct GltfMesh {
+    #[allow(dead_code)]
+    pub name: String,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+// Everything in a .gltf or .glb file: meshes, materials with their PNG
+// textures, the node hierarchy, skins and animations
+pub struct GltfScene {
+    pub nodes: Vec<SceneNode>,
+    // Top-level nodes of the default scene
+    pub roots: Vec<usize>,
+    pub meshes: Vec<GltfMesh>,
+    #[allow(dead_code)]
+    pub materials: Vec<Material>,
+    pub skins: Vec<Skin>,
+    #[allow(dead_code)]
+    pub animations: Vec<Animation>,
+    // What was skipped without failing the load, like non-PNG images
+    #[allow(dead_code)]
+    pub warnings: Vec<String>,
+}
+
+fn invalid(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn base64_decode(text: &str) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' | b'\n' | b'\r' | b' ' => continue,
+            _ => return Err(invalid("bad base64 data".to_string())),
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+// URIs may escape spaces and other characters as %XX
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(v) = uri.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok())
+        {
+            out.push(v);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+struct Loader<'a> {
+    json: &'a JsonValue,
+    base_dir: Option<&'a Path>,
+    buffers: Vec<Vec<u8>>,
+    warnings: Vec<String>,
+}
+
+impl Loader<'_> {
+    // Data URI or file next to the glTF
+    fn load_uri(&self, uri: &str) -> std::io::Result<Vec<u8>> {
+        if let Some(rest) = uri.strip_prefix("data:") {
+            let Some((_, data)) = rest.split_once(";base64,") else {
+                return Err(invalid("only base64 data URIs are supported".to_string()));
+            };
+            return base64_decode(data);
+        }
+        let Some(dir) = self.base_dir else {
+            return Err(invalid(format!("external file '{}' needs a base directory", uri)));
+        };
+        let path = dir.join(percent_decode(uri));
+        std::fs::read(&path).map_err(|e| Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+    }
+
+    fn load_buffers(&mut self, glb_bin: Option<Vec<u8>>) -> std::io::Result<()> {
+        let mut glb_bin = glb_bin;
+        for (i, buffer) in self.json.get("buffers").as_array().iter().enumerate() {
+            let data = match buffer.get("uri").as_str() {
+                Some(uri) => self
+                    .load_uri(uri)
+                    .map_err(|e| Error::new(e.kind(), format!("buffer {}: {}", i, e)))?,
+                // Only the first buffer of a .glb may leave out the uri
+                None if i == 0 => glb_bin
+                    .take()
+                    .ok_or_else(|| invalid("buffer 0 has no uri and there is no BIN chunk".to_string()))?,
+                None => return Err(invalid(format!("buffer {} has no uri", i))),
+            };
+            let length = buffer.get("byteLength").as_usize().unwrap_or(0);
+            if data.len() < length {
+                return Err(invalid(format!("buffer {} is shorter than its byteLength", i)));
+            }
+            self.buffers.push(data);
+        }
+        Ok(())
+    }
+
+    fn buffer_view(&self, index: usize) -> std::io::Result<(&[u8], Option<usize>)> {
+        let view = self.json.get("bufferViews").at(index);
+        if view.is_null() {
+            return Err(invalid(format!("no bufferView {}", index)));
+        }
+        let buffer = view
+            .get("buffer")
+            .as_usize()
+            .and_then(|b| self.buffers.get(b))
+            .ok_or_else(|| invalid(format!("bufferView {}: bad buffer", index)))?;
+        let offset = view.get("byteOffset").as_usize().unwrap_or(0);
+        let length = view.get("byteLength").as_usize().unwrap_or(0);
+        let data = offset
+            .checked_add(length)
+            .and_then(|end| buffer.get(offset..end))
+            .ok_or_else(|| invalid(format!("bufferView {} runs past its buffer", index)))?;
+        Ok((data, view.get("byteStride").as_usize()))
+    }
+
+    // Every element of an accessor as floats, `components` per element.
+    // Normalized integers come out in 0..1 or -1..1, others as they are.
+    fn accessor(&self, index: usize) -> std::io::Result<(Vec<f32>, usize)> {
+        let acc = self.json.get("accessors").at(index);
+        if acc.is_null() {
+            return Err(invalid(format!("no accessor {}", index)));
+        }
+        let err = |msg: &str| invalid(format!("accessor {}: {}", index, msg));
+        if !acc.get("sparse").is_null() {
+            return Err(err("sparse accessors aren't supported"));
+        }
+
+        let count = acc.get("count").as_usize().ok_or_else(|| err("missing count"))?;
+        let components = match acc.get("type").as_str() {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") | Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(err("unknown type")),
+        };
+        let component_type = acc.get("componentType").as_usize().unwrap_or(0);
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(err("unknown componentType")),
+        };
+        let normalized = acc.get("normalized").as_bool().unwrap_or(false);
+        // Counts come straight from the file, so none of this may overflow
+        let total = count.checked_mul(components).ok_or_else(|| err("out of range"))?;
+
+        let Some(view_index) = acc.get("bufferView").as_usize() else {
+            // No data means all zeros
+            let mut zeros = vec![];
+            zeros.try_reserve_exact(total).map_err(|_| err("out of range"))?;
+            zeros.resize(total, 0.0);
+            return Ok((zeros, components));
+        };
+        let (data, stride) = self.buffer_view(view_index)?;
+        let stride = stride.unwrap_or(size * components);
+        let offset = acc.get("byteOffset").as_usize().unwrap_or(0);
+        if count > 0 {
+            let end = (count - 1)
+                .checked_mul(stride)
+                .and_then(|last| last.checked_add(offset))
+                .and_then(|last| last.checked_add(size * components))
+                .ok_or_else(|| err("out of range"))?;
+            if end > data.len() {
+                return Err(err("runs past its bufferView"));
+            }
+        }
+
+        let mut out = Vec::with_capacity(total);
+        for element in 0..count {
+            for c in 0..components {
+                let at = offset + element * stride + c * size;
+                let b = &data[at..at + size];
+                let v = match component_type {
+                    5120 => {
+                        let v = b[0] as i8 as f32;
+                        if normalized { (v / 127.0).max(-1.0) } else { v }
+                    }
+                    5121 => {
+                        let v = b[0] as f32;
+                        if normalized { v / 255.0 } else { v }
+                    }
+                    5122 => {
+                        let v = i16::from_le_bytes([b[0], b[1]]) as f32;
+                        if normalized { (v / 32767.0).max(-1.0) } else { v }
+                    }
+                    5123 => {
+                        let v = u16::from_le_bytes([b[0], b[1]]) as f32;
+                        if normalized { v / 65535.0 } else { v }
+                    }
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                };
+                out.push(v);
+            }
+        }
+        Ok((out, components))
+    }
+
+    // Decoded PNG for each image, None where it couldn't be used
+    fn load_images(&mut self) -> Vec<Option<Texture>> {
+        let mut images = vec![];
+        for (i, image) in self.json.get("images").as_array().iter().enumerate() {
+            let bytes = match (image.get("uri").as_str(), image.get("bufferView").as_usize()) {
+                (Some(uri), _) => self.load_uri(uri),
+                (None, Some(view)) => self.buffer_view(view).map(|(data, _)| data.to_vec()),
+                _ => Err(invalid("no uri or bufferView".to_string())),
+            };
+            let texture = bytes.and_then(|bytes| {
+                if !bytes.starts_with(b"\x89PNG") {
+                    return Err(invalid("only PNG images are supported".to_string()));
+                }
+                Texture::from_png_bytes(&bytes)
+            });
+            match texture {
+                Ok(texture) => images.push(Some(texture)),
+                Err(e) => {
+                    self.warnings.push(format!("image {}: {}", i, e));
+                    images.push(None);
+                }
+            }
+        }
+        images
+    }
+
+    fn load_materials(&self, images: &[Option<Texture>]) -> Vec<Material> {
+        let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let mut materials = vec![];
+        for (i, mat) in self.json.get("materials").as_array().iter().enumerate() {
+            let name = mat.get("name").as_str().map_or(format!("material {}", i), |s| s.to_string());
+            let mut material = Material::new(&name);
+            let pbr = mat.get("pbrMetallicRoughness");
+            if let Some(f) = pbr.get("baseColorFactor").as_f32_vec()
+                && f.len() == 4
+            {
+                material.diffuse = Color::new(to_byte(f[0]), to_byte(f[1]), to_byte(f[2]), to_byte(f[3]));
+            }
+            material.diffuse_map = pbr
+                .get("baseColorTexture")
+                .get("index")
+                .as_usize()
+                .and_then(|t| self.json.get("textures").at(t).get("source").as_usize())
+                .and_then(|source| images.get(source))
+                .and_then(|image| image.clone());
+            materials.push(material);
+        }
+        materials
+    }
+
+    fn load_mesh(&mut self, index: usize, materials: &[Material]) -> std::io::Result<GltfMesh> {
+        let mesh = self.json.get("meshes").at(index);
+        let name = mesh.get("name").as_str().unwrap_or("").to_string();
+        let mut primitives = vec![];
+        for (p, prim) in mesh.get("primitives").as_array().iter().enumerate() {
+            let err = |e: Error| Error::new(e.kind(), format!("mesh {} primitive {}: {}", index, p, e));
+            let mode = prim.get("mode").as_usize().unwrap_or(4);
+            if mode < 4 {
+                self.warnings.push(format!("mesh {} primitive {}: points and lines skipped", index, p));
+                continue;
+            }
+            if !prim.get("targets").is_null() {
+                self.warnings.push(format!("mesh {} primitive {}: morph targets ignored", index, p));
+            }
+
+            let attributes = prim.get("attributes");
+            // Values and components per vertex, checked against the
+            // component counts the attribute can have
+            let attribute = |name: &str, allowed: &[usize]| -> std::io::Result<Option<(Vec<f32>, usize)>> {
+                let Some(a) = attributes.get(name).as_usize() else {
+                    return Ok(None);
+                };
+                let (values, components) = self.accessor(a)?;
+                if !allowed.contains(&components) {
+                    return Err(invalid(format!("{} can't have {} components", name, components)));
+                }
+                Ok(Some((values, components)))
+            };
+            let Some((positions, _)) = attribute("POSITION", &[3]).map_err(err)? else {
+                return Err(err(invalid("no POSITION attribute".to_string())));
+            };
+            let vertex_count = positions.len() / 3;
+            let normals = attribute("NORMAL", &[3]).map_err(err)?;
+            let uvs = attribute("TEXCOORD_0", &[2, 3, 4]).map_err(err)?;
+            let colors = attribute("COLOR_0", &[3, 4]).map_err(err)?;
+            let joints = attribute("JOINTS_0", &[4]).map_err(err)?;
+            let weights = attribute("WEIGHTS_0", &[4]).map_err(err)?;
+            let others = [
+                ("NORMAL", &normals),
+                ("TEXCOORD_0", &uvs),
+                ("COLOR_0", &colors),
+                ("JOINTS_0", &joints),
+                ("WEIGHTS_0", &weights),
+            ];
+            for (name, values) in others {
+                if values.as_ref().is_some_and(|(values, components)| values.len() / components < vertex_count) {
+                    return Err(err(invalid(format!("{} has fewer vertices than POSITION", name))));
+                }
+            }
+
+            let indices: Vec<usize> = match prim.get("indices").as_usize() {
+                Some(a) => self.accessor(a).map_err(err)?.0.iter().map(|&i| i as usize).collect(),
+                None => (0..vertex_count).collect(),
+            };
+            if indices.iter().any(|&i| i >= vertex_count) {
+                return Err(err(invalid("index out of range".to_string())));
+            }
+
+            // Corner triples for lists, strips and fans
+            let corners: Vec<[usize; 3]> = match mode {
+                4 => indices.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+                5 => (2..indices.len())
+                    .map(|i| {
+                        // Every other strip triangle is flipped to keep the winding
+                        if i % 2 == 0 {
+                            [indices[i - 2], indices[i - 1], indices[i]]
+                        } else {
+                            [indices[i - 1], indices[i - 2], indices[i]]
+                        }
+                    })
+                    .collect(),
+                6 => (2..indices.len())
+                    .map(|i| [indices[0], indices[i - 1], indices[i]])
+                    .collect(),
+                _ => return Err(err(invalid(format!("unknown mode {}", mode)))),
+            };
+
+            let material = prim.get("material").as_usize().filter(|&m| m < materials.len());
+            let base = material.map_or(Color::new(255, 255, 255, 255), |m| materials[m].diffuse);
+            let vertex = |i: usize| -> Vertex {
+                let pos = Vector3::new(positions[i * 3], positions[i * 3 + 1], positions[i * 3 + 2]);
+                let uv = match &uvs {
+                    Some((uv, n)) => Vector2::new(uv[i * n], uv[i * n + 1]),
+                    None => Vector2::new(0.0, 0.0),
+                };
+                let mut color = base;
+                if let Some((c, n)) = &colors {
+                    let scale = |a: u8, v: f32| (a as f32 * v.clamp(0.0, 1.0)).round() as u8;
+                    color = Color::new(
+                        scale(base.r, c[i * n]),
+                        scale(base.g, c[i * n + 1]),
+                        scale(base.b, c[i * n + 2]),
+                        if *n == 4 { scale(base.a, c[i * n + 3]) } else { base.a },
+                    );
+                }
+                let normal = match &normals {
+                    Some((n, _)) => Vector3::new(n[i * 3], n[i * 3 + 1], n[i * 3 + 2]).normalize_v(),
+                    None => Vector3::new(0.0, 0.0, 0.0),
+                };
+                Vertex::new(&pos, &uv, &color).with_normal(&normal)
+            };
+
+            let mut primitive = GltfPrimitive {
+                triangles: vec![],
+                material,
+                joints: vec![],
+                weights: vec![],
+            };
+            for tri in &corners {
+                primitive
+                    .triangles
+                    .push(Triangle::new(vertex(tri[0]), vertex(tri[1]), vertex(tri[2])));
+                if let (Some((j, jn)), Some((w, wn))) = (&joints, &weights) {
+                    for &i in tri {
+                        primitive.joints.push([0, 1, 2, 3].map(|k| j[i * jn + k] as usize));
+                        primitive.weights.push([0, 1, 2, 3].map(|k| w[i * wn + k]));
+                    }
+                }
+            }
+            primitives.push(primitive);
+        }
+        Ok(GltfMesh { name, primitives })
+    }
+
+    fn load_nodes(&self) -> std::io::Result<Vec<SceneNode>> {
+        let json_nodes = self.json.get("nodes").as_array();
+        let mut nodes = vec![];
+        for (i, n) in json_nodes.iter().enumerate() {
+            let mut node = SceneNode::new(n.get("name").as_str().unwrap_or(""));
+            if let Some(t) = n.get("translation").as_f32_vec().filter(|t| t.len() == 3) {
+                node.translation = Vector3::new(t[0], t[1], t[2]);
+            }
+            if let Some(r) = n.get("rotation").as_f32_vec().filter(|r| r.len() == 4) {
+                node.rotation = Quaternion::new(r[0], r[1], r[2], r[3]);
+            }
+            if let Some(s) = n.get("scale").as_f32_vec().filter(|s| s.len() == 3) {
+                node.scale = Vector3::new(s[0], s[1], s[2]);
+            }
+            if let Some(m) = n.get("matrix").as_f32_vec().filter(|m| m.len() == 16) {
+                let mut cols = [0.0; 16];
+                cols.copy_from_slice(&m);
+                node.matrix = Some(Matrix4::from_cols(cols));
+            }
+            node.mesh = n.get("mesh").as_usize();
+            node.skin = n.get("skin").as_usize();
+            for child in n.get("children").as_array() {
+                let child = child
+                    .as_usize()
+                    .filter(|&c| c < json_nodes.len())
+                    .ok_or_else(|| invalid(format!("node {}: bad child", i)))?;
+                node.children.push(child);
+            }
+            nodes.push(node);
+        }
+
+        for i in 0..nodes.len() {
+            for c in nodes[i].children.clone() {
+                if nodes[c].parent.is_some() {
+                    return Err(invalid(format!("node {} has more than one parent", c)));
+                }
+                nodes[c].parent = Some(i);
+            }
+        }
+        // With one parent each, nodes out of reach of every parentless node
+        // are in or under a cycle, which would send the traversals round
+        // forever
+        let mut reached = vec![false; nodes.len()];
+        let mut stack: Vec<usize> = (0..nodes.len()).filter(|&i| nodes[i].parent.is_none()).collect();
+        while let Some(i) = stack.pop() {
+            reached[i] = true;
+            stack.extend(&nodes[i].children);
+        }
+        if let Some(i) = reached.iter().position(|&r| !r) {
+            return Err(invalid(format!("node {} is part of a cycle", i)));
+        }
+        Ok(nodes)
+    }
+
+    fn load_skins(&self) -> std::io::Result<Vec<Skin>> {
+        let mut skins = vec![];
+        for skin in self.json.get("skins").as_array() {
+            let joints: Vec<usize> = skin.get("joints").as_array().iter().filter_map(|j| j.as_usize()).collect();
+            let inverse_bind_matrices = match skin.get("inverseBindMatrices").as_usize() {
+                Some(a) => self
+                    .accessor(a)?
+                    .0
+                    .chunks_exact(16)
+                    .map(|c| {
+                        let mut cols = [0.0; 16];
+                        cols.copy_from_slice(c);
+                        Matrix4::from_cols(cols)
+                    })
+                    .collect(),
+                None => vec![Matrix4::identity(); joints.len()],
+            };
+            skins.push(Skin {
+                name: skin.get("name").as_str().unwrap_or("").to_string(),
+                joints,
+                inverse_bind_matrices,
+                skeleton: skin.get("skeleton").as_usize(),
+            });
+        }
+        Ok(skins)
+    }
+
+    fn load_animations(&self) -> std::io::Result<Vec<Animation>> {
+        let mut animations = vec![];
+        for (a, anim) in self.json.get("animations").as_array().iter().enumerate() {
+            let samplers = anim.get("samplers");
+            let mut channels = vec![];
+            for channel in anim.get("channels").as_array() {
+                let target = channel.get("target");
+                // Channels aimed somewhere other than a node come from extensions
+                let Some(node) = target.get("node").as_usize() else {
+                    continue;
+                };
+                let path = match target.get("path").as_str() {
+                    Some("translation") => AnimationPath::Translation,
+                    Some("rotation") => AnimationPath::Rotation,
+                    Some("scale") => AnimationPath::Scale,
+                    Some("weights") => AnimationPath::Weights,
+                    _ => return Err(invalid(format!("animation {}: unknown channel path", a))),
+                };
+                let sampler = samplers.at(channel.get("sampler").as_usize().unwrap_or(usize::MAX));
+                let (Some(input), Some(output)) =
+                    (sampler.get("input").as_usize(), sampler.get("output").as_usize())
+                else {
+                    return Err(invalid(format!("animation {}: bad sampler", a)));
+                };
+                let interpolation = match sampler.get("interpolation").as_str() {
+                    Some("STEP") => KeyInterpolation::Step,
+                    Some("CUBICSPLINE") => KeyInterpolation::CubicSpline,
+                    _ => KeyInterpolation::Linear,
+                };
+                channels.push(AnimationChannel {
+                    node,
+                    path,
+                    interpolation,
+                    times: self.accessor(input)?.0,
+                    values: self.accessor(output)?.0,
+                });
+            }
+            animations.push(Animation {
+                name: anim.get("name").as_str().unwrap_or("").to_string(),
+                channels,
+            });
+        }
+        Ok(animations)
+    }
+}
+
+impl GltfScene {
+    // .gltf or .glb, told apart by the binary header
+    #[allow(dead_code)]
+    pub fn from_file(file_path: &str) -> std::io::Result<Self> {
+        let bytes = std::fs::read(file_path)?;
+        let base_dir = Path::new(file_path).parent();
+        let scene = if bytes.starts_with(b"glTF") {
+            Self::from_glb_bytes(&bytes, base_dir)
+        } else {
+            let text = String::from_utf8(bytes).map_err(|_| invalid("not UTF-8".to_string()))?;
+            Self::from_gltf_str(&text, base_dir)
+        };
+        scene.map_err(|e| Error::new(e.kind(), format!("{}: {}", file_path, e)))
+    }
+
+    pub fn from_glb_bytes(bytes: &[u8], base_dir: Option<&Path>) -> std::io::Result<Self> {
+        if !bytes.starts_with(b"glTF") {
+            return Err(invalid("not a .glb file".to_string()));
+        }
+        if read_u32(bytes, 4) != Some(2) {
+            return Err(invalid("only glTF 2.0 .glb files are supported".to_string()));
+        }
+
+        let mut json = None;
+        let mut bin = None;
+        let mut at = 12;
+        while let (Some(length), Some(kind)) = (read_u32(bytes, at), read_u32(bytes, at + 4)) {
+            let start = at + 8;
+            let data = bytes
+                .get(start..start + length as usize)
+                .ok_or_else(|| invalid("chunk runs past the end of the file".to_string()))?;
+            match kind {
+                0x4E4F534A => json = Some(data),
+                0x004E4942 if bin.is_none() => bin = Some(data.to_vec()),
+                // Unknown chunks are to be skipped
+                _ => {}
+            }
+            at = start + length as usize;
+        }
+
+        let json = json.ok_or_else(|| invalid("no JSON chunk".to_string()))?;
+        let text = std::str::from_utf8(json).map_err(|_| invalid("JSON chunk isn't UTF-8".to_string()))?;
+        let json = JsonValue::parse(text).map_err(invalid)?;
+        Self::from_json(&json, bin, base_dir)
+    }
+
+    // External buffers and images are looked up relative to `base_dir`
+    pub fn from_gltf_str(text: &str, base_dir: Option<&Path>) -> std::io::Result<Self> {
+        let json = JsonValue::parse(text).map_err(invalid)?;
+        Self::from_json(&json, None, base_dir)
+    }
+
+    fn from_json(json: &JsonValue, glb_bin: Option<Vec<u8>>, base_dir: Option<&Path>) -> std::io::Result<Self> {
+        let version = json.get("asset").get("version").as_str().unwrap_or("");
+        if !version.starts_with("2.") {
+            return Err(invalid(format!("glTF version '{}' isn't supported, only 2.x", version)));
+        }
+        for ext in json.get("extensionsRequired").as_array() {
+            let ext = ext.as_str().unwrap_or("");
+            if !SUPPORTED_EXTENSIONS.contains(&ext) {
+                return Err(invalid(format!("required extension '{}' isn't supported", ext)));
+            }
+        }
+
+        let mut loader = Loader {
+            json,
+            base_dir,
+            buffers: vec![],
+            warnings: vec![],
+        };
+        for ext in json.get("extensionsUsed").as_array() {
+            let ext = ext.as_str().unwrap_or("");
+            if !SUPPORTED_EXTENSIONS.contains(&ext) {
+                loader.warnings.push(format!("extension '{}' ignored", ext));
+            }
+        }
+
+        loader.load_buffers(glb_bin)?;
+        let images = loader.load_images();
+        let materials = loader.load_materials(&images);
+        let mut meshes = vec![];
+        for i in 0..json.get("meshes").as_array().len() {
+            meshes.push(loader.load_mesh(i, &materials)?);
+        }
+        let nodes = loader.load_nodes()?;
+        for (i, node) in nodes.iter().enumerate() {
+            if node.mesh.is_some_and(|m| m >= meshes.len()) {
+                return Err(invalid(format!("node {}: bad mesh", i)));
+            }
+        }
+        let skins = loader.load_skins()?;
+        if skins.iter().flat_map(|s| &s.joints).any(|&j| j >= nodes.len())
+            || nodes.iter().any(|n| n.skin.is_some_and(|s| s >= skins.len()))
+        {
+            return Err(invalid("skin refers to a missing node".to_string()));
+        }
+        let animations = loader.load_animations()?;
+
+        let scene = json
+            .get("scenes")
+            .at(json.get("scene").as_usize().unwrap_or(0));
+        let roots: Vec<usize> = if scene.is_null() {
+            (0..nodes.len()).filter(|&i| nodes[i].parent.is_none()).collect()
+        } else {
+            scene
+                .get("nodes")
+                .as_array()
+                .iter()
+                .filter_map(|n| n.as_usize())
+                .filter(|&n| n < nodes.len())
+                .collect()
+        };
+
+        Ok(GltfScene {
+            nodes,
+            roots,
+            meshes,
+            materials,
+            skins,
+            animations,
+            warnings: loader.warnings,
+        })
+    }
+
+    // World transform of every node, identity for nodes outside the hierarchy
+    pub fn world_matrices(&self) -> Vec<Matrix4> {
+        let mut world = vec![Matrix4::identity(); self.nodes.len()];
+        let mut stack: Vec<(usize, Matrix4)> = self
+            .roots
+            .iter()
+            .map(|&r| (r, Matrix4::identity()))
+            .collect();
+        while let Some((node, parent)) = stack.pop() {
+            world[node] = parent * self.nodes[node].local_matrix();
+            for &child in &self.nodes[node].children {
+                stack.push((child, world[node]));
+            }
+        }
+        world
+    }
+
+    // The whole scene posed and placed in world space, one entry per
+    // primitive with its material index, ready for DrawList::add
+    #[allow(dead_code)]
+    pub fn world_triangles(&self) -> Vec<(Vec<Triangle>, Option<usize>)> {
+        let world = self.world_matrices();
+        let mut out = vec![];
+        let mut stack: Vec<usize> = self.roots.clone();
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            stack.extend(&node.children);
+            let Some(mesh) = node.mesh.map(|m| &self.meshes[m]) else {
+                continue;
+            };
+            let joint_matrices = node.skin.map(|s| self.skins[s].joint_matrices(&world));
+
+            for primitive in &mesh.primitives {
+                let skinned = joint_matrices.as_ref().filter(|_| !primitive.joints.is_empty());
+                let corner_matrix = |corner: usize| -> Matrix4 {
+                    match skinned {
+                        // Skinned meshes ignore their node's own transform
+                        Some(joints) => {
+                            let mut m = Matrix4::from_cols([0.0; 16]);
+                            for k in 0..4 {
+                                let w = primitive.weights[corner][k];
+                                if let Some(joint) = joints.get(primitive.joints[corner][k])
+                                    && w != 0.0
+                                {
+                                    m = m.added(&joint.scaled(w));
+                                }
+                            }
+                            m
+                        }
+                        None => world[node_index],
+                    }
+                };
+                let place = |v: &Vertex, corner: usize| -> Vertex {
+                    let m = corner_matrix(corner);
+                    let mut placed = v.clone();
+                    placed.pos = m.transform_point(&v.pos);
+                    if v.normal.length() > 0.0 {
+                        placed.normal = m.transform_normal(&v.normal).normalize_v();
+                    }
+                    placed
+                };
+                let triangles = primitive
+                    .triangles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| Triangle::new(place(&t.v1, i * 3), place(&t.v2, i * 3 + 1), place(&t.v3, i * 3 + 2)))
+                    .collect();
+                out.push((triangles, primitive.material));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Four corners going round a unit square, then indices for a triangle
+    // list and for a strip zig-zagging across the square
+    const POSITIONS: [f32; 12] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0];
+    const INDICES: [u16; 8] = [0, 1, 2, 0, 1, 3, 2, 0];
+
+    fn buffer() -> Vec<u8> {
+        let mut bytes: Vec<u8> = POSITIONS.iter().flat_map(|f| f.to_le_bytes()).collect();
+        bytes.extend(INDICES.iter().flat_map(|i| i.to_le_bytes()));
+        bytes
+    }
+
+    fn base64_encode(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+            let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            for k in 0..4 {
+                if k <= chunk.len() {
+                    out.push(ALPHABET[(n >> (18 - 6 * k) & 63) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    // One triangle on node 0, moved by (1, 2, 3). Accessor 0 holds the
+    // corners, 1 the list indices, 4 the strip's, and 2 and 3 are wrong for
+    // most attributes.
+    // `uri` is spliced into buffer 0, empty for a .glb.
+    fn scene_json(uri: &str) -> String {
+        format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "buffers": [ {{ {uri} "byteLength": 64 }} ],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteLength": 48 }},
+                    {{ "buffer": 0, "byteOffset": 48, "byteLength": 6 }},
+                    {{ "buffer": 0, "byteOffset": 54, "byteLength": 8 }}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3" }},
+                    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+                    {{ "bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3" }},
+                    {{ "bufferView": 0, "componentType": 5126, "count": 12, "type": "SCALAR" }},
+                    {{ "bufferView": 2, "componentType": 5123, "count": 4, "type": "SCALAR" }}
+                ],
+                "meshes": [ {{ "primitives": [ {{ "attributes": {{ "POSITION": 0 }}, "indices": 1 }} ] }} ],
+                "nodes": [ {{ "mesh": 0, "translation": [1, 2, 3] }} ],
+                "scenes": [ {{ "nodes": [0] }} ]
+            }}"#
+        )
+    }
+
+    fn gltf(json: &str) -> std::io::Result<GltfScene> {
+        GltfScene::from_gltf_str(json, None)
+    }
+
+    fn embedded() -> String {
+        scene_json(&format!(r#""uri": "data:application/octet-stream;base64,{}","#, base64_encode(&buffer())))
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let chunk = |kind: u32, data: &[u8], pad: u8| {
+            let mut data = data.to_vec();
+            data.resize(data.len().next_multiple_of(4), pad);
+            let mut out = (data.len() as u32).to_le_bytes().to_vec();
+            out.extend(kind.to_le_bytes());
+            out.extend(data);
+            out
+        };
+        let mut body = chunk(0x4E4F534A, json.as_bytes(), b' ');
+        body.extend(chunk(0x004E4942, bin, 0));
+        let mut out = b"glTF".to_vec();
+        out.extend(2u32.to_le_bytes());
+        out.extend((12 + body.len() as u32).to_le_bytes());
+        out.extend(body);
+        out
+    }
+
+    fn positions(scene: &GltfScene) -> Vec<[f32; 3]> {
+        scene
+            .world_triangles()
+            .iter()
+            .flat_map(|(triangles, _)| triangles)
+            .flat_map(|t| [&t.v1, &t.v2, &t.v3])
+            .map(|v| [v.pos.x, v.pos.y, v.pos.z])
+            .collect()
+    }
+
+    fn error(result: std::io::Result<GltfScene>) -> String {
+        let e = result.err().expect("loaded");
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        e.to_string()
+    }
+
+    #[test]
+    fn decodes_base64_and_percent_escapes() {
+        assert_eq!(base64_decode("aGVsbG8gd29ybGQ=").unwrap(), b"hello world");
+        // URL-safe alphabet and line breaks
+        assert_eq!(base64_decode("+/8A\n").unwrap(), [0xFB, 0xFF, 0x00]);
+        assert_eq!(base64_decode("-_8A").unwrap(), [0xFB, 0xFF, 0x00]);
+        assert!(base64_decode("a*b").is_err());
+        assert_eq!(base64_decode(&base64_encode(&buffer())).unwrap(), buffer());
+        assert_eq!(percent_decode("my%20model%2Fbin.bin"), "my model/bin.bin");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+    }
+
+    #[test]
+    fn embedded_triangle() {
+        let scene = gltf(&embedded()).unwrap();
+        assert!(scene.warnings.is_empty());
+        assert_eq!(scene.roots, [0]);
+        assert_eq!(scene.meshes[0].primitives[0].triangles.len(), 1);
+        assert_eq!(positions(&scene), [[1.0, 2.0, 3.0], [2.0, 2.0, 3.0], [2.0, 3.0, 3.0]]);
+    }
+
+    #[test]
+    fn glb_matches_gltf() {
+        let json = scene_json("");
+        let scene = GltfScene::from_glb_bytes(&glb(&json, &buffer()), None).unwrap();
+        assert_eq!(positions(&scene), positions(&gltf(&embedded()).unwrap()));
+
+        let bytes = glb(&json, &buffer());
+        assert!(error(GltfScene::from_glb_bytes(&bytes[..bytes.len() - 4], None)).contains("chunk runs past"));
+        let mut version_1 = bytes.clone();
+        version_1[4] = 1;
+        assert!(GltfScene::from_glb_bytes(&version_1, None).is_err());
+        // BIN chunk missing
+        let mut json_only = glb(&json, &[]);
+        json_only.truncate(json_only.len() - 8);
+        assert!(error(GltfScene::from_glb_bytes(&json_only, None)).contains("no BIN chunk"));
+    }
+
+    #[test]
+    fn strips_and_fans_keep_the_winding() {
+        let square = |indices_and_mode: &str| {
+            let scene = gltf(&embedded().replace(r#""indices": 1"#, indices_and_mode)).unwrap();
+            scene.meshes[0].primitives[0].triangles.clone()
+        };
+        let winding = |tris: &[Triangle]| tris.iter().map(|t| t.face_normal().z).collect::<Vec<_>>();
+        // Corners in order make a fan
+        let fan = square(r#""mode": 6"#);
+        assert_eq!(winding(&fan), [1.0, 1.0]);
+        // Every other strip triangle is flipped back to match the first
+        let strip = square(r#""indices": 4, "mode": 5"#);
+        assert_eq!(winding(&strip), [1.0, 1.0]);
+        assert!(error(gltf(&embedded().replace(r#""indices": 1"#, r#""mode": 9"#))).contains("unknown mode 9"));
+    }
+
+    #[test]
+    fn rejects_cycles_and_shared_children() {
+        let nodes = |nodes: &str| embedded().replace(r#"[ { "mesh": 0, "translation": [1, 2, 3] } ]"#, nodes);
+        assert!(error(gltf(&nodes(r#"[ { "children": [0] } ]"#))).contains("node 0 is part of a cycle"));
+        assert!(error(gltf(&nodes(r#"[ { "children": [1] }, { "children": [0] } ]"#))).contains("cycle"));
+        // Under a cycle is just as unreachable
+        assert!(error(gltf(&nodes(r#"[ {}, { "children": [2] }, { "children": [1, 3] }, {} ]"#))).contains("cycle"));
+        assert!(error(gltf(&nodes(r#"[ { "children": [2] }, { "children": [2] }, {} ]"#)))
+            .contains("node 2 has more than one parent"));
+        assert!(error(gltf(&nodes(r#"[ { "children": [5] } ]"#))).contains("bad child"));
+        assert!(error(gltf(&nodes(r#"[ { "mesh": 3 } ]"#))).contains("bad mesh"));
+    }
+
+    #[test]
+    fn rejects_unsupported_extensions_and_versions() {
+        let with = |extra: &str| embedded().replacen('{', &format!("{{ {},", extra), 1);
+        let required = with(r#""extensionsRequired": ["KHR_draco_mesh_compression"]"#);
+        assert!(error(gltf(&required)).contains("required extension 'KHR_draco_mesh_compression'"));
+        assert!(gltf(&with(r#""extensionsRequired": ["KHR_materials_unlit"]"#)).is_ok());
+        let used = gltf(&with(r#""extensionsUsed": ["KHR_texture_transform"]"#)).unwrap();
+        assert_eq!(used.warnings, ["extension 'KHR_texture_transform' ignored"]);
+        assert!(error(gltf(&embedded().replace("2.0", "1.0"))).contains("only 2.x"));
+    }
+
+    #[test]
+    fn rejects_attributes_that_dont_fit() {
+        let attributes = |extra: &str| embedded().replace(r#""POSITION": 0"#, &format!(r#""POSITION": 0, {}"#, extra));
+        assert!(gltf(&attributes(r#""NORMAL": 0"#)).is_ok());
+        assert!(error(gltf(&attributes(r#""NORMAL": 2"#))).contains("NORMAL has fewer vertices than POSITION"));
+        assert!(error(gltf(&attributes(r#""COLOR_0": 3"#))).contains("COLOR_0 can't have 1 components"));
+        assert!(error(gltf(&attributes(r#""JOINTS_0": 0, "WEIGHTS_0": 0"#))).contains("JOINTS_0 can't have 3"));
+        assert!(error(gltf(&attributes(r#""TEXCOORD_0": 3"#))).contains("TEXCOORD_0 can't have 1"));
+        assert!(error(gltf(&embedded().replace(r#""POSITION": 0"#, r#""POSITION": 3"#))).contains("POSITION"));
+        assert!(error(gltf(&embedded().replace(r#""count": 3,"#, r#""count": 4,"#))).contains("runs past"));
+        let huge = embedded().replace(r#""count": 12,"#, r#""count": 18446744073709551615,"#);
+        let huge = huge.replace(r#""POSITION": 0"#, r#""POSITION": 0, "NORMAL": 3"#);
+        assert!(error(gltf(&huge)).contains("out of range"));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{noise::Perlin, texture::Texture};
+
+// Grid of heights, one per sample point. Sample (x, z) is row z, column x.
+#[derive(Clone, Debug)]
+pub struct Heightmap {
+    pub width: usize,
+    pub depth: usize,
+    pub heights: Vec<f32>,
+}
+
+impl Heightmap {
+    // Everything at one height, what `create_floor_rect` gives as a mesh
+    #[allow(dead_code)]
+    pub fn flat(width: usize, depth: usize, height: f32) -> Self {
+        Heightmap {
+            width: width.max(2),
+            depth: depth.max(2),
+            heights: vec![height; width.max(2) * depth.max(2)],
+        }
+    }
+
+    // Black is 0 and white is `max_height`. Colored images use their
+    // brightness. Needs at least 2x2 pixels to make one cell.
+    pub fn from_texture(texture: &Texture, max_height: f32) -> std::io::Result<Self> {
+        if texture.width < 2 || texture.height < 2 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "heightmap must be at least 2x2 pixels",
+            ));
+        }
+        let heights = texture
+            .pixels
+            .iter()
+            .map(|c| (c.r as f32 * 0.299 + c.g as f32 * 0.587 + c.b as f32 * 0.114) / 255.0 * max_height)
+            .collect();
+        Ok(Heightmap {
+            width: texture.width as usize,
+            depth: texture.height as usize,
+            heights,
+        })
+    }
+
+    pub fn from_png_file(path: &str, max_height: f32) -> std::io::Result<Self> {
+        Self::from_texture(&Texture::from_png_file(path)?, max_height)
+    }
+
+    // fBm noise, `frequency` is noise cycles per sample and heights span
+    // roughly -amplitude..amplitude
+    pub fn from_noise(
+        width: usize,
+        depth: usize,
+        noise: &Perlin,
+        frequency: f32,
+        octaves: u32,
+        amplitude: f32,
+    ) -> Self {
+        let (width, depth) = (width.max(2), depth.max(2));
+        let mut heights = Vec::with_capacity(width * depth);
+        for z in 0..depth {
+            for x in 0..width {
+                let n = noise.fbm(x as f32 * frequency, z as f32 * frequency, octaves, 2.0, 0.5);
+                heights.push(n * amplitude);
+            }
+        }
+        Heightmap { width, depth, heights }
+    }
+
+    // Clamped to the edges
+    pub fn get(&self, x: usize, z: usize) -> f32 {
+        self.heights[z.min(self.depth - 1) * self.width + x.min(self.width - 1)]
+    }
+
+    #[allow(dead_code)]
+    pub fn set(&mut self, x: usize, z: usize, height: f32) {
+        if x < self.width && z < self.depth {
+            self.heights[z * self.width + x] = height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn textures_need_a_whole_cell() {
+        for (width, height) in [(1, 1), (1, 4), (4, 1), (0, 0)] {
+            assert!(Heightmap::from_texture(&Texture::new(width, height), 1.0).is_err());
+        }
+        let mut texture = Texture::new(2, 3);
+        *texture.get_pixel_mut(1, 2) = Color::new(255, 255, 255, 255);
+        let heightmap = Heightmap::from_texture(&texture, 4.0).unwrap();
+        assert_eq!((heightmap.width, heightmap.depth), (2, 3));
+        assert_eq!(heightmap.get(0, 0), 0.0);
+        assert!((heightmap.get(1, 2) - 4.0).abs() < 1e-4);
+        // Clamped past the edge
+        assert_eq!(heightmap.get(5, 9), heightmap.get(1, 2));
+    }
+}
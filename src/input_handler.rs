@@ -9,6 +9,13 @@ pub struct InputHandler {
     pub space: KeyState,
     
     pub shift: KeyState,
+    pub ctrl: KeyState,
+    pub tab: KeyState,
+    pub enter: KeyState,
+    pub m: KeyState,
+    pub f: KeyState,
+    pub g: KeyState,
+    pub p: KeyState,
     
     pub w: KeyState,
     pub s: KeyState,
@@ -26,6 +33,12 @@ pub struct InputHandler {
     pub scroll_y: i32,
 }
 
+static UNBOUND_KEY: KeyState = KeyState {
+    click: false,
+    pressed: false,
+    released: false,
+};
+
 //get more useful input from events
 impl InputHandler {
     pub fn new() -> Self {
@@ -36,6 +49,13 @@ impl InputHandler {
             right: KeyState::new(),
             space: KeyState::new(),
             shift: KeyState::new(),
+            ctrl: KeyState::new(),
+            tab: KeyState::new(),
+            enter: KeyState::new(),
+            m: KeyState::new(),
+            f: KeyState::new(),
+            g: KeyState::new(),
+            p: KeyState::new(),
             w: KeyState::new(),
             a: KeyState::new(),
             s: KeyState::new(),
@@ -52,35 +72,76 @@ impl InputHandler {
     }
 
     pub fn new_frame(&mut self) {
-        self.mouse_left.click = false;
-        self.mouse_right.click = false;
-        self.mouse_middle.click = false;
-        self.mouse_left.released = false;
-        self.mouse_right.released = false;
-        self.mouse_middle.released = false;
-        self.mouse_delta.x = self.mouse_pos.x - self.mouse_pos_last.x;
-        self.mouse_delta.y = self.mouse_pos.y - self.mouse_pos_last.y;
+        for key in [
+            &mut self.up,
+            &mut self.down,
+            &mut self.left,
+            &mut self.right,
+            &mut self.space,
+            &mut self.shift,
+            &mut self.ctrl,
+            &mut self.tab,
+            &mut self.enter,
+            &mut self.m,
+            &mut self.f,
+            &mut self.g,
+            &mut self.p,
+            &mut self.w,
+            &mut self.s,
+            &mut self.a,
+            &mut self.d,
+            &mut self.mouse_left,
+            &mut self.mouse_middle,
+            &mut self.mouse_right,
+        ] {
+            key.click = false;
+            key.released = false;
+        }
+        self.mouse_delta.x = 0.0;
+        self.mouse_delta.y = 0.0;
         self.scroll_y = 0;
     }
 
+    pub fn mouse_button(&self, btn: MouseButton) -> &KeyState {
+        match btn {
+            MouseButton::Left => &self.mouse_left,
+            MouseButton::Middle => &self.mouse_middle,
+            MouseButton::Right => &self.mouse_right,
+            MouseButton::Unknown => &UNBOUND_KEY,
+        }
+    }
+
     pub fn handle_key_event(&mut self, key_ev: &KeyEvent) {
         let state = matches!(key_ev, KeyEvent::Pressed { .. });
-        let key_held = match key_ev {
+        let key_state = match key_ev {
             KeyEvent::Pressed { key } | KeyEvent::Released { key } => match key {
-                KeyCode::Up => &mut self.up.pressed,
-                KeyCode::Down => &mut self.down.pressed,
-                KeyCode::Left => &mut self.left.pressed,
-                KeyCode::Right => &mut self.right.pressed,
-                KeyCode::Space => &mut self.space.pressed,
-                KeyCode::LShift => &mut self.shift.pressed,
-                KeyCode::W => &mut self.w.pressed,
-                KeyCode::S => &mut self.s.pressed,
-                KeyCode::A => &mut self.a.pressed,
-                KeyCode::D => &mut self.d.pressed,
+                KeyCode::Up => &mut self.up,
+                KeyCode::Down => &mut self.down,
+                KeyCode::Left => &mut self.left,
+                KeyCode::Right => &mut self.right,
+                KeyCode::Space => &mut self.space,
+                KeyCode::LShift => &mut self.shift,
+                KeyCode::LCtrl => &mut self.ctrl,
+                KeyCode::Tab => &mut self.tab,
+                KeyCode::Return => &mut self.enter,
+                KeyCode::M => &mut self.m,
+                KeyCode::F => &mut self.f,
+                KeyCode::G => &mut self.g,
+                KeyCode::P => &mut self.p,
+                KeyCode::W => &mut self.w,
+                KeyCode::S => &mut self.s,
+                KeyCode::A => &mut self.a,
+                KeyCode::D => &mut self.d,
                 _ => return,
             },
         };
-        *key_held = state;
+        if state && !key_state.pressed {
+            key_state.click = true;
+        }
+        if !state && key_state.pressed {
+            key_state.released = true;
+        }
+        key_state.pressed = state;
     }
 
     pub fn handle_mouse_event(&mut self, mouse_ev: &MouseEvent) {
@@ -143,11 +204,15 @@ impl InputHandler {
                     _ => {}
                 }
             }
-            MouseEvent::NewPosition { x, y } => {
+            MouseEvent::NewPosition { x, y, xrel, yrel } => {
                 self.mouse_pos_last.x = self.mouse_pos.x;
                 self.mouse_pos_last.y = self.mouse_pos.y;
                 self.mouse_pos.x = (*x as f32).clamp(0.0, SCREEN_WIDTH as f32);
                 self.mouse_pos.y = (*y as f32).clamp(0.0, SCREEN_HEIGHT as f32);
+                // Accumulates every move since the last new_frame. Uses the raw
+                // relative motion so it keeps working with a captured mouse.
+                self.mouse_delta.x += *xrel as f32;
+                self.mouse_delta.y += *yrel as f32;
             }
             MouseEvent::WheelScroll { y } => {
                 self.scroll_y = if *y > 0 { 1 } else if *y < 0 { -1 } else { 0 };
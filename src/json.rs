@@ -0,0 +1,339 @@
+// Just enough JSON for reading glTF files: the full grammar, numbers as f64,
+// objects keeping their key order
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+static NULL: JsonValue = JsonValue::Null;
+
+impl JsonValue {
+    // Errors say which line the problem is on
+    pub fn parse(text: &str) -> Result<JsonValue, String> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            pos: 0,
+        };
+        parser.skip_whitespace();
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.bytes.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    // Member of an object, Null when missing or not an object
+    pub fn get(&self, key: &str) -> &JsonValue {
+        match self {
+            JsonValue::Object(members) => members
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&NULL, |(_, v)| v),
+            _ => &NULL,
+        }
+    }
+
+    // Element of an array, Null when out of range or not an array
+    pub fn at(&self, index: usize) -> &JsonValue {
+        match self {
+            JsonValue::Array(items) => items.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.as_f64().map(|n| n as f32)
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            JsonValue::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    // Empty for anything that isn't an array
+    pub fn as_array(&self) -> &[JsonValue] {
+        match self {
+            JsonValue::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    // Array of numbers, None if anything in it isn't one
+    pub fn as_f32_vec(&self) -> Option<Vec<f32>> {
+        match self {
+            JsonValue::Array(items) => items.iter().map(|v| v.as_f32()).collect(),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> String {
+        let consumed = &self.bytes[..self.pos.min(self.bytes.len())];
+        let line = consumed.iter().filter(|&&b| b == b'\n').count() + 1;
+        format!("line {}: {}", line, msg)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect_word(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, String> {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(JsonValue::String(self.string()?)),
+            Some(b't') => self.expect_word("true", JsonValue::Bool(true)),
+            Some(b'f') => self.expect_word("false", JsonValue::Bool(false)),
+            Some(b'n') => self.expect_word("null", JsonValue::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue, String> {
+        self.pos += 1;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, String> {
+        self.pos += 1;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .filter(|d| d.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("bad \\u escape"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut out = String::new();
+        let mut run_start = self.pos;
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    out.push_str(&String::from_utf8_lossy(&self.bytes[run_start..self.pos]));
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    out.push_str(&String::from_utf8_lossy(&self.bytes[run_start..self.pos]));
+                    self.pos += 1;
+                    let escape = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match escape {
+                        b'"' => out.push('"'),
+                        b'\\' => out.push('\\'),
+                        b'/' => out.push('/'),
+                        b'b' => out.push('\u{8}'),
+                        b'f' => out.push('\u{c}'),
+                        b'n' => out.push('\n'),
+                        b'r' => out.push('\r'),
+                        b't' => out.push('\t'),
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            // Surrogate pair. A high half without its low
+                            // half, or the other way round, isn't a character
+                            // and comes out as U+FFFD.
+                            if (0xD800..0xDC00).contains(&code) && self.bytes[self.pos..].starts_with(b"\\u") {
+                                let after = self.pos;
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                if (0xDC00..0xE000).contains(&low) {
+                                    code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                                } else {
+                                    self.pos = after;
+                                }
+                            }
+                            out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        }
+                        _ => return Err(self.error("bad escape")),
+                    }
+                    run_start = self.pos;
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<JsonValue, String> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .map(JsonValue::Number)
+            .ok_or_else(|| self.error("bad number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(json: &str) -> String {
+        JsonValue::parse(json).unwrap().as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn values_keep_their_order() {
+        let value = JsonValue::parse(r#" { "b": [1, -2.5e1, true, null], "a": { "c": false } } "#).unwrap();
+        let JsonValue::Object(members) = &value else {
+            panic!("not an object");
+        };
+        let keys: Vec<&str> = members.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["b", "a"]);
+        assert_eq!(value.get("b").as_f32_vec(), None);
+        assert_eq!(value.get("b").at(1).as_f64(), Some(-25.0));
+        assert_eq!(value.get("b").at(2).as_bool(), Some(true));
+        assert!(value.get("b").at(3).is_null());
+        assert!(value.get("b").at(4).is_null());
+        assert_eq!(value.get("a").get("c").as_bool(), Some(false));
+        assert!(value.get("missing").get("deeper").is_null());
+        assert_eq!(value.get("b").at(0).as_usize(), Some(1));
+        assert_eq!(value.get("b").at(1).as_usize(), None);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(string(r#""a\"b\\c\/d\b\f\n\r\t""#), "a\"b\\c/d\u{8}\u{c}\n\r\t");
+        assert_eq!(string(r#""caf\u00e9 \u00C9""#), "caf\u{e9} \u{c9}");
+        assert_eq!(string("\"raw \u{e9}\""), "raw \u{e9}");
+        assert!(JsonValue::parse(r#""\x""#).is_err());
+        assert!(JsonValue::parse(r#""\u12g4""#).is_err());
+        assert!(JsonValue::parse(r#""\u+123""#).is_err());
+    }
+
+    #[test]
+    fn surrogates() {
+        assert_eq!(string(r#""\ud83d\ude00""#), "\u{1F600}");
+        // Unpaired halves
+        assert_eq!(string(r#""\ud83d""#), "\u{FFFD}");
+        assert_eq!(string(r#""\ude00x""#), "\u{FFFD}x");
+        assert_eq!(string(r#""\ud83dA""#), "\u{FFFD}A");
+        assert_eq!(string(r#""\ud83d\u0041""#), "\u{FFFD}A");
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let three_lines = "{\n  \"a\": 1,\n  \"b\": tru\n}";
+        assert_eq!(JsonValue::parse(three_lines), Err("line 3: unexpected character".to_string()));
+        assert_eq!(JsonValue::parse("[1,\n2\n"), Err("line 3: expected ',' or ']'".to_string()));
+        assert_eq!(JsonValue::parse("{\"a\" 1}"), Err("line 1: expected ':'".to_string()));
+        assert_eq!(JsonValue::parse("\n\n\"open"), Err("line 3: unterminated string".to_string()));
+        assert_eq!(JsonValue::parse("1 2"), Err("line 1: trailing characters".to_string()));
+        assert_eq!(JsonValue::parse("[1.2.3]"), Err("line 1: bad number".to_string()));
+        assert_eq!(JsonValue::parse(""), Err("line 1: unexpected end of input".to_string()));
+    }
+}
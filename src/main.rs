@@ -1,5 +1,7 @@
 //#![allow(warnings)]
 
+use crt_pass::CrtPass;
+use post_process::{BloomPass, VignettePass};
 use screen::Screen;
 use sdl2win::SDL2Window;
 use nameless_3d_game::Nameless3DThing;
@@ -8,32 +10,74 @@ use window::Window;
 mod triangle_gen;
 mod input_handler;
 mod texture;
+mod transform;
+mod animation;
+mod billboard;
+mod billboard_batch;
+mod bounds;
 mod camera;
+mod camera_path;
+mod camera_rig;
+mod camera_shake;
+mod cel_shader;
 mod color;
+mod crt_pass;
+mod csg;
+mod debug_draw;
+mod depth_encoding;
 mod dither_shader;
 mod draw_list;
 mod dummy_passthru_shader;
 mod even_line_missing_shader;
 mod everything_is_red_shader;
+mod fly_controller;
+mod fog;
+mod frustum;
 mod game;
+mod game_clock;
+mod gltf_scene;
+mod heightmap;
+mod json;
 mod key_event;
 mod keycode;
+mod material;
+mod matrix4;
+mod mesh;
+mod mesh_export;
+mod mesh_processing;
 mod mouse_button;
 mod mouse_event;
+mod noise;
+mod obj_model;
+mod ordered_dither_shader;
+mod palette;
 mod pixel_placement;
 mod pixel_shader;
+mod post_process;
+mod quaternion;
+mod ray;
 mod rect;
+mod scene_node;
 mod screen;
 mod sdl2win;
 mod nameless_3d_game;
+mod orbit_controller;
+mod sprite_sheet;
+mod terrain;
+mod terrain_splat;
 mod triangle;
 mod vec2;
 mod vec3;
 mod vertex;
+mod viewport;
 mod window;
 
 fn main() {
     let mut screen = Screen::new();
+    screen.post_process.push("bloom", Box::new(BloomPass::new(200.0, 0.6)));
+    screen.post_process.push("vignette", Box::new(VignettePass::new(0.6)));
+    screen.post_process.push("crt", Box::new(CrtPass::new()));
+    screen.post_process.set_enabled("crt", false);
     let win = SDL2Window;
     let mut game = Nameless3DThing::new();
     win.start(&mut screen, &mut game);
@@ -0,0 +1,102 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{color::Color, texture::Texture};
+
+// Surface description from a Wavefront MTL file. Only the diffuse part is
+// used, the renderer has no specular lighting.
+pub struct Material {
+    pub name: String,
+    // Kd, with d (or 1 - Tr) as alpha
+    pub diffuse: Color,
+    // map_Kd
+    pub diffuse_map: Option<Texture>,
+}
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        Material {
+            name: name.to_string(),
+            diffuse: Color::new(255, 255, 255, 255),
+            diffuse_map: None,
+        }
+    }
+
+    pub fn from_mtl_file(file_path: &str) -> std::io::Result<Vec<Material>> {
+        let text = std::fs::read_to_string(file_path)?;
+        let base_dir = Path::new(file_path).parent();
+        Self::from_mtl_str(&text, base_dir).map_err(|e| {
+            Error::new(e.kind(), format!("{}: {}", file_path, e))
+        })
+    }
+
+    // Texture paths are looked up relative to `base_dir`, or the working
+    // directory when there is none
+    pub fn from_mtl_str(text: &str, base_dir: Option<&Path>) -> std::io::Result<Vec<Material>> {
+        let invalid = |line: usize, msg: &str| {
+            Error::new(ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+        };
+        let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        let mut materials: Vec<Material> = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let keyword = parts[0];
+            if keyword == "newmtl" {
+                let name = line["newmtl".len()..].trim();
+                if name.is_empty() {
+                    return Err(invalid(line_no, "newmtl needs a name"));
+                }
+                materials.push(Material::new(name));
+                continue;
+            }
+
+            let Some(material) = materials.last_mut() else {
+                if matches!(keyword, "Kd" | "d" | "Tr" | "map_Kd") {
+                    return Err(invalid(line_no, &format!("{} before any newmtl", keyword)));
+                }
+                continue;
+            };
+            let number = |index: usize| -> std::io::Result<f32> {
+                parts
+                    .get(index)
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| invalid(line_no, &format!("bad number in {}", keyword)))
+            };
+            match keyword {
+                "Kd" => {
+                    let r = number(1)?;
+                    // A single value means grey
+                    let (g, b) = if parts.len() >= 4 { (number(2)?, number(3)?) } else { (r, r) };
+                    material.diffuse = Color::new(to_byte(r), to_byte(g), to_byte(b), material.diffuse.a);
+                }
+                "d" => material.diffuse.a = to_byte(number(1)?),
+                "Tr" => material.diffuse.a = to_byte(1.0 - number(1)?),
+                "map_Kd" => {
+                    // Options such as -s or -o come first, the file name is last
+                    let Some(file) = parts.last().filter(|_| parts.len() > 1) else {
+                        return Err(invalid(line_no, "map_Kd needs a file name"));
+                    };
+                    let full_path = match base_dir {
+                        Some(dir) => dir.join(file),
+                        None => Path::new(file).to_path_buf(),
+                    };
+                    let texture = Texture::from_png_file(&full_path.to_string_lossy()).map_err(|e| {
+                        invalid(line_no, &format!("can't load '{}': {}", full_path.display(), e))
+                    })?;
+                    material.diffuse_map = Some(texture);
+                }
+                // Ambient, specular, illumination model and the rest don't apply here
+                _ => {}
+            }
+        }
+        Ok(materials)
+    }
+}
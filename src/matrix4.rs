@@ -0,0 +1,135 @@
+use std::ops::Mul;
+
+use crate::{quaternion::Quaternion, vec3::Vector3};
+
+// 4x4 affine transform, column-major like glTF: m[column * 4 + row]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub m: [f32; 16],
+}
+
+impl Matrix4 {
+    pub fn identity() -> Self {
+        let mut m = [0.0; 16];
+        m[0] = 1.0;
+        m[5] = 1.0;
+        m[10] = 1.0;
+        m[15] = 1.0;
+        Matrix4 { m }
+    }
+
+    pub fn from_cols(m: [f32; 16]) -> Self {
+        Matrix4 { m }
+    }
+
+    // Scale, then rotate, then translate
+    pub fn from_trs(translation: &Vector3, rotation: &Quaternion, scale: &Vector3) -> Self {
+        let Quaternion { x, y, z, w } = rotation.normalize();
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        let (xy, xz, yz) = (x * y, x * z, y * z);
+        let (wx, wy, wz) = (w * x, w * y, w * z);
+        Matrix4 {
+            m: [
+                (1.0 - 2.0 * (yy + zz)) * scale.x,
+                2.0 * (xy + wz) * scale.x,
+                2.0 * (xz - wy) * scale.x,
+                0.0,
+                2.0 * (xy - wz) * scale.y,
+                (1.0 - 2.0 * (xx + zz)) * scale.y,
+                2.0 * (yz + wx) * scale.y,
+                0.0,
+                2.0 * (xz + wy) * scale.z,
+                2.0 * (yz - wx) * scale.z,
+                (1.0 - 2.0 * (xx + yy)) * scale.z,
+                0.0,
+                translation.x,
+                translation.y,
+                translation.z,
+                1.0,
+            ],
+        }
+    }
+
+    pub fn transform_point(&self, p: &Vector3) -> Vector3 {
+        let m = &self.m;
+        Vector3::new(
+            m[0] * p.x + m[4] * p.y + m[8] * p.z + m[12],
+            m[1] * p.x + m[5] * p.y + m[9] * p.z + m[13],
+            m[2] * p.x + m[6] * p.y + m[10] * p.z + m[14],
+        )
+    }
+
+    // Ignores translation
+    #[allow(dead_code)]
+    pub fn transform_vector(&self, v: &Vector3) -> Vector3 {
+        let m = &self.m;
+        Vector3::new(
+            m[0] * v.x + m[4] * v.y + m[8] * v.z,
+            m[1] * v.x + m[5] * v.y + m[9] * v.z,
+            m[2] * v.x + m[6] * v.y + m[10] * v.z,
+        )
+    }
+
+    // For normals: the inverse transpose of the upper 3x3, so non-uniform
+    // scale doesn't tilt them. Not normalized.
+    pub fn transform_normal(&self, n: &Vector3) -> Vector3 {
+        let m = &self.m;
+        let (a, b, c) = (m[0], m[4], m[8]);
+        let (d, e, f) = (m[1], m[5], m[9]);
+        let (g, h, i) = (m[2], m[6], m[10]);
+        // Cofactors, which are the inverse transpose up to the determinant's
+        // scale; only the sign of the determinant matters for direction
+        let cof = [
+            e * i - f * h,
+            f * g - d * i,
+            d * h - e * g,
+            c * h - b * i,
+            a * i - c * g,
+            b * g - a * h,
+            b * f - c * e,
+            c * d - a * f,
+            a * e - b * d,
+        ];
+        let det = a * cof[0] + b * cof[1] + c * cof[2];
+        let sign = if det < 0.0 { -1.0 } else { 1.0 };
+        Vector3::new(
+            (cof[0] * n.x + cof[1] * n.y + cof[2] * n.z) * sign,
+            (cof[3] * n.x + cof[4] * n.y + cof[5] * n.z) * sign,
+            (cof[6] * n.x + cof[7] * n.y + cof[8] * n.z) * sign,
+        )
+    }
+
+    // Weighted sum, for blending skinning matrices
+    pub fn scaled(&self, s: f32) -> Self {
+        let mut m = self.m;
+        for v in &mut m {
+            *v *= s;
+        }
+        Matrix4 { m }
+    }
+
+    pub fn added(&self, other: &Matrix4) -> Self {
+        let mut m = self.m;
+        for (v, o) in m.iter_mut().zip(&other.m) {
+            *v += o;
+        }
+        Matrix4 { m }
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    // Applies `rhs` first, then `self`
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut m = [0.0; 16];
+        for col in 0..4 {
+            for row in 0..4 {
+                m[col * 4 + row] = (0..4)
+                    .map(|k| self.m[k * 4 + row] * rhs.m[col * 4 + k])
+                    .sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
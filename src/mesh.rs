@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use crate::{
+    bounds::{Aabb, BoundingSphere}, camera::Camera, pixel_shader::PixelShader, ray::{Ray, TriangleHit}, screen::Screen,
+    texture::Texture, triangle::{Triangle, VertexProjector}, vertex::Vertex,
+};
+
+// Triangles sharing their corners: every three indices pick the vertices of
+// one triangle, wound the same way `Triangle` is
+#[derive(Clone)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Mesh {
+            vertices: vec![],
+            indices: vec![],
+        }
+    }
+
+    // Corners that are exactly the same become one vertex
+    pub fn from_triangles(triangles: &[Triangle]) -> Self {
+        let mut mesh = Mesh::new();
+        mesh.add_triangles(triangles);
+        mesh
+    }
+
+    pub fn add_triangles(&mut self, triangles: &[Triangle]) {
+        // Only dedupes against what's being added, so earlier vertices keep
+        // their indices
+        let mut seen: HashMap<[u32; 16], u32> = HashMap::new();
+        for tri in triangles {
+            for vertex in [&tri.v1, &tri.v2, &tri.v3] {
+                let index = *seen.entry(exact_key(vertex)).or_insert_with(|| {
+                    self.vertices.push(vertex.clone());
+                    (self.vertices.len() - 1) as u32
+                });
+                self.indices.push(index);
+            }
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn triangle(&self, index: usize) -> Option<Triangle> {
+        let corners = self.indices.get(index * 3..index * 3 + 3)?;
+        Some(Triangle::new(
+            self.vertices[corners[0] as usize].clone(),
+            self.vertices[corners[1] as usize].clone(),
+            self.vertices[corners[2] as usize].clone(),
+        ))
+    }
+
+    pub fn to_triangles(&self) -> Vec<Triangle> {
+        (0..self.triangle_count()).filter_map(|i| self.triangle(i)).collect()
+    }
+
+    pub fn bounds(&self) -> BoundingSphere {
+        BoundingSphere::from_aabb(&Aabb::from_points(self.vertices.iter().map(|v| &v.pos)))
+    }
+
+    // Merges vertices whose positions, normals, tangents and texture
+    // coordinates are within `epsilon` of each other and whose colors match, snapping them to
+    // a grid of that size. Triangles that collapse are dropped, and so are
+    // vertices nothing uses any more.
+    #[allow(dead_code)]
+    pub fn weld(&mut self, epsilon: f32) {
+        let epsilon = epsilon.max(f32::MIN_POSITIVE);
+        let snap = |v: f32| (v / epsilon).round() as i64;
+        let mut merged: HashMap<[i64; 16], u32> = HashMap::new();
+        let mut vertices = vec![];
+        let remap: Vec<u32> = self
+            .vertices
+            .iter()
+            .map(|v| {
+                let key = [
+                    snap(v.pos.x),
+                    snap(v.pos.y),
+                    snap(v.pos.z),
+                    snap(v.texture_coord.x),
+                    snap(v.texture_coord.y),
+                    snap(v.normal.x),
+                    snap(v.normal.y),
+                    snap(v.normal.z),
+                    snap(v.tangent.x),
+                    snap(v.tangent.y),
+                    snap(v.tangent.z),
+                    v.tangent_sign.signum() as i64,
+                    v.color.r as i64,
+                    v.color.g as i64,
+                    v.color.b as i64,
+                    v.color.a as i64,
+                ];
+                *merged.entry(key).or_insert_with(|| {
+                    vertices.push(v.clone());
+                    (vertices.len() - 1) as u32
+                })
+            })
+            .collect();
+
+        let mut indices = Vec::with_capacity(self.indices.len());
+        for corners in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| remap[corners[i] as usize]);
+            if a != b && b != c && a != c {
+                indices.extend([a, b, c]);
+            }
+        }
+        self.vertices = vertices;
+        self.indices = indices;
+        self.remove_unused_vertices();
+    }
+
+    pub fn remove_unused_vertices(&mut self) {
+        let mut remap = vec![u32::MAX; self.vertices.len()];
+        let mut vertices = vec![];
+        for index in &mut self.indices {
+            let old = *index as usize;
+            if remap[old] == u32::MAX {
+                remap[old] = vertices.len() as u32;
+                vertices.push(self.vertices[old].clone());
+            }
+            *index = remap[old];
+        }
+        self.vertices = vertices;
+    }
+
+    // Nearest hit and the index of the triangle it was on
+    pub fn raycast(&self, ray: &Ray) -> Option<(usize, TriangleHit)> {
+        let mut nearest: Option<(usize, TriangleHit)> = None;
+        for (i, corners) in self.indices.chunks_exact(3).enumerate() {
+            let [a, b, c] = [0, 1, 2].map(|k| &self.vertices[corners[k] as usize].pos);
+            if let Some(hit) = ray.intersect_corners(a, b, c)
+                && nearest.is_none_or(|(_, n)| hit.distance < n.distance)
+            {
+                nearest = Some((i, hit));
+            }
+        }
+        nearest
+    }
+
+    // Each vertex is projected at most once, however many triangles share
+    // it, and only if some triangle uses it
+    pub fn draw(&self, screen: &mut Screen, cam: &Camera, shader: &dyn PixelShader, texture: &Texture) {
+        let triangle_count = self.triangle_count();
+        screen.cull_stats.triangles_submitted += triangle_count;
+        screen.depth_range = (cam.near_plane, cam.far_plane);
+        let Some(projector) = VertexProjector::new(cam, &screen.viewport()) else {
+            return;
+        };
+
+        // Post-transform cache: None until projected, then whatever the
+        // projection gave, which is None again for clipped vertices
+        let mut projected: Vec<Option<Option<(Vertex, f32)>>> = vec![None; self.vertices.len()];
+        for corners in self.indices.chunks_exact(3) {
+            let mut screen_corners = [None, None, None];
+            for (k, &index) in corners.iter().enumerate() {
+                let cached = projected[index as usize]
+                    .get_or_insert_with(|| projector.project(&self.vertices[index as usize]));
+                screen_corners[k] = cached.clone();
+            }
+            let [Some((p1, w1)), Some((p2, w2)), Some((p3, w3))] = screen_corners else {
+                continue;
+            };
+
+            let [v1, v2, v3] = [0, 1, 2].map(|k| self.vertices[corners[k] as usize].clone());
+            let world = Triangle::new(v1, v2, v3);
+            Triangle::new(p1, p2, p3).fill(&world, &[w1, w2, w3], screen, shader, texture);
+            screen.cull_stats.triangles_drawn += 1;
+        }
+    }
+}
+
+// Bit patterns of everything in a vertex, so only identical ones match
+fn exact_key(v: &Vertex) -> [u32; 16] {
+    let c = &v.color;
+    [
+        v.pos.x.to_bits(),
+        v.pos.y.to_bits(),
+        v.pos.z.to_bits(),
+        v.texture_coord.x.to_bits(),
+        v.texture_coord.y.to_bits(),
+        v.normal.x.to_bits(),
+        v.normal.y.to_bits(),
+        v.normal.z.to_bits(),
+        v.tangent.x.to_bits(),
+        v.tangent.y.to_bits(),
+        v.tangent.z.to_bits(),
+        v.tangent_sign.to_bits(),
+        c.r as u32,
+        c.g as u32,
+        c.b as u32,
+        c.a as u32,
+    ]
+}
@@ -0,0 +1,415 @@
+use std::{fmt::Write as _, path::Path};
+
+use crate::{material::Material, mesh::Mesh, vec3::Vector3, vertex::Vertex};
+
+// Writers for handing meshes to other tools. Every format gets positions;
+// what else survives depends on the format, see each function.
+pub struct MeshExport;
+
+impl MeshExport {
+    // Wavefront OBJ with positions, vertex colors (RGB after the position,
+    // which `ObjModel` reads back), texture coordinates and normals.
+    // `mtl_file` and `material` add mtllib and usemtl lines.
+    pub fn obj_string(mesh: &Mesh, mtl_file: Option<&str>, material: Option<&str>) -> String {
+        let mut out = String::new();
+        if let Some(mtl_file) = mtl_file {
+            let _ = writeln!(out, "mtllib {}", mtl_file);
+        }
+        for v in &mesh.vertices {
+            let c = &v.color;
+            let _ = writeln!(
+                out,
+                "v {} {} {} {} {} {}",
+                v.pos.x,
+                v.pos.y,
+                v.pos.z,
+                c.r as f32 / 255.0,
+                c.g as f32 / 255.0,
+                c.b as f32 / 255.0
+            );
+        }
+        // OBJ puts v = 0 at the bottom of the image, the engine at the top
+        for v in &mesh.vertices {
+            let _ = writeln!(out, "vt {} {}", v.texture_coord.x, 1.0 - v.texture_coord.y);
+        }
+        for v in &mesh.vertices {
+            let _ = writeln!(out, "vn {} {} {}", v.normal.x, v.normal.y, v.normal.z);
+        }
+        if let Some(material) = material {
+            let _ = writeln!(out, "usemtl {}", material);
+        }
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0] + 1, tri[1] + 1, tri[2] + 1];
+            let _ = writeln!(out, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}");
+        }
+        out
+    }
+
+    // One material. `map_file` is where the diffuse map has been or will be
+    // saved, relative to the .mtl.
+    pub fn mtl_string(material: &Material, map_file: Option<&str>) -> String {
+        let c = &material.diffuse;
+        let mut out = format!(
+            "newmtl {}\nKd {} {} {}\nd {}\n",
+            material.name,
+            c.r as f32 / 255.0,
+            c.g as f32 / 255.0,
+            c.b as f32 / 255.0,
+            c.a as f32 / 255.0
+        );
+        if let Some(map_file) = map_file {
+            let _ = writeln!(out, "map_Kd {}", map_file);
+        }
+        out
+    }
+
+    // Writes `path`, and with a material a .mtl next to it plus the diffuse
+    // map as a PNG if the material has one. The PNG is named after the
+    // material with anything but letters, digits, '-' and '_' made '_', so
+    // names with spaces or slashes still make one file next to the .obj.
+    #[allow(dead_code)]
+    pub fn save_obj(mesh: &Mesh, material: Option<&Material>, path: &str) -> std::io::Result<()> {
+        let path = Path::new(path);
+        let Some(material) = material else {
+            return std::fs::write(path, Self::obj_string(mesh, None, None));
+        };
+
+        let stem = path.file_stem().map_or("mesh".into(), |s| s.to_string_lossy());
+        let mtl_file = format!("{}.mtl", stem);
+        let map_file = material
+            .diffuse_map
+            .as_ref()
+            .map(|_| format!("{}_{}.png", stem, file_name_part(&material.name)));
+        if let (Some(texture), Some(map_file)) = (&material.diffuse_map, &map_file) {
+            texture.save_png_file(&path.with_file_name(map_file).to_string_lossy())?;
+        }
+        std::fs::write(path.with_file_name(&mtl_file), Self::mtl_string(material, map_file.as_deref()))?;
+        std::fs::write(path, Self::obj_string(mesh, Some(&mtl_file), Some(&material.name)))
+    }
+
+    // ASCII STL: positions and face normals only, which is all slicers need
+    pub fn stl_ascii_string(mesh: &Mesh, name: &str) -> String {
+        let mut out = format!("solid {}\n", name);
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| &mesh.vertices[tri[k] as usize]);
+            let n = facet_normal(a, b, c);
+            let _ = writeln!(out, "  facet normal {} {} {}", n.x, n.y, n.z);
+            let _ = writeln!(out, "    outer loop");
+            for v in [a, b, c] {
+                let _ = writeln!(out, "      vertex {} {} {}", v.pos.x, v.pos.y, v.pos.z);
+            }
+            let _ = writeln!(out, "    endloop");
+            let _ = writeln!(out, "  endfacet");
+        }
+        let _ = writeln!(out, "endsolid {}", name);
+        out
+    }
+
+    // Binary STL: 80 byte header, triangle count, then 50 bytes a triangle
+    pub fn stl_binary_bytes(mesh: &Mesh) -> Vec<u8> {
+        let count = mesh.triangle_count();
+        let mut out = Vec::with_capacity(84 + count * 50);
+        let mut header = [0u8; 80];
+        let title = b"binary STL";
+        header[..title.len()].copy_from_slice(title);
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&(count as u32).to_le_bytes());
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| &mesh.vertices[tri[k] as usize]);
+            for p in [facet_normal(a, b, c), a.pos, b.pos, c.pos] {
+                for f in [p.x, p.y, p.z] {
+                    out.extend_from_slice(&f.to_le_bytes());
+                }
+            }
+            // Attribute byte count, unused
+            out.extend_from_slice(&[0, 0]);
+        }
+        out
+    }
+
+    #[allow(dead_code)]
+    pub fn save_stl(mesh: &Mesh, path: &str, binary: bool) -> std::io::Result<()> {
+        if binary {
+            std::fs::write(path, Self::stl_binary_bytes(mesh))
+        } else {
+            let name = Path::new(path).file_stem().map_or("mesh".into(), |s| s.to_string_lossy());
+            std::fs::write(path, Self::stl_ascii_string(mesh, &name))
+        }
+    }
+
+    // PLY with positions, normals, texture coordinates and RGBA colors per
+    // vertex, and triangles indexing them
+    pub fn ply_bytes(mesh: &Mesh, binary: bool) -> Vec<u8> {
+        let format = if binary { "binary_little_endian" } else { "ascii" };
+        let header = format!(
+            "ply\nformat {} 1.0\nelement vertex {}\n\
+             property float x\nproperty float y\nproperty float z\n\
+             property float nx\nproperty float ny\nproperty float nz\n\
+             property float s\nproperty float t\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\nproperty uchar alpha\n\
+             element face {}\nproperty list uchar uint vertex_indices\nend_header\n",
+            format,
+            mesh.vertices.len(),
+            mesh.triangle_count()
+        );
+        let mut out = header.into_bytes();
+
+        if binary {
+            for v in &mesh.vertices {
+                let floats = [v.pos.x, v.pos.y, v.pos.z, v.normal.x, v.normal.y, v.normal.z];
+                for f in floats.into_iter().chain([v.texture_coord.x, 1.0 - v.texture_coord.y]) {
+                    out.extend_from_slice(&f.to_le_bytes());
+                }
+                out.extend_from_slice(&[v.color.r, v.color.g, v.color.b, v.color.a]);
+            }
+            for tri in mesh.indices.chunks_exact(3) {
+                out.push(3);
+                for &i in tri {
+                    out.extend_from_slice(&i.to_le_bytes());
+                }
+            }
+        } else {
+            let mut text = String::new();
+            for v in &mesh.vertices {
+                let c = &v.color;
+                let _ = writeln!(
+                    text,
+                    "{} {} {} {} {} {} {} {} {} {} {} {}",
+                    v.pos.x,
+                    v.pos.y,
+                    v.pos.z,
+                    v.normal.x,
+                    v.normal.y,
+                    v.normal.z,
+                    v.texture_coord.x,
+                    1.0 - v.texture_coord.y,
+                    c.r,
+                    c.g,
+                    c.b,
+                    c.a
+                );
+            }
+            for tri in mesh.indices.chunks_exact(3) {
+                let _ = writeln!(text, "3 {} {} {}", tri[0], tri[1], tri[2]);
+            }
+            out.extend_from_slice(text.as_bytes());
+        }
+        out
+    }
+
+    #[allow(dead_code)]
+    pub fn save_ply(mesh: &Mesh, path: &str, binary: bool) -> std::io::Result<()> {
+        std::fs::write(path, Self::ply_bytes(mesh, binary))
+    }
+}
+
+fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+// From the winding, since STL readers expect it to agree with the vertex order
+fn facet_normal(a: &Vertex, b: &Vertex, c: &Vertex) -> Vector3 {
+    (b.pos - a.pos).cross(&(c.pos - a.pos)).normalize_v()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, obj_model::ObjModel, texture::Texture, triangle::Triangle, vec2::Vector2};
+
+    // Two triangles folded along their shared edge, each vertex with its own
+    // texture coordinates, normal and color
+    fn folded_quad() -> Mesh {
+        let vertex = |pos: [f32; 3], uv: [f32; 2], normal: [f32; 3], rgb: [u8; 3]| {
+            let color = Color::new(rgb[0], rgb[1], rgb[2], 255);
+            Vertex::new(&Vector3::new(pos[0], pos[1], pos[2]), &Vector2::new(uv[0], uv[1]), &color)
+                .with_normal(&Vector3::new(normal[0], normal[1], normal[2]).normalize_v())
+        };
+        let a = vertex([0.0, 0.0, 0.0], [0.0, 0.25], [0.0, 0.0, 1.0], [255, 0, 0]);
+        let b = vertex([1.0, 0.0, 0.0], [0.75, 0.25], [0.0, 1.0, 1.0], [0, 255, 0]);
+        let c = vertex([1.0, 1.0, 0.5], [0.75, 1.0], [1.0, 0.0, 1.0], [0, 0, 255]);
+        let d = vertex([0.0, 1.5, -1.0], [0.0, 1.0], [0.0, 1.0, 0.0], [10, 20, 30]);
+        Mesh::from_triangles(&[Triangle::new(a.clone(), b, c.clone()), Triangle::new(a, c, d)])
+    }
+
+    fn close(a: &Vector3, b: &Vector3) -> bool {
+        (*a - *b).length() < 1e-5
+    }
+
+    fn floats(line: &str, skip: usize) -> Vec<f32> {
+        line.split_whitespace().skip(skip).map(|s| s.parse().unwrap()).collect()
+    }
+
+    fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn facet_normals(mesh: &Mesh) -> Vec<Vector3> {
+        mesh.to_triangles().iter().map(|t| facet_normal(&t.v1, &t.v2, &t.v3)).collect()
+    }
+
+    #[test]
+    fn obj_round_trips_through_obj_model() {
+        let mesh = folded_quad();
+        let text = MeshExport::obj_string(&mesh, None, None);
+        // Written with v = 0 at the bottom of the image
+        assert!(text.lines().any(|l| l == "vt 0.75 0.75"));
+
+        let model = ObjModel::from_obj_str(&text, None).unwrap();
+        let imported: Vec<&Triangle> = model.triangles().collect();
+        let original = mesh.to_triangles();
+        assert_eq!(imported.len(), original.len());
+        for (a, b) in imported.iter().zip(&original) {
+            for (a, b) in [(&a.v1, &b.v1), (&a.v2, &b.v2), (&a.v3, &b.v3)] {
+                assert!(close(&a.pos, &b.pos));
+                assert!(close(&a.normal, &b.normal));
+                assert!((a.texture_coord.x - b.texture_coord.x).abs() < 1e-5);
+                assert!((a.texture_coord.y - b.texture_coord.y).abs() < 1e-5);
+                assert_eq!((a.color.r, a.color.g, a.color.b), (b.color.r, b.color.g, b.color.b));
+            }
+        }
+    }
+
+    #[test]
+    fn obj_saves_its_material_and_map() {
+        let dir = std::env::temp_dir().join(format!("mesh_export_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut material = Material::new("red brick/2");
+        material.diffuse = Color::new(255, 0, 0, 128);
+        let mut map = Texture::new(2, 2);
+        *map.get_pixel_mut(1, 0) = Color::new(0, 255, 0, 255);
+        material.diffuse_map = Some(map);
+        let path = dir.join("quad.obj");
+        MeshExport::save_obj(&folded_quad(), Some(&material), &path.to_string_lossy()).unwrap();
+
+        let mut files: Vec<String> =
+            std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        files.sort();
+        let model = ObjModel::from_obj_file(&path.to_string_lossy());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, ["quad.mtl", "quad.obj", "quad_red_brick_2.png"]);
+        let model = model.unwrap();
+        assert_eq!(model.materials.len(), 1);
+        let loaded = &model.materials[0];
+        assert_eq!(loaded.name, "red brick/2");
+        assert_eq!(loaded.diffuse, material.diffuse);
+        let map = loaded.diffuse_map.as_ref().unwrap();
+        assert_eq!((map.width, map.height), (2, 2));
+        assert_eq!(*map.get_pixel(1, 0), Color::new(0, 255, 0, 255));
+        assert!(model.meshes.iter().all(|m| m.material == Some(0)));
+    }
+
+    #[test]
+    fn ascii_stl_has_facets_with_normals() {
+        let mesh = folded_quad();
+        let text = MeshExport::stl_ascii_string(&mesh, "quad");
+        assert!(text.starts_with("solid quad\n"));
+        assert!(text.ends_with("endsolid quad\n"));
+
+        let normals: Vec<Vector3> = text
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("facet normal"))
+            .map(|l| {
+                let n = floats(l, 0);
+                Vector3::new(n[0], n[1], n[2])
+            })
+            .collect();
+        let expected = facet_normals(&mesh);
+        assert_eq!(normals.len(), expected.len());
+        for (n, e) in normals.iter().zip(&expected) {
+            assert!(close(n, e));
+        }
+
+        let vertices: Vec<Vec<f32>> =
+            text.lines().filter(|l| l.trim().starts_with("vertex")).map(|l| floats(l, 1)).collect();
+        let corners: Vec<Vector3> = mesh.to_triangles().iter().flat_map(|t| [t.v1.pos, t.v2.pos, t.v3.pos]).collect();
+        assert_eq!(vertices.len(), corners.len());
+        for (v, c) in vertices.iter().zip(&corners) {
+            assert!(close(&Vector3::new(v[0], v[1], v[2]), c));
+        }
+    }
+
+    #[test]
+    fn binary_stl_has_facets_with_normals() {
+        let mesh = folded_quad();
+        let bytes = MeshExport::stl_binary_bytes(&mesh);
+        assert_eq!(bytes.len(), 84 + 50 * mesh.triangle_count());
+        assert_eq!(u32_at(&bytes, 80) as usize, mesh.triangle_count());
+
+        for (k, (expected, tri)) in facet_normals(&mesh).iter().zip(mesh.to_triangles()).enumerate() {
+            let at = 84 + 50 * k;
+            let vector = |i: usize| {
+                let at = at + i * 12;
+                Vector3::new(f32_at(&bytes, at), f32_at(&bytes, at + 4), f32_at(&bytes, at + 8))
+            };
+            assert!(close(&vector(0), expected));
+            assert!(close(&vector(1), &tri.v1.pos));
+            assert!(close(&vector(2), &tri.v2.pos));
+            assert!(close(&vector(3), &tri.v3.pos));
+        }
+    }
+
+    // Splits off the header, checking it declares the mesh's vertex and
+    // face counts
+    fn ply_body<'a>(bytes: &'a [u8], mesh: &Mesh, format: &str) -> &'a [u8] {
+        let end = b"end_header\n";
+        let split = bytes.windows(end.len()).position(|w| w == end).unwrap() + end.len();
+        let header = std::str::from_utf8(&bytes[..split]).unwrap();
+        assert!(header.starts_with("ply\n"));
+        assert!(header.contains(&format!("format {} 1.0\n", format)));
+        assert!(header.contains(&format!("element vertex {}\n", mesh.vertices.len())));
+        assert!(header.contains(&format!("element face {}\n", mesh.triangle_count())));
+        &bytes[split..]
+    }
+
+    #[test]
+    fn ascii_ply_lists_vertices_and_faces() {
+        let mesh = folded_quad();
+        let bytes = MeshExport::ply_bytes(&mesh, false);
+        let body = std::str::from_utf8(ply_body(&bytes, &mesh, "ascii")).unwrap();
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), mesh.vertices.len() + mesh.triangle_count());
+
+        for (line, v) in lines.iter().zip(&mesh.vertices) {
+            let values = floats(line, 0);
+            assert!(close(&Vector3::new(values[0], values[1], values[2]), &v.pos));
+            assert!(close(&Vector3::new(values[3], values[4], values[5]), &v.normal));
+            assert_eq!((values[6], values[7]), (v.texture_coord.x, 1.0 - v.texture_coord.y));
+            assert_eq!(values[8..], [v.color.r, v.color.g, v.color.b, v.color.a].map(f32::from));
+        }
+        for (line, tri) in lines[mesh.vertices.len()..].iter().zip(mesh.indices.chunks_exact(3)) {
+            assert_eq!(*line, format!("3 {} {} {}", tri[0], tri[1], tri[2]));
+        }
+    }
+
+    #[test]
+    fn binary_ply_lists_vertices_and_faces() {
+        let mesh = folded_quad();
+        let bytes = MeshExport::ply_bytes(&mesh, true);
+        let body = ply_body(&bytes, &mesh, "binary_little_endian");
+        // Eight floats and four color bytes a vertex, a count byte and three
+        // indices a face
+        assert_eq!(body.len(), mesh.vertices.len() * 36 + mesh.triangle_count() * 13);
+
+        for (k, v) in mesh.vertices.iter().enumerate() {
+            let at = k * 36;
+            let f = |i: usize| f32_at(body, at + i * 4);
+            assert!(close(&Vector3::new(f(0), f(1), f(2)), &v.pos));
+            assert!(close(&Vector3::new(f(3), f(4), f(5)), &v.normal));
+            assert_eq!((f(6), f(7)), (v.texture_coord.x, 1.0 - v.texture_coord.y));
+            assert_eq!(body[at + 32..at + 36], [v.color.r, v.color.g, v.color.b, v.color.a]);
+        }
+        let faces = &body[mesh.vertices.len() * 36..];
+        for (face, tri) in faces.chunks_exact(13).zip(mesh.indices.chunks_exact(3)) {
+            assert_eq!(face[0], 3);
+            assert_eq!([u32_at(face, 1), u32_at(face, 5), u32_at(face, 9)], tri);
+        }
+    }
+}
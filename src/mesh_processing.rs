@@ -0,0 +1,778 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{
+    bounds::{Aabb, BoundingSphere, Obb},
+    color::Color,
+    mesh::Mesh,
+    triangle::Triangle,
+    vec2::Vector2,
+    vec3::Vector3,
+    vertex::Vertex,
+};
+
+// Operations on whole meshes. Most work best on a welded mesh, see
+// `Mesh::weld`, since vertices that are only split for their attributes look
+// like holes in the surface.
+pub struct MeshProcessing;
+
+impl MeshProcessing {
+    // Recomputes every normal. Faces around a corner are smoothed together
+    // when they meet at no more than `crease_angle_deg`, so 0 gives flat
+    // shading and 180 smooths everything. Vertices get split where a corner
+    // ends up with more than one normal.
+    #[allow(dead_code)]
+    pub fn generate_normals(mesh: &mut Mesh, crease_angle_deg: f32) {
+        let corners = mesh.indices.len() / 3 * 3;
+        let face_normals: Vec<Vector3> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|c| face_normal(&mesh.vertices, c).normalize_v())
+            .collect();
+
+        // Faces touching each position, with the angle they have there
+        let mut around: HashMap<[u32; 3], Vec<(usize, f32)>> = HashMap::new();
+        for corner in 0..corners {
+            let (face, k) = (corner / 3, corner % 3);
+            let tri = &mesh.indices[face * 3..face * 3 + 3];
+            let p = mesh.vertices[tri[k] as usize].pos;
+            let to_next = mesh.vertices[tri[(k + 1) % 3] as usize].pos - p;
+            let to_prev = mesh.vertices[tri[(k + 2) % 3] as usize].pos - p;
+            let angle = angle_between(&to_next, &to_prev);
+            around.entry(position_key(&p)).or_default().push((face, angle));
+        }
+
+        let cos_crease = crease_angle_deg.clamp(0.0, 180.0).to_radians().cos() - 1e-5;
+        let mut triangles = Vec::with_capacity(corners / 3);
+        for (face, tri) in mesh.indices.chunks_exact(3).enumerate() {
+            let normal_of = |k: usize| {
+                let vertex = &mesh.vertices[tri[k] as usize];
+                let own = face_normals[face];
+                let mut sum = Vector3::new(0.0, 0.0, 0.0);
+                for &(other, angle) in &around[&position_key(&vertex.pos)] {
+                    if Vector3::dot(&own, &face_normals[other]) >= cos_crease {
+                        sum += face_normals[other] * angle;
+                    }
+                }
+                let normal = sum.normalize_v();
+                vertex.clone().with_normal(if normal.length() > 0.0 { &normal } else { &own })
+            };
+            triangles.push(Triangle::new(normal_of(0), normal_of(1), normal_of(2)));
+        }
+        *mesh = Mesh::from_triangles(&triangles);
+    }
+
+    // Tangents along increasing u for normal mapping, made perpendicular to
+    // each vertex normal. Needs texture coordinates; vertices whose triangles
+    // have none get an arbitrary tangent perpendicular to the normal.
+    #[allow(dead_code)]
+    pub fn generate_tangents(mesh: &mut Mesh) {
+        let n = mesh.vertices.len();
+        let mut u_dirs = vec![Vector3::new(0.0, 0.0, 0.0); n];
+        let mut v_dirs = vec![Vector3::new(0.0, 0.0, 0.0); n];
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| &mesh.vertices[tri[k] as usize]);
+            let (e1, e2) = (b.pos - a.pos, c.pos - a.pos);
+            let (du1, dv1) = (b.texture_coord.x - a.texture_coord.x, b.texture_coord.y - a.texture_coord.y);
+            let (du2, dv2) = (c.texture_coord.x - a.texture_coord.x, c.texture_coord.y - a.texture_coord.y);
+            let det = du1 * dv2 - du2 * dv1;
+            if det.abs() < 1e-12 {
+                continue;
+            }
+            let r = 1.0 / det;
+            let u_dir = (e1 * dv2 - e2 * dv1) * r;
+            let v_dir = (e2 * du1 - e1 * du2) * r;
+            for &i in tri {
+                u_dirs[i as usize] += u_dir;
+                v_dirs[i as usize] += v_dir;
+            }
+        }
+
+        for (i, vertex) in mesh.vertices.iter_mut().enumerate() {
+            let normal = vertex.normal.normalize_v();
+            // Gram-Schmidt against the normal
+            let mut tangent = (u_dirs[i] - normal * Vector3::dot(&normal, &u_dirs[i])).normalize_v();
+            if tangent.length() == 0.0 {
+                tangent = any_perpendicular(&normal);
+            }
+            vertex.tangent = tangent;
+            vertex.tangent_sign = if Vector3::dot(&normal.cross(&tangent), &v_dirs[i]) < 0.0 { -1.0 } else { 1.0 };
+        }
+    }
+
+    // Quadric error metric edge collapses (Garland and Heckbert) until at
+    // most `target_triangles` are left or nothing can go without folding
+    // the surface over. Open edges are weighted so outlines hold their shape.
+    #[allow(dead_code)]
+    pub fn simplify(mesh: &Mesh, target_triangles: usize) -> Mesh {
+        let mut vertices = mesh.vertices.clone();
+        let mut tris: Vec<[u32; 3]> = mesh.indices.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+        let mut live_tris = vec![true; tris.len()];
+        let mut live_count = tris.len();
+        let mut vertex_tris: Vec<Vec<usize>> = vec![vec![]; vertices.len()];
+        for (t, tri) in tris.iter().enumerate() {
+            for &v in tri {
+                vertex_tris[v as usize].push(t);
+            }
+        }
+
+        let mut quadrics = vec![Quadric::zero(); vertices.len()];
+        let mut edge_faces: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        for (t, tri) in tris.iter().enumerate() {
+            let normal = face_normal(&vertices, tri).normalize_v();
+            if normal.length() == 0.0 {
+                continue;
+            }
+            let plane = Quadric::plane(&normal, &vertices[tri[0] as usize].pos);
+            for k in 0..3 {
+                quadrics[tri[k] as usize].add(&plane);
+                edge_faces.entry(edge_key(tri[k], tri[(k + 1) % 3])).or_default().push(t);
+            }
+        }
+        // A plane through each open edge at right angles to its face keeps
+        // the border from drifting inwards
+        for (&(a, b), faces) in &edge_faces {
+            if faces.len() != 1 {
+                continue;
+            }
+            let (pa, pb) = (vertices[a as usize].pos, vertices[b as usize].pos);
+            let face = face_normal(&vertices, &tris[faces[0]]);
+            let normal = (pb - pa).cross(&face).normalize_v();
+            if normal.length() == 0.0 {
+                continue;
+            }
+            let border = Quadric::plane(&normal, &pa).scaled(BORDER_WEIGHT);
+            quadrics[a as usize].add(&border);
+            quadrics[b as usize].add(&border);
+        }
+
+        let mut live_vertex = vec![true; vertices.len()];
+        let mut versions = vec![0u32; vertices.len()];
+        // Cheapest collapse first. Entries remember the versions of their
+        // ends and are skipped once either has changed.
+        let mut heap: BinaryHeap<EdgeEntry> = BinaryHeap::new();
+        let push_edge = |heap: &mut BinaryHeap<_>, vertices: &[Vertex], quadrics: &[Quadric], versions: &[u32], a: u32, b: u32| {
+            let (ai, bi) = (a as usize, b as usize);
+            let (cost, _) = collapse_target(&vertices[ai].pos, &vertices[bi].pos, &quadrics[ai].sum(&quadrics[bi]));
+            heap.push(Reverse((cost_key(cost), a, b, versions[ai], versions[bi])));
+        };
+        let mut edges: Vec<(u32, u32)> = edge_faces.keys().copied().collect();
+        // Ties break the same way every run
+        edges.sort_unstable();
+        for (a, b) in edges {
+            push_edge(&mut heap, &vertices, &quadrics, &versions, a, b);
+        }
+
+        while live_count > target_triangles {
+            let Some(Reverse((_, a, b, version_a, version_b))) = heap.pop() else {
+                break;
+            };
+            let (ai, bi) = (a as usize, b as usize);
+            if !live_vertex[ai] || !live_vertex[bi] || versions[ai] != version_a || versions[bi] != version_b {
+                continue;
+            }
+            let quadric = quadrics[ai].sum(&quadrics[bi]);
+            let (_, target) = collapse_target(&vertices[ai].pos, &vertices[bi].pos, &quadric);
+
+            // Refuse collapses that would turn a neighbouring face over
+            let flips = vertex_tris[ai].iter().chain(&vertex_tris[bi]).any(|&t| {
+                if !live_tris[t] || (tris[t].contains(&a) && tris[t].contains(&b)) {
+                    return false;
+                }
+                let before = face_normal(&vertices, &tris[t]);
+                let moved = tris[t].map(|v| if v == a || v == b { target } else { vertices[v as usize].pos });
+                let after = (moved[1] - moved[0]).cross(&(moved[2] - moved[0]));
+                Vector3::dot(&before, &after) <= 0.0
+            });
+            if flips {
+                continue;
+            }
+
+            // Attributes slide along the edge to wherever the new point is
+            let edge = vertices[bi].pos - vertices[ai].pos;
+            let along = Vector3::dot(&(target - vertices[ai].pos), &edge) / Vector3::dot(&edge, &edge).max(1e-20);
+            let mut merged = blend(&[(&vertices[ai], 1.0 - along.clamp(0.0, 1.0)), (&vertices[bi], along.clamp(0.0, 1.0))]);
+            merged.pos = target;
+            vertices[ai] = merged;
+            quadrics[ai] = quadric;
+            live_vertex[bi] = false;
+            versions[ai] += 1;
+
+            for t in std::mem::take(&mut vertex_tris[bi]) {
+                if !live_tris[t] {
+                    continue;
+                }
+                if tris[t].contains(&a) {
+                    live_tris[t] = false;
+                    live_count -= 1;
+                } else {
+                    for v in &mut tris[t] {
+                        if *v == b {
+                            *v = a;
+                        }
+                    }
+                    vertex_tris[ai].push(t);
+                }
+            }
+            vertex_tris[ai].retain(|&t| live_tris[t]);
+
+            let mut neighbors: Vec<u32> = vertex_tris[ai].iter().flat_map(|&t| tris[t]).filter(|&v| v != a).collect();
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            for n in neighbors {
+                push_edge(&mut heap, &vertices, &quadrics, &versions, a, n);
+            }
+        }
+
+        let mut out = Mesh {
+            vertices,
+            indices: tris
+                .iter()
+                .zip(&live_tris)
+                .filter(|&(_, &live)| live)
+                .flat_map(|(tri, _)| *tri)
+                .collect(),
+        };
+        out.remove_unused_vertices();
+        out
+    }
+
+    // Loop subdivision, each level splits every triangle in four and pulls
+    // the surface towards a smooth limit. Open edges are smoothed as curves.
+    #[allow(dead_code)]
+    pub fn subdivide_loop(mesh: &Mesh, levels: usize) -> Mesh {
+        let mut mesh = mesh.clone();
+        for _ in 0..levels {
+            mesh = loop_level(&mesh);
+        }
+        mesh
+    }
+
+    // Catmull-Clark subdivision. The first level turns each triangle into
+    // three quads and every level after splits each quad in four; the
+    // result is triangulated at the end.
+    #[allow(dead_code)]
+    pub fn subdivide_catmull_clark(mesh: &Mesh, levels: usize) -> Mesh {
+        let mut vertices = mesh.vertices.clone();
+        let mut faces: Vec<Vec<u32>> = mesh.indices.chunks_exact(3).map(|c| c.to_vec()).collect();
+        for _ in 0..levels {
+            (vertices, faces) = catmull_clark_level(&vertices, &faces);
+        }
+        let mut out = Mesh {
+            vertices,
+            indices: vec![],
+        };
+        for face in &faces {
+            for k in 1..face.len().saturating_sub(1) {
+                out.indices.extend([face[0], face[k], face[k + 1]]);
+            }
+        }
+        out
+    }
+
+    #[allow(dead_code)]
+    pub fn aabb(mesh: &Mesh) -> Aabb {
+        Aabb::from_points(mesh.vertices.iter().map(|v| &v.pos))
+    }
+
+    #[allow(dead_code)]
+    pub fn bounding_sphere(mesh: &Mesh) -> BoundingSphere {
+        let points: Vec<Vector3> = mesh.vertices.iter().map(|v| v.pos).collect();
+        BoundingSphere::from_points(&points)
+    }
+
+    #[allow(dead_code)]
+    pub fn obb(mesh: &Mesh) -> Obb {
+        let points: Vec<Vector3> = mesh.vertices.iter().map(|v| v.pos).collect();
+        Obb::from_points(&points)
+    }
+}
+
+// Collapse cost key, edge ends, then the ends' versions when it was pushed.
+// Reversed so the heap pops the cheapest first.
+type EdgeEntry = Reverse<(u64, u32, u32, u32, u32)>;
+
+// How much more an open edge resists moving than a face does
+const BORDER_WEIGHT: f64 = 1000.0;
+
+// Symmetric 4x4 matrix summing squared distances to planes, upper triangle
+// row by row
+#[derive(Clone, Copy)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn zero() -> Self {
+        Quadric([0.0; 10])
+    }
+
+    fn plane(normal: &Vector3, point: &Vector3) -> Self {
+        let (a, b, c) = (normal.x as f64, normal.y as f64, normal.z as f64);
+        let d = -(a * point.x as f64 + b * point.y as f64 + c * point.z as f64);
+        Quadric([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d])
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (q, o) in self.0.iter_mut().zip(&other.0) {
+            *q += o;
+        }
+    }
+
+    fn sum(&self, other: &Quadric) -> Quadric {
+        let mut q = *self;
+        q.add(other);
+        q
+    }
+
+    fn scaled(mut self, s: f64) -> Quadric {
+        for q in &mut self.0 {
+            *q *= s;
+        }
+        self
+    }
+
+    fn error(&self, p: &Vector3) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (p.x as f64, p.y as f64, p.z as f64);
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    // Point of least error, None when the planes don't pin one down
+    fn minimum(&self) -> Option<Vector3> {
+        let q = &self.0;
+        let m = [[q[0], q[1], q[2]], [q[1], q[4], q[5]], [q[2], q[5], q[7]]];
+        let rhs = [-q[3], -q[6], -q[8]];
+        let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        // Cramer's rule
+        let solve = |col: usize| {
+            let mut mc = m;
+            for row in 0..3 {
+                mc[row][col] = rhs[row];
+            }
+            (mc[0][0] * (mc[1][1] * mc[2][2] - mc[1][2] * mc[2][1]) - mc[0][1] * (mc[1][0] * mc[2][2] - mc[1][2] * mc[2][0])
+                + mc[0][2] * (mc[1][0] * mc[2][1] - mc[1][1] * mc[2][0]))
+                / det
+        };
+        Some(Vector3::new(solve(0) as f32, solve(1) as f32, solve(2) as f32))
+    }
+}
+
+// Cheapest place to put the merged vertex of an edge, and what it costs:
+// the quadric's minimum if it has one and it's not far off the edge,
+// otherwise the best of the ends and the middle
+fn collapse_target(a: &Vector3, b: &Vector3, quadric: &Quadric) -> (f64, Vector3) {
+    let mut candidates = vec![*a, *b, (*a + *b) * 0.5];
+    if let Some(p) = quadric.minimum() {
+        let reach = (*b - *a).length() * 2.0;
+        if (p - (*a + *b) * 0.5).length() <= reach {
+            candidates.push(p);
+        }
+    }
+    candidates
+        .into_iter()
+        .map(|p| (quadric.error(&p).max(0.0), p))
+        .min_by(|x, y| x.0.total_cmp(&y.0))
+        .unwrap()
+}
+
+// Non-negative floats order the same as their bits
+fn cost_key(cost: f64) -> u64 {
+    cost.max(0.0).to_bits()
+}
+
+fn edge_key(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+fn position_key(p: &Vector3) -> [u32; 3] {
+    [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]
+}
+
+// Not normalized, so its length is twice the face's area
+fn face_normal(vertices: &[Vertex], tri: &[u32]) -> Vector3 {
+    let [a, b, c] = [0, 1, 2].map(|k| vertices[tri[k] as usize].pos);
+    (b - a).cross(&(c - a))
+}
+
+fn angle_between(a: &Vector3, b: &Vector3) -> f32 {
+    let lengths = a.length() * b.length();
+    if lengths == 0.0 {
+        return 0.0;
+    }
+    (Vector3::dot(a, b) / lengths).clamp(-1.0, 1.0).acos()
+}
+
+fn any_perpendicular(n: &Vector3) -> Vector3 {
+    let helper = if n.x.abs() < 0.9 { Vector3::new(1.0, 0.0, 0.0) } else { Vector3::new(0.0, 1.0, 0.0) };
+    n.cross(&helper).normalize_v()
+}
+
+// Weighted sum of every attribute, weights should add up to 1. Normals and
+// tangents are renormalized, the tangent sign comes from the heaviest vertex.
+fn blend(parts: &[(&Vertex, f32)]) -> Vertex {
+    let mut pos = Vector3::new(0.0, 0.0, 0.0);
+    let mut normal = Vector3::new(0.0, 0.0, 0.0);
+    let mut tangent = Vector3::new(0.0, 0.0, 0.0);
+    let (mut u, mut v) = (0.0, 0.0);
+    let mut rgba = [0.0f32; 4];
+    for &(vertex, w) in parts {
+        pos += vertex.pos * w;
+        normal += vertex.normal * w;
+        tangent += vertex.tangent * w;
+        u += vertex.texture_coord.x * w;
+        v += vertex.texture_coord.y * w;
+        let c = &vertex.color;
+        for (sum, channel) in rgba.iter_mut().zip([c.r, c.g, c.b, c.a]) {
+            *sum += channel as f32 * w;
+        }
+    }
+    let [r, g, b, a] = rgba.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    let mut out = Vertex::new(&pos, &Vector2::new(u, v), &Color::new(r, g, b, a)).with_normal(&normal.normalize_v());
+    out.tangent = tangent.normalize_v();
+    out.tangent_sign = parts
+        .iter()
+        .max_by(|x, y| x.1.total_cmp(&y.1))
+        .map_or(1.0, |(vertex, _)| vertex.tangent_sign);
+    out
+}
+
+fn loop_level(mesh: &Mesh) -> Mesh {
+    let n = mesh.vertices.len();
+    // Vertices opposite each edge, one per face it borders
+    let mut opposite: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+    let mut neighbors: Vec<Vec<u32>> = vec![vec![]; n];
+    for tri in mesh.indices.chunks_exact(3) {
+        for k in 0..3 {
+            let (a, b, c) = (tri[k], tri[(k + 1) % 3], tri[(k + 2) % 3]);
+            opposite.entry(edge_key(a, b)).or_default().push(c);
+            neighbors[a as usize].push(b);
+            neighbors[b as usize].push(a);
+        }
+    }
+    for list in &mut neighbors {
+        list.sort_unstable();
+        list.dedup();
+    }
+
+    let pos = |i: u32| mesh.vertices[i as usize].pos;
+    let mut vertices: Vec<Vertex> = mesh
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(i, vertex)| {
+            let i = i as u32;
+            let border: Vec<u32> = neighbors[i as usize]
+                .iter()
+                .copied()
+                .filter(|&nb| opposite[&edge_key(i, nb)].len() == 1)
+                .collect();
+            let mut out = vertex.clone();
+            if border.len() == 2 {
+                out.pos = pos(i) * 0.75 + (pos(border[0]) + pos(border[1])) * 0.125;
+            } else if border.is_empty() && !neighbors[i as usize].is_empty() {
+                let valence = neighbors[i as usize].len();
+                let beta = if valence == 3 { 3.0 / 16.0 } else { 3.0 / (8.0 * valence as f32) };
+                let ring = neighbors[i as usize].iter().fold(Vector3::new(0.0, 0.0, 0.0), |sum, &nb| sum + pos(nb));
+                out.pos = pos(i) * (1.0 - valence as f32 * beta) + ring * beta;
+            }
+            // Corners where more than two open edges meet stay put
+            out
+        })
+        .collect();
+
+    let mut edge_vertex: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut midpoint = |a: u32, b: u32, vertices: &mut Vec<Vertex>| -> u32 {
+        *edge_vertex.entry(edge_key(a, b)).or_insert_with(|| {
+            let mut vertex = blend(&[(&mesh.vertices[a as usize], 0.5), (&mesh.vertices[b as usize], 0.5)]);
+            let across = &opposite[&edge_key(a, b)];
+            if across.len() == 2 {
+                vertex.pos = (pos(a) + pos(b)) * 0.375 + (pos(across[0]) + pos(across[1])) * 0.125;
+            }
+            vertices.push(vertex);
+            (vertices.len() - 1) as u32
+        })
+    };
+
+    let mut indices = Vec::with_capacity(mesh.indices.len() * 4);
+    for tri in mesh.indices.chunks_exact(3) {
+        let (a, b, c) = (tri[0], tri[1], tri[2]);
+        let ab = midpoint(a, b, &mut vertices);
+        let bc = midpoint(b, c, &mut vertices);
+        let ca = midpoint(c, a, &mut vertices);
+        indices.extend([a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca]);
+    }
+    Mesh { vertices, indices }
+}
+
+fn catmull_clark_level(vertices: &[Vertex], faces: &[Vec<u32>]) -> (Vec<Vertex>, Vec<Vec<u32>>) {
+    let n = vertices.len();
+    let pos = |i: u32| vertices[i as usize].pos;
+
+    let mut out: Vec<Vertex> = vertices.to_vec();
+    let face_points: Vec<u32> = faces
+        .iter()
+        .map(|face| {
+            let w = 1.0 / face.len() as f32;
+            let parts: Vec<(&Vertex, f32)> = face.iter().map(|&v| (&vertices[v as usize], w)).collect();
+            out.push(blend(&parts));
+            (out.len() - 1) as u32
+        })
+        .collect();
+
+    let mut edge_faces: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        for k in 0..face.len() {
+            edge_faces.entry(edge_key(face[k], face[(k + 1) % face.len()])).or_default().push(f);
+        }
+    }
+
+    // Edge points, in a fixed order so the output doesn't depend on hashing
+    let mut edges: Vec<(u32, u32)> = edge_faces.keys().copied().collect();
+    edges.sort_unstable();
+    let mut edge_point: HashMap<(u32, u32), u32> = HashMap::new();
+    for &(a, b) in &edges {
+        let mut vertex = blend(&[(&vertices[a as usize], 0.5), (&vertices[b as usize], 0.5)]);
+        let adjacent = &edge_faces[&(a, b)];
+        if adjacent.len() == 2 {
+            let (f1, f2) = (out[face_points[adjacent[0]] as usize].pos, out[face_points[adjacent[1]] as usize].pos);
+            vertex.pos = (pos(a) + pos(b) + f1 + f2) * 0.25;
+        }
+        out.push(vertex);
+        edge_point.insert((a, b), (out.len() - 1) as u32);
+    }
+
+    // Move the original vertices
+    let mut vertex_faces: Vec<Vec<usize>> = vec![vec![]; n];
+    for (f, face) in faces.iter().enumerate() {
+        for &v in face {
+            vertex_faces[v as usize].push(f);
+        }
+    }
+    let mut vertex_edges: Vec<Vec<(u32, u32)>> = vec![vec![]; n];
+    for &(a, b) in &edges {
+        vertex_edges[a as usize].push((a, b));
+        vertex_edges[b as usize].push((a, b));
+    }
+    for v in 0..n {
+        let p = pos(v as u32);
+        let border: Vec<u32> = vertex_edges[v]
+            .iter()
+            .filter(|e| edge_faces[e].len() == 1)
+            .map(|&(a, b)| if a == v as u32 { b } else { a })
+            .collect();
+        if border.len() == 2 {
+            out[v].pos = p * 0.75 + (pos(border[0]) + pos(border[1])) * 0.125;
+        } else if border.is_empty() && !vertex_faces[v].is_empty() {
+            let valence = vertex_edges[v].len() as f32;
+            let face_avg = vertex_faces[v]
+                .iter()
+                .fold(Vector3::new(0.0, 0.0, 0.0), |sum, &f| sum + out[face_points[f] as usize].pos)
+                * (1.0 / vertex_faces[v].len() as f32);
+            let edge_avg = vertex_edges[v]
+                .iter()
+                .fold(Vector3::new(0.0, 0.0, 0.0), |sum, &(a, b)| sum + (pos(a) + pos(b)) * 0.5)
+                * (1.0 / valence);
+            out[v].pos = (face_avg + edge_avg * 2.0 + p * (valence - 3.0)) * (1.0 / valence);
+        }
+    }
+
+    let mut new_faces = Vec::with_capacity(faces.iter().map(|f| f.len()).sum());
+    for (f, face) in faces.iter().enumerate() {
+        let m = face.len();
+        for k in 0..m {
+            let (prev, here, next) = (face[(k + m - 1) % m], face[k], face[(k + 1) % m]);
+            new_faces.push(vec![
+                here,
+                edge_point[&edge_key(here, next)],
+                face_points[f],
+                edge_point[&edge_key(prev, here)],
+            ]);
+        }
+    }
+    (out, new_faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::triangle_gen::TriangleGen;
+
+    const WHITE: Color = Color { r: 255, g: 255, b: 255, a: 255 };
+
+    fn close(a: &Vector3, b: &Vector3) -> bool {
+        (*a - *b).length() < 1e-3
+    }
+
+    // Unit cube with one vertex per corner, so normals have to split it
+    fn shared_corner_cube() -> Mesh {
+        let triangles: Vec<Triangle> =
+            TriangleGen::create_box(&Vector3::new(0.0, 0.0, 0.0), &Vector3::new(2.0, 2.0, 2.0), &WHITE)
+                .iter()
+                .map(|t| {
+                    let bare = |v: &Vertex| Vertex::new(&v.pos, &Vector2::new(0.0, 0.0), &v.color);
+                    Triangle::new(bare(&t.v1), bare(&t.v2), bare(&t.v3))
+                })
+                .collect();
+        Mesh::from_triangles(&triangles)
+    }
+
+    fn tetrahedron() -> Mesh {
+        let p = [
+            Vector3::new(1.0, 1.0, 1.0),
+            Vector3::new(1.0, -1.0, -1.0),
+            Vector3::new(-1.0, 1.0, -1.0),
+            Vector3::new(-1.0, -1.0, 1.0),
+        ];
+        let vertex = |i: usize| Vertex::new(&p[i], &Vector2::new(0.0, 0.0), &WHITE);
+        let faces = [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]];
+        let triangles: Vec<Triangle> =
+            faces.iter().map(|f| Triangle::new(vertex(f[0]), vertex(f[1]), vertex(f[2]))).collect();
+        Mesh::from_triangles(&triangles)
+    }
+
+    fn area(mesh: &Mesh) -> f32 {
+        mesh.indices
+            .chunks_exact(3)
+            .map(|c| face_normal(&mesh.vertices, c).length() * 0.5)
+            .sum()
+    }
+
+    #[test]
+    fn normals_at_zero_crease_are_flat() {
+        let mut mesh = shared_corner_cube();
+        assert_eq!(mesh.vertices.len(), 8);
+        MeshProcessing::generate_normals(&mut mesh, 0.0);
+        // Every corner splits three ways, one per face
+        assert_eq!(mesh.vertices.len(), 24);
+        for tri in mesh.indices.chunks_exact(3) {
+            let face = face_normal(&mesh.vertices, tri).normalize_v();
+            for &i in tri {
+                assert!(close(&mesh.vertices[i as usize].normal, &face));
+            }
+        }
+    }
+
+    #[test]
+    fn normals_at_180_crease_are_smooth() {
+        let mut mesh = shared_corner_cube();
+        MeshProcessing::generate_normals(&mut mesh, 180.0);
+        assert_eq!(mesh.vertices.len(), 8);
+        for vertex in &mesh.vertices {
+            // Straight out through the corner
+            assert!(close(&vertex.normal, &vertex.pos.normalize_v()));
+        }
+    }
+
+    #[test]
+    fn tangents_follow_u() {
+        // Quad facing +Y with u along +x and v along -z
+        let corner = |u: f32, v: f32| {
+            Vertex::new(&Vector3::new(u, 0.0, -v), &Vector2::new(u, v), &WHITE)
+                .with_normal(&Vector3::new(0.0, 1.0, 0.0))
+        };
+        let mut mesh = Mesh::from_triangles(&[
+            Triangle::new(corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0)),
+            Triangle::new(corner(0.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)),
+        ]);
+        MeshProcessing::generate_tangents(&mut mesh);
+        for vertex in &mesh.vertices {
+            assert!(close(&vertex.tangent, &Vector3::new(1.0, 0.0, 0.0)));
+            assert_eq!(vertex.tangent_sign, 1.0);
+        }
+    }
+
+    #[test]
+    fn simplify_reaches_target_and_keeps_border() {
+        let grid = Mesh::from_triangles(&TriangleGen::create_plane_grid(
+            &Vector3::new(0.0, 0.0, 0.0),
+            2.0,
+            2.0,
+            8,
+            8,
+            &WHITE,
+        ));
+        assert_eq!(grid.triangle_count(), 128);
+        let simple = MeshProcessing::simplify(&grid, 32);
+        // Border collapses only take one triangle, so it can land one short
+        assert!((31..=32).contains(&simple.triangle_count()));
+
+        // Still the same square, without holes or folds
+        let bounds = MeshProcessing::aabb(&simple);
+        assert!(close(&bounds.min, &Vector3::new(-1.0, 0.0, -1.0)));
+        assert!(close(&bounds.max, &Vector3::new(1.0, 0.0, 1.0)));
+        assert!((area(&simple) - 4.0).abs() < 1e-3);
+        for vertex in &simple.vertices {
+            let on_edge = vertex.pos.x.abs() > 0.999 || vertex.pos.z.abs() > 0.999;
+            let was_on_edge = grid
+                .vertices
+                .iter()
+                .any(|v| close(&v.pos, &vertex.pos) && (v.pos.x.abs() > 0.999 || v.pos.z.abs() > 0.999));
+            assert!(!on_edge || was_on_edge);
+        }
+    }
+
+    #[test]
+    fn loop_splits_each_triangle_in_four() {
+        let tetra = tetrahedron();
+        assert_eq!(MeshProcessing::subdivide_loop(&tetra, 1).triangle_count(), 16);
+        assert_eq!(MeshProcessing::subdivide_loop(&tetra, 2).triangle_count(), 64);
+    }
+
+    #[test]
+    fn catmull_clark_makes_three_quads_then_four() {
+        let tetra = tetrahedron();
+        // Quads come out as two triangles each
+        assert_eq!(MeshProcessing::subdivide_catmull_clark(&tetra, 1).triangle_count(), 4 * 3 * 2);
+        assert_eq!(MeshProcessing::subdivide_catmull_clark(&tetra, 2).triangle_count(), 4 * 3 * 4 * 2);
+    }
+
+    #[test]
+    fn bounds_of_rotated_box() {
+        // 4 x 2 x 1 box turned 30 degrees about Y
+        let (sin, cos) = 30f32.to_radians().sin_cos();
+        let triangles: Vec<Triangle> =
+            TriangleGen::create_box(&Vector3::new(0.0, 0.0, 0.0), &Vector3::new(4.0, 2.0, 1.0), &WHITE)
+                .iter()
+                .map(|t| {
+                    let turn = |v: &Vertex| {
+                        let p = Vector3::new(v.pos.x * cos + v.pos.z * sin, v.pos.y, v.pos.z * cos - v.pos.x * sin);
+                        Vertex::new(&(p + Vector3::new(1.0, 2.0, 3.0)), &v.texture_coord, &v.color)
+                    };
+                    Triangle::new(turn(&t.v1), turn(&t.v2), turn(&t.v3))
+                })
+                .collect();
+        let mesh = Mesh::from_triangles(&triangles);
+        let center = Vector3::new(1.0, 2.0, 3.0);
+
+        let aabb = MeshProcessing::aabb(&mesh);
+        let half = Vector3::new(2.0 * cos + 0.5 * sin, 1.0, 2.0 * sin + 0.5 * cos);
+        assert!(close(&aabb.min, &(center - half)));
+        assert!(close(&aabb.max, &(center + half)));
+
+        let sphere = MeshProcessing::bounding_sphere(&mesh);
+        let half_diagonal = (4.0f32 + 1.0 + 0.25).sqrt();
+        assert!(sphere.radius >= half_diagonal - 1e-3);
+        assert!(sphere.radius <= half_diagonal * 1.05);
+        for vertex in &mesh.vertices {
+            assert!((vertex.pos - sphere.center).length() <= sphere.radius + 1e-3);
+        }
+
+        let obb = MeshProcessing::obb(&mesh);
+        assert!(close(&obb.center, &center));
+        assert!(close(&obb.half_extents, &Vector3::new(2.0, 1.0, 0.5)));
+        assert!((obb.volume() - 8.0).abs() < 1e-2);
+        let expected_axes = [Vector3::new(cos, 0.0, -sin), Vector3::new(0.0, 1.0, 0.0), Vector3::new(sin, 0.0, cos)];
+        for (axis, expected) in obb.axes.iter().zip(&expected_axes) {
+            assert!((Vector3::dot(axis, expected).abs() - 1.0).abs() < 1e-3);
+        }
+        assert!(mesh.vertices.iter().all(|v| obb.contains(&v.pos)));
+    }
+}
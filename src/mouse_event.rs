@@ -3,6 +3,6 @@ use crate::mouse_button::MouseButton;
 pub enum MouseEvent {
     ButtonDown { x: u32, y: u32, btn: MouseButton },
     ButtonRelease { x: u32, y: u32, btn: MouseButton },
-    NewPosition { x: u32, y: u32 },
+    NewPosition { x: u32, y: u32, xrel: i32, yrel: i32 },
     WheelScroll { y: i32 }
 }
@@ -1,58 +1,168 @@
 use crate::{
-    camera::Camera, color::Color, dither_shader::DitherShader, draw_list::DrawList, dummy_passthru_shader::DummyPassthruShader, even_line_missing_shader::EvenLineMissingShader, game::Game, input_handler::InputHandler, key_event::KeyEvent, mouse_event::MouseEvent, pixel_shader::{SuperShader, TexturedRainbowShader}, screen::{Screen, SCREEN_HEIGHT, SCREEN_WIDTH}, texture::Texture, triangle_gen::TriangleGen, vec2::Vector2, vec3::Vector3
+    billboard::{Billboard, BillboardMode}, billboard_batch::BillboardBatch, bounds::Aabb, camera::{Camera, Projection}, camera_path::{CameraKeyframe, CameraPath, CameraPathPlayer, Easing, PathInterpolation}, camera_rig::FollowRig, color::Color, csg::Csg, debug_draw::DebugDraw, dither_shader::DitherShader, draw_list::DrawList, dummy_passthru_shader::DummyPassthruShader, even_line_missing_shader::EvenLineMissingShader, fly_controller::FlyController, fog::{Fog, FogColor}, game::Game, game_clock::GameClock, heightmap::Heightmap, input_handler::InputHandler, key_event::KeyEvent, mouse_event::MouseEvent, noise::Perlin, orbit_controller::OrbitController, pixel_shader::{SuperShader, TexturedRainbowShader}, rect::Rect, screen::{Screen, SCREEN_WIDTH}, sprite_sheet::{SpriteAnimation, SpriteSheet}, terrain::Terrain, terrain_splat::TerrainSplat, texture::Texture, transform::Transform, triangle::Triangle, triangle_gen::TriangleGen, vec2::Vector2, vec3::Vector3, viewport::Viewport
 };
 
 pub struct Nameless3DThing {
     pub cam: Camera,
     pub input: InputHandler,
+    pub orbit: OrbitController,
+    pub fly: FlyController,
+    // Tab switches between orbiting the scene and flying through it
+    pub fly_mode: bool,
     dith_sh: TexturedRainbowShader,
     tex: Texture,
+    pub fog: Fog,
+    pub clock: GameClock,
+    // Enter plays a flythrough of the scene, any other camera input is
+    // ignored until it finishes
+    pub flythrough: CameraPathPlayer,
+    // Field of view and roll from before the flythrough, which sets both
+    lens_before_flythrough: (f32, f32),
+    // M cycles through the viewport layouts
+    pub layout: ViewLayout,
+    // F hands the camera to a rig following the walker around the room
+    pub follow_mode: bool,
+    pub walker: Transform,
+    pub follow: FollowRig,
+    collision: DrawList,
+    // The room's pieces grouped by shader, built once since cutting the
+    // window takes a CSG subtract
+    room_floor: DrawList,
+    room_striped: DrawList,
+    room_wall: DrawList,
+    // Hills around the room, from terrain.png when there is one
+    pub terrain: Terrain,
+    terrain_splat: TerrainSplat,
+    // G shows debug shapes: axes, a grid, the room's bounds and the walker
+    pub show_debug: bool,
+    room_bounds: Aabb,
+    // Posts standing on the terrain around the room
+    posts: DrawList,
+    // Trees on the hills and glowing orbs floating in the room
+    sprites: BillboardBatch,
+    orbs: Vec<usize>,
+    orb_animation: SpriteAnimation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ViewLayout {
+    Single,
+    Minimap,
+    FourView,
 }
 
 impl Nameless3DThing {
     pub fn new() -> Self {
+        let cam = Camera::new();
+        let orbit = OrbitController::from_camera(&cam);
+        let lens_before_flythrough = (cam.fov, cam.roll);
+        let fly = FlyController::from_camera(&cam);
+        let walker = Transform::new(Vector3::new(2.5, 0.0, 0.0));
+        let terrain = Self::terrain();
+        let room = Self::room();
+        let [floor_tris, floor2_tris, wall1_tris, wall2_tris] = &room;
+        let mut room_floor = DrawList::new();
+        room_floor.add(floor_tris);
+        let mut room_striped = DrawList::new();
+        room_striped.add(floor2_tris);
+        room_striped.add(wall1_tris);
+        let mut room_wall = DrawList::new();
+        room_wall.add(wall2_tris);
+        let posts = Self::posts(&terrain);
+        let (sprites, orbs) = Self::sprites(&terrain);
+        let terrain_splat = TerrainSplat::new(
+            terrain.auto_splat_weights(2.0, 0.3),
+            vec![
+                Self::speckled(Color::new(70, 140, 50, 255), 1),
+                Self::speckled(Color::new(120, 110, 100, 255), 2),
+                Self::speckled(Color::new(240, 240, 250, 255), 3),
+            ],
+            24.0,
+        );
         Nameless3DThing {
-            cam: Camera::new(),
+            cam,
             input: InputHandler::new(),
+            orbit,
+            fly,
+            fly_mode: false,
             dith_sh: TexturedRainbowShader::new(5.0),
             tex: Texture::new(100, 100),
+            fog: Fog::linear(12.0, 17.0, FogColor::Fixed(Color::new(255, 255, 255, 80))),
+            clock: GameClock::new(),
+            flythrough: CameraPathPlayer::new(Self::flythrough_path()),
+            lens_before_flythrough,
+            layout: ViewLayout::Single,
+            follow_mode: false,
+            walker,
+            follow: FollowRig::new(&walker),
+            collision: Self::collision_scene(&terrain, &room),
+            room_floor,
+            room_striped,
+            room_wall,
+            terrain,
+            terrain_splat,
+            show_debug: false,
+            room_bounds: Aabb::from_triangles(&room.concat()),
+            posts,
+            sprites,
+            orbs,
+            orb_animation: SpriteAnimation::new(0, 4, 6.0),
         }
     }
-}
 
-impl Game for Nameless3DThing {
-    fn update_tick(&mut self) {
-        self.input.new_frame();
+    // Loaded from flythrough.campath when present and valid so it can be
+    // tweaked without rebuilding, otherwise a loop around the room
+    fn flythrough_path() -> CameraPath {
+        if let Ok(path) = CameraPath::load_from_file("flythrough.campath") {
+            return path;
+        }
+
+        let target = Vector3::new(0.0, 0.75, 0.0);
+        let mut path = CameraPath::new(PathInterpolation::CatmullRom);
+        path.add_keyframe(
+            CameraKeyframe::new(0.0, Vector3::new(0.0, 2.0, -6.0), target, 90.0, 0.0)
+                .with_easing(Easing::EaseIn),
+        );
+        path.add_keyframe(CameraKeyframe::new(2.0, Vector3::new(5.0, 3.0, 0.0), target, 80.0, 10.0));
+        path.add_keyframe(CameraKeyframe::new(4.0, Vector3::new(0.0, 4.0, 6.0), target, 70.0, 0.0));
+        path.add_keyframe(
+            CameraKeyframe::new(6.0, Vector3::new(-5.0, 1.0, 0.0), target, 80.0, -10.0)
+                .with_easing(Easing::EaseOut),
+        );
+        path.add_keyframe(CameraKeyframe::new(8.0, Vector3::new(0.0, 2.0, -6.0), target, 90.0, 0.0));
+        path
+    }
 
+    // Arrow keys, space and shift move the orbit target, the mouse orbits around it
+    fn update_orbit(&mut self) {
         let amt = 0.1;
+        let mut movement = Vector3::new(0.0, 0.0, 0.0);
         if self.input.up.pressed {
-            self.cam.pos.z += amt;
+            movement.z += amt;
         }
         if self.input.down.pressed {
-            self.cam.pos.z -= amt;
+            movement.z -= amt;
         }
         if self.input.left.pressed {
-            self.cam.pos.x -= amt;
+            movement.x -= amt;
         }
         if self.input.right.pressed
         /*|| self.mouse_left_click*/
         {
-            self.cam.pos.x += amt;
+            movement.x += amt;
         }
         if self.input.shift.pressed {
-            self.cam.pos.y -= amt;
+            movement.y -= amt;
         }
         if self.input.space.pressed {
-            self.cam.pos.y += amt;
-        }       
-        
-        self.dith_sh.time += 0.01;
+            movement.y += amt;
+        }
+        self.orbit.move_target(&movement);
+        self.orbit.update(&self.input, &mut self.cam, self.clock.dt);
     }
 
-    fn render_tick(&self, screen: &mut Screen) {
-        screen.clear(&Color::new(0, 190, 255, 255));
-        let sh = DummyPassthruShader;
-
+    // Floor, upper floor and the two walls of the little room in the middle
+    fn room() -> [Vec<Triangle>; 4] {
         let floor_tris = TriangleGen::create_floor_rect(
             Vector2::new(-1.0, -1.5),
             Vector2::new(1.0, 1.5),
@@ -67,7 +177,8 @@ impl Game for Nameless3DThing {
             Color::new(10, 128, 50, 255),
         );
 
-        let wall1_tris = TriangleGen::create_wall(
+        // Solid, with a window cut through it
+        let wall1 = Csg::from_triangles(&TriangleGen::create_wall_block(
             &Vector3 {
                 x: -1.0,
                 y: 0.0,
@@ -75,11 +186,16 @@ impl Game for Nameless3DThing {
             },
             2.0,
             1.5,
+            0.1,
             0.0,
             &Color::new(0, 0, 20, 255),
-        );
-
-        let mut draw_list = DrawList::new();
+        ));
+        let window = Csg::from_triangles(&TriangleGen::create_box(
+            &Vector3::new(0.0, 0.8, -1.5),
+            &Vector3::new(0.8, 0.5, 0.4),
+            &Color::new(0, 0, 20, 255),
+        ));
+        let wall1_tris = wall1.subtract(&window).to_triangles();
 
         let wall2_tris = TriangleGen::create_wall(
             &Vector3 {
@@ -93,53 +209,316 @@ impl Game for Nameless3DThing {
             &Color::new(0, 0, 20, 255),
         );
 
-        draw_list.add(&wall2_tris);
+        [floor_tris, floor2_tris, wall1_tris, wall2_tris]
+    }
+
+    // Centered on the room and reaching past the -50..50 flat floor it
+    // replaced at y = -5, dipping a few units either side of it. Noise
+    // stands in when terrain.png is missing or unreadable.
+    fn terrain() -> Terrain {
+        let heightmap = Heightmap::from_png_file("terrain.png", 6.0)
+            .unwrap_or_else(|_| Heightmap::from_noise(129, 129, &Perlin::new(7), 0.04, 4, 3.0));
+        let cell_size = 150.0 / (heightmap.width.max(heightmap.depth) - 1) as f32;
+        Terrain::new(heightmap, Vector3::new(-75.0, -5.0, -75.0), cell_size).with_lod_distances(vec![15.0, 30.0, 50.0])
+    }
+
+    // Frames 0 to 3 are an orb pulsing, frame 4 is a tree, all 16 pixels
+    // square on a transparent background
+    // Eight of them in a ring just outside the flat part around the room
+    fn posts(terrain: &Terrain) -> DrawList {
+        let mut posts = DrawList::new();
+        for i in 0..8 {
+            let angle = i as f32 * std::f32::consts::TAU / 8.0;
+            let (x, z) = (angle.cos() * 9.0, angle.sin() * 9.0);
+            let Some(y) = terrain.height_at(x, z) else {
+                continue;
+            };
+            posts.add(&TriangleGen::create_cylinder(
+                &Vector3::new(x, y + 1.0, z),
+                0.1,
+                2.0,
+                8,
+                true,
+                &Color::new(90, 60, 30, 255),
+            ));
+        }
+        posts
+    }
+
+    fn sprite_sheet() -> SpriteSheet {
+        let mut texture = Texture::new(16 * 5, 16);
+        texture.pixels.fill(Color::new(0, 0, 0, 0));
+        for frame in 0..4 {
+            let radius = 4.0 + frame as f32 * 1.2;
+            for y in 0..16 {
+                for x in 0..16 {
+                    let (dx, dy) = (x as f32 - 7.5, y as f32 - 7.5);
+                    let r = (dx * dx + dy * dy).sqrt();
+                    if r < radius {
+                        let glow = (255.0 * (1.0 - r / radius * 0.6)) as u8;
+                        *texture.get_pixel_mut(frame * 16 + x, y) = Color::new(255, glow, 60, 255);
+                    }
+                }
+            }
+        }
+        for y in 0..16 {
+            for x in 0..16 {
+                let half_width = y as f32 * 0.55;
+                let color = if y < 12 && (x as f32 - 7.5).abs() < half_width {
+                    Color::new(30, 110 + y as u8 * 5, 40, 255)
+                } else if y >= 12 && (7..9).contains(&x) {
+                    Color::new(90, 60, 30, 255)
+                } else {
+                    continue;
+                };
+                *texture.get_pixel_mut(64 + x, y) = color;
+            }
+        }
+        SpriteSheet::new(texture, 5, 1)
+    }
+
+    // A ring of trees outside the posts, and the orbs with their indices
+    fn sprites(terrain: &Terrain) -> (BillboardBatch, Vec<usize>) {
+        let mut sprites = BillboardBatch::new(Self::sprite_sheet());
+        for i in 0..12 {
+            let angle = (i as f32 + 0.5) * std::f32::consts::TAU / 12.0;
+            let (x, z) = (angle.cos() * 12.0, angle.sin() * 12.0);
+            let Some(y) = terrain.height_at(x, z) else {
+                continue;
+            };
+            sprites.add(
+                Billboard::new(Vector3::new(x, y, z), Vector2::new(2.0, 3.0))
+                    .with_pivot(Vector2::new(0.5, 0.0))
+                    .with_mode(BillboardMode::Cylindrical)
+                    .with_frame(4),
+            );
+        }
+        let orbs = [Vector3::new(-0.5, 1.0, -0.8), Vector3::new(0.5, 1.3, 0.0), Vector3::new(-0.3, 0.8, 0.9)]
+            .iter()
+            .map(|&p| sprites.add(Billboard::new(p, Vector2::new(0.3, 0.3))))
+            .collect();
+        (sprites, orbs)
+    }
+
+    // Small splat layer, a color with some noise in it so tiling shows
+    fn speckled(color: Color, seed: u32) -> Texture {
+        let noise = Perlin::new(seed);
+        let mut texture = Texture::new(16, 16);
+        for y in 0..16 {
+            for x in 0..16 {
+                let shade = 1.0 + noise.fbm(x as f32 * 0.4, y as f32 * 0.4, 2, 2.0, 0.5) * 0.25;
+                let channel = |c: u8| (c as f32 * shade).clamp(0.0, 255.0) as u8;
+                *texture.get_pixel_mut(x, y) = Color::new(channel(color.r), channel(color.g), channel(color.b), 255);
+            }
+        }
+        texture
+    }
+
+    // What the follow camera's arm bumps into
+    fn collision_scene(terrain: &Terrain, room: &[Vec<Triangle>]) -> DrawList {
+        let mut scene = terrain.full_detail();
+        for part in room {
+            scene.add(part);
+        }
+        scene
+    }
+
+    // Carry on from wherever the path left the camera, with the lens it had
+    // before
+    fn end_flythrough(&mut self) {
+        (self.cam.fov, self.cam.roll) = self.lens_before_flythrough;
+        self.orbit = OrbitController::from_camera(&self.cam);
+        self.fly = FlyController::from_camera(&self.cam);
+    }
+
+    // Walks the walker round the room, facing where it's going, with the
+    // follow rig behind it. Left click shakes the camera.
+    fn update_follow(&mut self) {
+        let angle = self.clock.time * 0.4;
+        let radius = 2.5;
+        self.walker.position = Vector3::new(angle.cos() * radius, 0.0, angle.sin() * radius);
+        // Tangent of the circle, see Transform::forward for the yaw convention
+        self.walker.yaw = (-angle.sin()).atan2(-angle.cos());
+
+        if self.input.mouse_left.click {
+            self.follow.shake.add_trauma(0.5);
+        }
+        self.follow.update(&self.walker, Some(&self.collision), &mut self.cam, self.clock.dt);
+    }
+
+    // Always shown: a red line down onto the origin and whatever the follow
+    // camera is tracking
+    fn queue_markers(&self) {
+        DebugDraw::line(&Vector3::new(0.0, 10.0, 0.0), &Vector3::new(0.0, 0.0, 0.0), &Color::new(255, 0, 0, 255), 3.0);
+        let head = self.walker.position + Vector3::new(0.0, 1.2, 0.0);
+        DebugDraw::line(&self.walker.position, &head, &Color::new(255, 200, 0, 255), 8.0);
+    }
+
+    fn queue_debug_shapes(&self) {
+        DebugDraw::axes(&Transform::new(Vector3::new(0.0, 0.0, 0.0)), 1.0, 3.0);
+        DebugDraw::axes(&Transform::new(self.orbit.target), 0.5, 2.0);
+        DebugDraw::grid(&Vector3::new(0.0, 0.0, 0.0), 10.0, 10, &Color::new(60, 60, 60, 255), 1.0);
+        DebugDraw::wire_box(&self.room_bounds, &Color::new(255, 255, 0, 255), 2.0);
+
+        let head = self.walker.position + Vector3::new(0.0, 1.2, 0.0);
+        DebugDraw::arrow(&head, &(head + self.walker.forward() * 0.8), &Color::new(255, 0, 255, 255), 2.0);
+        let middle = self.walker.position + Vector3::new(0.0, 0.6, 0.0);
+        DebugDraw::wire_sphere(&middle, 0.6, &Color::new(0, 255, 255, 255), 1.0);
+
+        if self.layout == ViewLayout::Minimap {
+            let minimap = self.minimap();
+            let aspect_ratio = minimap.rect.size.x / minimap.rect.size.y;
+            DebugDraw::frustum(&minimap.camera, aspect_ratio, 25.0, &Color::new(255, 128, 0, 255), 1.0);
+        }
+    }
+
+    // Top-down view of the area around the orbit target, in the top right corner
+    fn minimap(&self) -> Viewport {
+        let mut camera = Camera::new();
+        camera.pointing_at = self.orbit.target;
+        camera.pos = self.orbit.target + Vector3::new(0.0, 20.0, 0.0);
+        camera.projection = Projection::Orthographic;
+        camera.projection_blend = 1.0;
+        camera.ortho_height = 12.0;
+        let size = Vector2::new(160.0, 120.0);
+        let rect = Rect::new(Vector2::new(SCREEN_WIDTH as f32 - size.x - 8.0, 8.0), size);
+        Viewport::new(rect, camera).with_clear_color(Color::new(20, 20, 40, 255))
+    }
+
+    // Panes of the current layout in drawing order, each with its camera
+    fn views(&self) -> Vec<Viewport> {
+        match self.layout {
+            ViewLayout::Single => vec![Viewport::full_screen(self.cam.clone())],
+            ViewLayout::Minimap => vec![Viewport::full_screen(self.cam.clone()), self.minimap()],
+            ViewLayout::FourView => {
+                let mut views = Viewport::four_view(self.orbit.target, 15.0, 8.0);
+                // The perspective pane follows the controllers
+                if let Some(perspective) = views.last_mut() {
+                    perspective.camera = self.cam.clone();
+                }
+                views
+            }
+        }
+    }
+
+    fn draw_scene(&self, screen: &mut Screen, cam: &Camera) {
+        let sh = DummyPassthruShader;
 
         let super_shader = SuperShader::new(vec![
             Box::new(EvenLineMissingShader),
             Box::new(DitherShader),
         ]);
+        self.room_striped.draw(screen, cam, &super_shader, &self.tex);
+        self.room_floor.draw(screen, cam, &sh, &self.tex);
+        self.room_wall.draw(screen, cam, &self.dith_sh, &self.tex);
 
-        let mut elm_dl = DrawList::new();
-        elm_dl.add(&floor2_tris);
-        elm_dl.add(&wall1_tris);
+        self.terrain.draw(screen, cam, &self.terrain_splat, &self.tex);
 
-        elm_dl.draw(screen, &self.cam, &super_shader, &self.tex);
+        self.sprites.draw(screen, cam);
 
-        let mut sh_dl = DrawList::new();
-        sh_dl.add(&floor_tris);
-        sh_dl.draw(screen, &self.cam, &sh, &self.tex);
+        self.posts.draw(screen, cam, &sh, &self.tex);
+    }
+}
 
-        draw_list.draw(screen, &self.cam, &self.dith_sh, &self.tex);
+impl Game for Nameless3DThing {
+    fn update_tick(&mut self) {
+        self.clock.tick();
 
-        // Add this to the render_tick method
-        let big_floor = TriangleGen::create_floor_rect(
-            Vector2::new(-50.0, -50.0),
-            Vector2::new(100.0, 100.0), 
-            -5.0,
-            Color::new(0, 0, 0, 255)
-        );
+        if self.input.g.click {
+            self.show_debug = !self.show_debug;
+        }
+        if self.show_debug {
+            self.queue_debug_shapes();
+        }
+        self.queue_markers();
 
-        let mut big_floor_dl = DrawList::new();
-        big_floor_dl.add(&big_floor);
-        big_floor_dl.draw(screen, &self.cam, &sh, &self.tex);
-
-        let start = Vector3::new(0.0, 10.0, 0.0);
-        let end = Vector3::new(0.0, 0.0, 0.0);
-        let line_tris = TriangleGen::create_3d_line(
-            &start,
-            &end,
-            &self.cam,
-            &Color::new(255, 0, 0, 255),  // Start color (red)
-            &Color::new(255, 0, 0, 255),  // End color (red)
-            0.1  // Line thickness
-        );
+        if self.input.enter.click {
+            if self.flythrough.is_playing() {
+                self.flythrough.stop();
+                self.end_flythrough();
+            } else {
+                self.lens_before_flythrough = (self.cam.fov, self.cam.roll);
+                self.flythrough.play(&self.clock);
+            }
+        }
+        if self.flythrough.is_playing() {
+            if !self.flythrough.update(&self.clock, &mut self.cam) {
+                self.end_flythrough();
+            }
+            self.input.new_frame();
+            return;
+        }
 
-        // Create a draw list for the line
-        let mut line_dl = DrawList::new();
-        line_dl.add(&line_tris);
-        line_dl.draw(screen, &self.cam, &sh, &self.tex);
-}
+        if self.input.m.click {
+            self.layout = match self.layout {
+                ViewLayout::Single => ViewLayout::Minimap,
+                ViewLayout::Minimap => ViewLayout::FourView,
+                ViewLayout::FourView => ViewLayout::Single,
+            };
+        }
+
+        // Eases between perspective and orthographic, see Camera::update
+        if self.input.p.click {
+            self.cam.toggle_projection();
+        }
+
+        if self.input.f.click {
+            self.follow_mode = !self.follow_mode;
+            if self.follow_mode {
+                self.follow.snap(&self.walker);
+            } else {
+                self.orbit = OrbitController::from_camera(&self.cam);
+                self.fly = FlyController::from_camera(&self.cam);
+            }
+        }
+
+        if self.input.tab.click {
+            self.fly_mode = !self.fly_mode;
+            if self.fly_mode {
+                self.fly = FlyController::from_camera(&self.cam);
+            } else {
+                self.orbit = OrbitController::from_camera(&self.cam);
+            }
+        }
+
+        if self.follow_mode {
+            self.update_follow();
+        } else if self.fly_mode {
+            self.fly.update(&self.input, &mut self.cam, self.clock.dt);
+        } else {
+            self.update_orbit();
+        }
+        self.cam.update(self.clock.dt);
+
+        self.dith_sh.time += 0.01;
+
+        // Out of step so they don't all pulse together
+        for (i, &orb) in self.orbs.iter().enumerate() {
+            self.sprites.billboards[orb].frame = self.orb_animation.frame_at(self.clock.time + i as f32 * 0.25);
+        }
+
+        // Events for the next frame accumulate from here
+        self.input.new_frame();
+    }
+
+    fn render_tick(&self, screen: &mut Screen) {
+        screen.clear(&Color::new(0, 190, 255, 255));
+
+        let views = self.views();
+        // Only the pane following the controllers has fog
+        let main = if self.layout == ViewLayout::FourView { views.len() - 1 } else { 0 };
+        for (i, view) in views.iter().enumerate() {
+            view.begin(screen);
+            screen.fog = if i == main { self.fog } else { Fog::off() };
+            self.draw_scene(screen, &view.camera);
+            // The minimap covers part of the main view, which lines drawn
+            // after the frame would go over, so they go in pane by pane
+            if self.layout == ViewLayout::Minimap {
+                DebugDraw::draw(screen, &view.camera);
+            }
+        }
+        screen.reset_viewport();
+    }
 
     fn key_event(&mut self, key_ev: &KeyEvent) {
         self.input.handle_key_event(&key_ev);
@@ -149,17 +528,13 @@ impl Game for Nameless3DThing {
     
     fn mouse_event(&mut self, mouse_ev: &MouseEvent) {
         self.input.handle_mouse_event(&mouse_ev);
+    }
 
-        let screen_res = Vector2::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32);
-        /*if self.input.mouse_left.pressed {
-            self.cam.first_person_look(&self.input.mouse_delta, &screen_res)
-        }*/
-        if self.input.mouse_left/*_right*/.pressed {
-            self.cam.drag_move(&self.input.mouse_pos_last, &self.input.mouse_pos, &screen_res)
-        }
+    fn wants_relative_mouse(&self) -> bool {
+        self.fly_mode && !self.follow_mode
+    }
 
-        if self.input.scroll_y != 0 {
-            self.cam.zoom(self.input.scroll_y as f32)
-        }
+    fn debug_views(&self) -> Vec<Viewport> {
+        self.views()
     }
 }
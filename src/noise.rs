@@ -0,0 +1,76 @@
+// Classic 2D Perlin gradient noise, seeded so the same seed always gives the
+// same terrain
+#[derive(Clone)]
+pub struct Perlin {
+    // Permutation of 0..256 repeated twice, so lookups never need wrapping
+    perm: [u8; 512],
+}
+
+// Gradient directions, unit-ish vectors spread around the circle
+const GRADIENTS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (0.70710677, 0.70710677),
+    (-0.70710677, 0.70710677),
+    (0.70710677, -0.70710677),
+    (-0.70710677, -0.70710677),
+];
+
+impl Perlin {
+    pub fn new(seed: u32) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        // xorshift, never zero so it can't get stuck
+        let mut state = seed.wrapping_mul(0x9E37_79B9) | 1;
+        for i in (1..256).rev() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            table.swap(i, state as usize % (i + 1));
+        }
+        Perlin {
+            perm: std::array::from_fn(|i| table[i & 255]),
+        }
+    }
+
+    // Roughly -1..1, zero at every integer lattice point
+    pub fn noise(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (xi, yi) = ((x0 as i32 & 255) as usize, (y0 as i32 & 255) as usize);
+
+        let gradient_dot = |ix: usize, iy: usize, dx: f32, dy: f32| {
+            let hash = self.perm[self.perm[ix] as usize + iy];
+            let (gx, gy) = GRADIENTS[hash as usize & 7];
+            gx * dx + gy * dy
+        };
+        let n00 = gradient_dot(xi, yi, fx, fy);
+        let n10 = gradient_dot(xi + 1, yi, fx - 1.0, fy);
+        let n01 = gradient_dot(xi, yi + 1, fx, fy - 1.0);
+        let n11 = gradient_dot(xi + 1, yi + 1, fx - 1.0, fy - 1.0);
+
+        // Quintic fade, so the surface has no creases at cell borders
+        let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let (u, v) = (fade(fx), fade(fy));
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        lerp(lerp(n00, n10, u), lerp(n01, n11, u), v) * std::f32::consts::SQRT_2
+    }
+
+    // Fractal Brownian motion: `octaves` layers of noise, each `lacunarity`
+    // times the frequency and `gain` times the amplitude of the last.
+    // Normalized back to roughly -1..1.
+    pub fn fbm(&self, x: f32, y: f32, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut total_amplitude = 0.0;
+        for _ in 0..octaves.max(1) {
+            sum += self.noise(x * frequency, y * frequency) * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= gain;
+            frequency *= lacunarity;
+        }
+        sum / total_amplitude
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    color::Color, material::Material, triangle::Triangle, vec2::Vector2, vec3::Vector3,
+    vertex::Vertex,
+};
+
+// Triangles sharing an object, group and material, ready for a DrawList
+pub struct ObjMesh {
+    pub object: String,
+    pub group: String,
+    // Index into ObjModel::materials
+    pub material: Option<usize>,
+    pub triangles: Vec<Triangle>,
+}
+
+// A Wavefront OBJ file split into meshes, plus the materials its MTL files
+// defined. Vertex colors are the material's diffuse color, texture
+// coordinates are flipped so v = 0 is the top of the image like
+// Texture::sample expects.
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<Material>,
+}
+
+// Resolves a 1-based (or negative, counting back from the end) OBJ index
+fn resolve_index(index: &str, count: usize, line: usize, what: &str) -> std::io::Result<usize> {
+    let invalid = |msg: String| Error::new(ErrorKind::InvalidData, format!("line {}: {}", line, msg));
+    let i: i64 = index
+        .parse()
+        .map_err(|_| invalid(format!("bad {} index '{}'", what, index)))?;
+    let resolved = if i < 0 { count as i64 + i } else { i - 1 };
+    if i == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(invalid(format!("{} index {} out of range", what, i)));
+    }
+    Ok(resolved as usize)
+}
+
+// Ear clipping in the plane the polygon faces most, so concave faces come
+// out right. Triangles keep the polygon's winding.
+fn triangulate(points: &[Vector3]) -> Vec<[usize; 3]> {
+    // Newell's method, which copes with concave and slightly bent polygons
+    let mut normal = Vector3::new(0.0, 0.0, 0.0);
+    for (i, a) in points.iter().enumerate() {
+        let b = &points[(i + 1) % points.len()];
+        normal.x += (a.y - b.y) * (a.z + b.z);
+        normal.y += (a.z - b.z) * (a.x + b.x);
+        normal.z += (a.x - b.x) * (a.y + b.y);
+    }
+    let (nx, ny, nz) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+    let flat: Vec<(f32, f32)> = points
+        .iter()
+        .map(|p| {
+            if nz >= nx && nz >= ny {
+                (p.x, p.y)
+            } else if nx >= ny {
+                (p.y, p.z)
+            } else {
+                (p.z, p.x)
+            }
+        })
+        .collect();
+    let cross = |o: usize, a: usize, b: usize| {
+        let (o, a, b) = (flat[o], flat[a], flat[b]);
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    // Sign of the projected area tells which turn is convex
+    let winding = (1..points.len() - 1).map(|k| cross(0, k, k + 1)).sum::<f32>().signum();
+    let inside = |p: usize, a: usize, b: usize, c: usize| {
+        cross(a, b, p) * winding >= 0.0 && cross(b, c, p) * winding >= 0.0 && cross(c, a, p) * winding >= 0.0
+    };
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = vec![];
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+        let ear = (0..n).find(|&i| {
+            let (prev, cur, next) = corner(i);
+            cross(prev, cur, next) * winding > 0.0
+                && remaining
+                    .iter()
+                    .all(|&p| p == prev || p == cur || p == next || !inside(p, prev, cur, next))
+        });
+        // Only self-intersecting or degenerate polygons run out of ears,
+        // those get cut up from the first corner
+        let i = ear.unwrap_or(0);
+        let (prev, cur, next) = corner(i);
+        triangles.push([prev, cur, next]);
+        remaining.remove(i);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+impl ObjModel {
+    #[allow(dead_code)]
+    pub fn from_obj_file(file_path: &str) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(file_path)?;
+        let base_dir = Path::new(file_path).parent();
+        Self::from_obj_str(&text, base_dir).map_err(|e| {
+            Error::new(e.kind(), format!("{}: {}", file_path, e))
+        })
+    }
+
+    // mtllib files are looked up relative to `base_dir`, without one they
+    // are skipped. A usemtl naming no loaded material leaves the mesh
+    // without one either way, the same as a file without an MTL.
+    pub fn from_obj_str(text: &str, base_dir: Option<&Path>) -> std::io::Result<Self> {
+        let invalid = |line: usize, msg: &str| {
+            Error::new(ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+        };
+
+        let mut positions: Vec<Vector3> = vec![];
+        let mut colors: Vec<Option<Color>> = vec![];
+        let mut uvs: Vec<Vector2> = vec![];
+        let mut normals: Vec<Vector3> = vec![];
+
+        let mut model = ObjModel {
+            meshes: vec![],
+            materials: vec![],
+        };
+        let mut current = ObjMesh {
+            object: String::new(),
+            group: String::new(),
+            material: None,
+            triangles: vec![],
+        };
+
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let rest = line[parts[0].len()..].trim();
+            let numbers = |min: usize| -> std::io::Result<Vec<f32>> {
+                let values = parts[1..]
+                    .iter()
+                    .map(|s| s.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid(line_no, &format!("bad number in {}", parts[0])))?;
+                if values.len() < min {
+                    return Err(invalid(line_no, &format!("{} needs {} values", parts[0], min)));
+                }
+                Ok(values)
+            };
+
+            match parts[0] {
+                "v" => {
+                    let v = numbers(3)?;
+                    positions.push(Vector3::new(v[0], v[1], v[2]));
+                    // Some exporters append an RGB vertex color
+                    colors.push(if v.len() >= 6 {
+                        let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+                        Some(Color::new(to_byte(v[3]), to_byte(v[4]), to_byte(v[5]), 255))
+                    } else {
+                        None
+                    });
+                }
+                "vt" => {
+                    let v = numbers(1)?;
+                    uvs.push(Vector2::new(v[0], 1.0 - v.get(1).copied().unwrap_or(0.0)));
+                }
+                "vn" => {
+                    let v = numbers(3)?;
+                    normals.push(Vector3::new(v[0], v[1], v[2]).normalize_v());
+                }
+                "f" => {
+                    if parts.len() < 4 {
+                        return Err(invalid(line_no, "face needs at least 3 vertices"));
+                    }
+                    let diffuse = current
+                        .material
+                        .map_or(Color::new(255, 255, 255, 255), |m| model.materials[m].diffuse);
+
+                    let mut corners = vec![];
+                    for corner in &parts[1..] {
+                        // v, v/vt, v//vn or v/vt/vn
+                        let mut refs = corner.split('/');
+                        let pos_index = resolve_index(refs.next().unwrap_or(""), positions.len(), line_no, "vertex")?;
+                        let uv = match refs.next() {
+                            Some(s) if !s.is_empty() => uvs[resolve_index(s, uvs.len(), line_no, "texture")?],
+                            _ => Vector2::new(0.0, 0.0),
+                        };
+                        let normal = match refs.next() {
+                            Some(s) if !s.is_empty() => normals[resolve_index(s, normals.len(), line_no, "normal")?],
+                            _ => Vector3::new(0.0, 0.0, 0.0),
+                        };
+                        let color = colors[pos_index].unwrap_or(diffuse);
+                        corners.push(Vertex::new(&positions[pos_index], &uv, &color).with_normal(&normal));
+                    }
+
+                    let points: Vec<Vector3> = corners.iter().map(|c| c.pos).collect();
+                    for [a, b, c] in triangulate(&points) {
+                        current.triangles.push(Triangle::new(
+                            corners[a].clone(),
+                            corners[b].clone(),
+                            corners[c].clone(),
+                        ));
+                    }
+                }
+                "o" | "g" | "usemtl" => {
+                    let material = if parts[0] == "usemtl" {
+                        model.materials.iter().position(|m| m.name == rest)
+                    } else {
+                        current.material
+                    };
+                    let next = ObjMesh {
+                        object: if parts[0] == "o" { rest.to_string() } else { current.object.clone() },
+                        group: if parts[0] == "g" { rest.to_string() } else { current.group.clone() },
+                        material,
+                        triangles: vec![],
+                    };
+                    let finished = std::mem::replace(&mut current, next);
+                    if !finished.triangles.is_empty() {
+                        model.meshes.push(finished);
+                    }
+                }
+                "mtllib" => {
+                    if let Some(dir) = base_dir {
+                        let path = dir.join(rest);
+                        let materials = Material::from_mtl_file(&path.to_string_lossy())
+                            .map_err(|e| invalid(line_no, &e.to_string()))?;
+                        model.materials.extend(materials);
+                    }
+                }
+                // Smoothing groups, lines, points and curves aren't used
+                _ => {}
+            }
+        }
+
+        if !current.triangles.is_empty() {
+            model.meshes.push(current);
+        }
+        Ok(model)
+    }
+
+    #[allow(dead_code)]
+    pub fn triangles(&self) -> impl Iterator<Item = &Triangle> {
+        self.meshes.iter().flat_map(|mesh| &mesh.triangles)
+    }
+
+    #[allow(dead_code)]
+    pub fn mesh_material(&self, mesh: &ObjMesh) -> Option<&Material> {
+        mesh.material.map(|m| &self.materials[m])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area_and_normal(tri: &Triangle) -> Vector3 {
+        (tri.v2.pos - tri.v1.pos).cross(&(tri.v3.pos - tri.v1.pos)) * 0.5
+    }
+
+    #[test]
+    fn concave_face_is_ear_clipped() {
+        // L shape, counter-clockwise seen from +z. A fan from the first
+        // corner would cut across the notch.
+        let text = "v 2 1 0\nv 1 1 0\nv 1 2 0\nv 0 2 0\nv 0 0 0\nv 2 0 0\nf 1 2 3 4 5 6\n";
+        let model = ObjModel::from_obj_str(text, None).unwrap();
+        let triangles: Vec<&Triangle> = model.triangles().collect();
+        assert_eq!(triangles.len(), 4);
+        let mut area = 0.0;
+        for tri in &triangles {
+            // Same winding as the polygon, and nothing across the notch
+            let normal = area_and_normal(tri);
+            assert!(normal.z > 0.0);
+            area += normal.z;
+            let center = (tri.v1.pos + tri.v2.pos + tri.v3.pos) * (1.0 / 3.0);
+            assert!(center.x < 1.0 || center.y < 1.0);
+        }
+        assert!((area - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn attributes_and_negative_indices() {
+        let text = "v 0 0 0 1 0 0\nv 1 0 0\nv 0 1 0\nvt 0.25 0.25\nvn 0 0 2\nf -3/1/1 -2/1/1 -1/1/1\n";
+        let model = ObjModel::from_obj_str(text, None).unwrap();
+        let tri = model.triangles().next().unwrap();
+        assert_eq!((tri.v1.color.r, tri.v1.color.g), (255, 0));
+        // Without a vertex color or a material it's white
+        assert_eq!((tri.v2.color.r, tri.v2.color.g), (255, 255));
+        assert_eq!((tri.v1.texture_coord.x, tri.v1.texture_coord.y), (0.25, 0.75));
+        assert_eq!((tri.v3.normal.x, tri.v3.normal.y, tri.v3.normal.z), (0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn objects_groups_and_materials_split_meshes() {
+        let dir = std::env::temp_dir().join(format!("obj_model_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("test.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+        let text = "mtllib test.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\n\
+                    o first\nusemtl red\nf 1 2 3\n\
+                    g second\nf 1 2 3\n\
+                    usemtl missing\nf 1 2 3\n";
+        let model = ObjModel::from_obj_str(text, Some(&dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(model.materials.len(), 1);
+        let summary: Vec<(&str, &str, Option<usize>)> =
+            model.meshes.iter().map(|m| (m.object.as_str(), m.group.as_str(), m.material)).collect();
+        assert_eq!(summary, [("first", "", Some(0)), ("first", "second", Some(0)), ("first", "second", None)]);
+        let red = &model.meshes[0].triangles[0].v1.color;
+        assert_eq!((red.r, red.g, red.b), (255, 0, 0));
+
+        // Without a directory the mtllib is skipped and usemtl finds nothing
+        let model = ObjModel::from_obj_str(text, None).unwrap();
+        assert!(model.meshes.iter().all(|m| m.material.is_none()));
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = |text: &str| ObjModel::from_obj_str(text, None).err().unwrap().to_string();
+        assert_eq!(error("v 0 0 0\nf 1 2 3\n"), "line 2: vertex index 2 out of range");
+        assert_eq!(error("# comment\n\nv 0 x 0\n"), "line 3: bad number in v");
+        assert_eq!(error("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1/4 2 3\n"), "line 4: texture index 4 out of range");
+        assert_eq!(error("v 0 0 0\nf 1 1\n"), "line 2: face needs at least 3 vertices");
+        assert_eq!(error("v 0 0\n"), "line 1: v needs 3 values");
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{camera::Camera, input_handler::InputHandler, mouse_button::MouseButton, vec3::Vector3};
+
+pub struct OrbitBindings {
+    pub rotate: MouseButton,
+    pub pan: MouseButton,
+}
+
+// Orbits a camera around a target point: drag to rotate or pan, scroll to dolly.
+// Input moves the goal values and the camera eases towards them every update.
+pub struct OrbitController {
+    pub target: Vector3,
+    // Radians around the Y axis, 0 looks down -Z
+    pub yaw: f32,
+    // Radians above the horizon
+    pub pitch: f32,
+    pub distance: f32,
+
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+
+    // Radians per pixel of drag
+    pub rotate_sensitivity: f32,
+    // World units per pixel of drag, per unit of distance from the target
+    pub pan_sensitivity: f32,
+    // Fraction of the distance covered per scroll step
+    pub dolly_speed: f32,
+    // How quickly the camera catches up with input, higher is snappier
+    pub damping: f32,
+    pub bindings: OrbitBindings,
+
+    goal_target: Vector3,
+    goal_yaw: f32,
+    goal_pitch: f32,
+    goal_distance: f32,
+}
+
+impl OrbitController {
+    pub fn new(target: Vector3, yaw: f32, pitch: f32, distance: f32) -> Self {
+        let limit = 89.0f32.to_radians();
+        OrbitController {
+            target,
+            yaw,
+            pitch: pitch.clamp(-limit, limit),
+            distance,
+            min_pitch: -limit,
+            max_pitch: limit,
+            min_distance: 0.5,
+            max_distance: 100.0,
+            rotate_sensitivity: 0.008,
+            pan_sensitivity: 0.0015,
+            dolly_speed: 0.1,
+            damping: 15.0,
+            bindings: OrbitBindings {
+                rotate: MouseButton::Left,
+                pan: MouseButton::Right,
+            },
+            goal_target: target,
+            goal_yaw: yaw,
+            goal_pitch: pitch.clamp(-limit, limit),
+            goal_distance: distance,
+        }
+    }
+
+    // Picks up wherever the camera currently is
+    pub fn from_camera(cam: &Camera) -> Self {
+        let offset = cam.pos - cam.pointing_at;
+        let distance = offset.length().max(0.0001);
+        let yaw = offset.x.atan2(offset.z);
+        let pitch = (offset.y / distance).clamp(-1.0, 1.0).asin();
+        Self::new(cam.pointing_at, yaw, pitch, distance)
+    }
+
+    pub fn rotate(&mut self, yaw_delta: f32, pitch_delta: f32) {
+        self.goal_yaw += yaw_delta;
+        self.goal_pitch = (self.goal_pitch + pitch_delta).clamp(self.min_pitch, self.max_pitch);
+    }
+
+    pub fn pan(&mut self, right_amount: f32, up_amount: f32) {
+        let (right, up) = self.screen_axes();
+        self.goal_target = self.goal_target + right * right_amount + up * up_amount;
+    }
+
+    // Shifts the whole orbit in world space without smoothing
+    pub fn move_target(&mut self, offset: &Vector3) {
+        self.target += *offset;
+        self.goal_target += *offset;
+    }
+
+    // Positive amounts move closer to the target
+    pub fn dolly(&mut self, amount: f32) {
+        self.goal_distance =
+            (self.goal_distance * (1.0 - amount)).clamp(self.min_distance, self.max_distance);
+    }
+
+    // Centers an axis-aligned box and backs off until it fits the field of view
+    #[allow(dead_code)]
+    pub fn frame_bounds(&mut self, min: &Vector3, max: &Vector3, fov_deg: f32) {
+        let center = (*min + *max) * 0.5;
+        let radius = (*max - *min).length() * 0.5;
+        let half_fov = (fov_deg.to_radians() / 2.0).max(0.01);
+        self.goal_target = center;
+        self.goal_distance = (radius / half_fov.sin()).clamp(self.min_distance, self.max_distance);
+    }
+
+    // Jumps straight to the goal, skipping the smoothing
+    #[allow(dead_code)]
+    pub fn snap(&mut self) {
+        self.target = self.goal_target;
+        self.yaw = self.goal_yaw;
+        self.pitch = self.goal_pitch;
+        self.distance = self.goal_distance;
+    }
+
+    pub fn update(&mut self, input: &InputHandler, cam: &mut Camera, dt: f32) {
+        let delta = input.mouse_delta;
+        if input.mouse_button(self.bindings.rotate).pressed {
+            self.rotate(-delta.x * self.rotate_sensitivity, delta.y * self.rotate_sensitivity);
+        }
+        if input.mouse_button(self.bindings.pan).pressed {
+            let scale = self.pan_sensitivity * self.distance;
+            self.pan(-delta.x * scale, delta.y * scale);
+        }
+        if input.scroll_y != 0 {
+            self.dolly(input.scroll_y as f32 * self.dolly_speed);
+        }
+
+        // Frame rate independent exponential smoothing
+        let t = if self.damping > 0.0 { 1.0 - (-self.damping * dt).exp() } else { 1.0 };
+        self.target = self.target + (self.goal_target - self.target) * t;
+        self.yaw += (self.goal_yaw - self.yaw) * t;
+        self.pitch += (self.goal_pitch - self.pitch) * t;
+        self.distance += (self.goal_distance - self.distance) * t;
+
+        self.apply(cam);
+    }
+
+    pub fn apply(&self, cam: &mut Camera) {
+        cam.pointing_at = self.target;
+        cam.pos = self.target + self.offset_dir() * self.distance;
+    }
+
+    // Unit vector from the target towards the camera
+    fn offset_dir(&self) -> Vector3 {
+        Vector3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        )
+    }
+
+    fn screen_axes(&self) -> (Vector3, Vector3) {
+        let forward = self.offset_dir() * -1.0;
+        // Built from yaw alone so it never degenerates at the pitch limits
+        let right = Vector3::new(self.yaw.cos(), 0.0, -self.yaw.sin());
+        let up = right.cross(&forward);
+        (right, up)
+    }
+}
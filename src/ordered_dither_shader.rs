@@ -0,0 +1,196 @@
+use crate::{color::Color, palette::Palette, pixel_placement::PixelPlacement, pixel_shader::PixelShader, triangle::Triangle};
+
+// Tiled threshold map, values in 0..1 with every rank used once
+#[derive(Clone)]
+pub struct DitherMatrix {
+    pub size: usize,
+    values: Vec<f32>,
+}
+
+impl DitherMatrix {
+    #[allow(dead_code)]
+    pub fn bayer2() -> Self {
+        Self::bayer(1)
+    }
+
+    #[allow(dead_code)]
+    pub fn bayer4() -> Self {
+        Self::bayer(2)
+    }
+
+    #[allow(dead_code)]
+    pub fn bayer8() -> Self {
+        Self::bayer(3)
+    }
+
+    // Recursive Bayer construction for a 2^levels square matrix
+    fn bayer(levels: u32) -> Self {
+        let mut size = 1;
+        let mut ranks = vec![0u32];
+        for _ in 0..levels {
+            let next_size = size * 2;
+            let mut next = vec![0u32; next_size * next_size];
+            for y in 0..size {
+                for x in 0..size {
+                    let r = ranks[y * size + x] * 4;
+                    next[y * next_size + x] = r;
+                    next[y * next_size + x + size] = r + 2;
+                    next[(y + size) * next_size + x] = r + 3;
+                    next[(y + size) * next_size + x + size] = r + 1;
+                }
+            }
+            size = next_size;
+            ranks = next;
+        }
+        Self::from_ranks(size, &ranks)
+    }
+
+    // Void-and-cluster blue noise. Slow to build, so create it once and reuse.
+    #[allow(dead_code)]
+    pub fn blue_noise(size: usize) -> Self {
+        let size = size.max(1);
+        let n = size * size;
+        let sigma = 1.5f32;
+
+        // Gaussian falloff by toroidal offset, so the tile repeats seamlessly
+        let mut falloff = vec![0.0f32; n];
+        for dy in 0..size {
+            for dx in 0..size {
+                let wx = dx.min(size - dx) as f32;
+                let wy = dy.min(size - dy) as f32;
+                falloff[dy * size + dx] = (-(wx * wx + wy * wy) / (2.0 * sigma * sigma)).exp();
+            }
+        }
+        let toggle = |energy: &mut [f32], index: usize, sign: f32| {
+            let (ix, iy) = (index % size, index / size);
+            for y in 0..size {
+                for x in 0..size {
+                    let dx = (x + size - ix) % size;
+                    let dy = (y + size - iy) % size;
+                    energy[y * size + x] += sign * falloff[dy * size + dx];
+                }
+            }
+        };
+        let tightest_cluster = |pattern: &[bool], energy: &[f32]| {
+            (0..n)
+                .filter(|&i| pattern[i])
+                .max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+                .unwrap()
+        };
+        let largest_void = |pattern: &[bool], energy: &[f32]| {
+            (0..n)
+                .filter(|&i| !pattern[i])
+                .min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+                .unwrap()
+        };
+
+        // Random starting pattern with about a tenth of the cells set
+        let mut seed = 0x2545f491u32;
+        let mut pattern = vec![false; n];
+        let mut energy = vec![0.0f32; n];
+        let initial = (n / 10).max(1);
+        let mut placed = 0;
+        while placed < initial {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let i = seed as usize % n;
+            if !pattern[i] {
+                pattern[i] = true;
+                toggle(&mut energy, i, 1.0);
+                placed += 1;
+            }
+        }
+
+        // Move points from clusters into voids until that stops changing anything
+        loop {
+            let cluster = tightest_cluster(&pattern, &energy);
+            pattern[cluster] = false;
+            toggle(&mut energy, cluster, -1.0);
+            let void = largest_void(&pattern, &energy);
+            pattern[void] = true;
+            toggle(&mut energy, void, 1.0);
+            if void == cluster {
+                break;
+            }
+        }
+
+        let mut ranks = vec![0u32; n];
+
+        // Ranks below the initial count come from peeling clusters off a copy
+        let mut peel = pattern.clone();
+        let mut peel_energy = energy.clone();
+        for rank in (0..initial).rev() {
+            let cluster = tightest_cluster(&peel, &peel_energy);
+            peel[cluster] = false;
+            toggle(&mut peel_energy, cluster, -1.0);
+            ranks[cluster] = rank as u32;
+        }
+
+        // The rest are ranked by filling the largest void each time
+        for rank in initial..n {
+            let void = largest_void(&pattern, &energy);
+            pattern[void] = true;
+            toggle(&mut energy, void, 1.0);
+            ranks[void] = rank as u32;
+        }
+
+        Self::from_ranks(size, &ranks)
+    }
+
+    fn from_ranks(size: usize, ranks: &[u32]) -> Self {
+        let count = ranks.len() as f32;
+        DitherMatrix {
+            size,
+            values: ranks.iter().map(|&r| (r as f32 + 0.5) / count).collect(),
+        }
+    }
+
+    // Threshold for a screen pixel, centered on zero (-0.5..0.5)
+    pub fn offset_at(&self, x: usize, y: usize) -> f32 {
+        self.values[(y % self.size) * self.size + x % self.size] - 0.5
+    }
+}
+
+// Snaps fragments to a fixed palette, optionally with ordered dithering
+pub struct OrderedDitherShader {
+    pub palette: Palette,
+    pub matrix: Option<DitherMatrix>,
+    // How far (in 0-255 color steps) the threshold can push a channel
+    pub spread: f32,
+}
+
+impl OrderedDitherShader {
+    #[allow(dead_code)]
+    pub fn new(palette: Palette, matrix: Option<DitherMatrix>, spread: f32) -> Self {
+        OrderedDitherShader {
+            palette,
+            matrix,
+            spread,
+        }
+    }
+
+    pub fn dither(&self, x: usize, y: usize, r: f32, g: f32, b: f32, a: u8) -> Color {
+        let offset = match &self.matrix {
+            Some(matrix) => matrix.offset_at(x, y) * self.spread,
+            None => 0.0,
+        };
+        self.palette.nearest(r + offset, g + offset, b + offset, a)
+    }
+}
+
+impl PixelShader for OrderedDitherShader {
+    fn process(&self, pp: &mut PixelPlacement, _triangle: &Triangle) {
+        if pp.color.a == 0 {
+            return;
+        }
+        pp.color = self.dither(
+            pp.x,
+            pp.y,
+            pp.color.r as f32,
+            pp.color.g as f32,
+            pp.color.b as f32,
+            pp.color.a,
+        );
+    }
+}
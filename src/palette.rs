@@ -0,0 +1,141 @@
+use std::io::{Error, ErrorKind};
+
+use crate::{color::Color, texture::Texture};
+
+#[derive(Clone, Debug)]
+pub struct Palette {
+    pub colors: Vec<Color>,
+}
+
+const fn rgb(hex: u32) -> Color {
+    Color::new((hex >> 16) as u8, (hex >> 8) as u8, hex as u8, 255)
+}
+
+const EGA: [Color; 16] = [
+    rgb(0x000000), rgb(0x0000aa), rgb(0x00aa00), rgb(0x00aaaa),
+    rgb(0xaa0000), rgb(0xaa00aa), rgb(0xaa5500), rgb(0xaaaaaa),
+    rgb(0x555555), rgb(0x5555ff), rgb(0x55ff55), rgb(0x55ffff),
+    rgb(0xff5555), rgb(0xff55ff), rgb(0xffff55), rgb(0xffffff),
+];
+
+// "Pepto" measurement of the VIC-II colors
+const C64: [Color; 16] = [
+    rgb(0x000000), rgb(0xffffff), rgb(0x68372b), rgb(0x70a4b2),
+    rgb(0x6f3d86), rgb(0x588d43), rgb(0x352879), rgb(0xb8c76f),
+    rgb(0x6f4f25), rgb(0x433900), rgb(0x9a6759), rgb(0x444444),
+    rgb(0x6c6c6c), rgb(0x9ad284), rgb(0x6c5eb5), rgb(0x959595),
+];
+
+const GAME_BOY: [Color; 4] = [
+    rgb(0x0f380f), rgb(0x306230), rgb(0x8bac0f), rgb(0x9bbc0f),
+];
+
+impl Palette {
+    pub fn new(colors: Vec<Color>) -> Self {
+        Palette { colors }
+    }
+
+    #[allow(dead_code)]
+    pub fn ega() -> Self {
+        Palette::new(EGA.to_vec())
+    }
+
+    #[allow(dead_code)]
+    pub fn c64() -> Self {
+        Palette::new(C64.to_vec())
+    }
+
+    #[allow(dead_code)]
+    pub fn game_boy() -> Self {
+        Palette::new(GAME_BOY.to_vec())
+    }
+
+    // Every distinct opaque color in the image, in reading order
+    #[allow(dead_code)]
+    pub fn from_png_file(path: &str) -> std::io::Result<Self> {
+        let swatch = Texture::from_png_file(path)?;
+        let mut colors: Vec<Color> = vec![];
+        for pixel in &swatch.pixels {
+            if pixel.a == 0 {
+                continue;
+            }
+            let opaque = Color::new(pixel.r, pixel.g, pixel.b, 255);
+            if !colors.contains(&opaque) {
+                colors.push(opaque);
+            }
+        }
+        if colors.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "swatch has no opaque pixels"));
+        }
+        Ok(Palette::new(colors))
+    }
+
+    #[allow(dead_code)]
+    pub fn from_gpl_file(path: &str) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_gpl_str(&text)
+    }
+
+    // GIMP palette: a "GIMP Palette" header, optional Name/Columns lines,
+    // then one "R G B [name]" entry per line
+    pub fn from_gpl_str(text: &str) -> std::io::Result<Self> {
+        let invalid = |line: usize, msg: &str| {
+            Error::new(ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+        };
+
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == "GIMP Palette" => {}
+            _ => return Err(invalid(1, "missing 'GIMP Palette' header")),
+        }
+
+        let mut colors = vec![];
+        for (i, line) in lines {
+            let line = line.trim();
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("Name:")
+                || line.starts_with("Columns:")
+            {
+                continue;
+            }
+            let channels: Vec<u8> = line
+                .split_whitespace()
+                .take(3)
+                .map(|s| s.parse::<u8>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid(i + 1, "bad color entry"))?;
+            if channels.len() != 3 {
+                return Err(invalid(i + 1, "expected R G B"));
+            }
+            colors.push(Color::new(channels[0], channels[1], channels[2], 255));
+        }
+
+        if colors.is_empty() {
+            return Err(invalid(1, "palette has no colors"));
+        }
+        Ok(Palette::new(colors))
+    }
+
+    // Closest entry by luma-weighted distance, keeping the input alpha
+    pub fn nearest(&self, r: f32, g: f32, b: f32, a: u8) -> Color {
+        let mut best = Color::new(0, 0, 0, a);
+        let mut best_dist = f32::INFINITY;
+        for c in &self.colors {
+            let dr = r - c.r as f32;
+            let dg = g - c.g as f32;
+            let db = b - c.b as f32;
+            let dist = 0.299 * dr * dr + 0.587 * dg * dg + 0.114 * db * db;
+            if dist < best_dist {
+                best_dist = dist;
+                best = Color::new(c.r, c.g, c.b, a);
+            }
+        }
+        best
+    }
+
+    #[allow(dead_code)]
+    pub fn quantize(&self, color: &Color) -> Color {
+        self.nearest(color.r as f32, color.g as f32, color.b as f32, color.a)
+    }
+}
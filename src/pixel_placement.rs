@@ -1,4 +1,4 @@
-use crate::color::Color;
+use crate::{color::Color, vec2::Vector2, vec3::Vector3};
 
 #[derive(Debug, Clone, Copy)]
 pub struct PixelPlacement {
@@ -6,4 +6,8 @@ pub struct PixelPlacement {
     pub y: usize,
     pub color: Color,
     pub depth: f32,
+    pub world_pos: Vector3,
+    pub normal: Vector3,
+    // Texture coordinate, perspective-correct
+    pub uv: Vector2,
 }
\ No newline at end of file
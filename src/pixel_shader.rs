@@ -1,4 +1,4 @@
-use crate::{color::Color, pixel_placement::PixelPlacement, triangle::Triangle};
+use crate::{color::Color, pixel_placement::PixelPlacement, texture::Texture, triangle::Triangle};
 
 pub trait PixelShader {
     fn process(&self, pp: &mut PixelPlacement, triangle: &Triangle);
@@ -74,38 +74,46 @@ impl PixelShader for TexturedRainbowShader {
     }
 }
 
-pub struct DepthFogShader {
-    fog_color: Color,
-    fog_start: f32,
-    fog_end: f32,
+// Multiplies the vertex color by the texture at the pixel's texture coordinate
+pub struct TextureShader<'a> {
+    pub texture: &'a Texture,
 }
 
-impl DepthFogShader {
-    pub fn new(fog_color: Color, fog_start: f32, fog_end: f32) -> Self {
-        DepthFogShader {
-            fog_color,
-            fog_start,
-            fog_end,
-        }
+impl<'a> TextureShader<'a> {
+    pub fn new(texture: &'a Texture) -> Self {
+        TextureShader { texture }
     }
 }
 
-impl PixelShader for DepthFogShader {
+impl PixelShader for TextureShader<'_> {
     fn process(&self, pp: &mut PixelPlacement, _triangle: &Triangle) {
-        if pp.color.a == 0 {
-            return;
-        }
-
-        // Calculate fog factor based on depth
-        let fog_factor = ((pp.depth - self.fog_start) / (self.fog_end - self.fog_start))
-            .clamp(0.0, 1.0);
-
-        // Linearly interpolate between pixel color and fog color
+        let texel = self.texture.sample(pp.uv.x, pp.uv.y);
+        let modulate = |a: u8, b: u8| (a as u16 * b as u16 / 255) as u8;
         pp.color = Color {
-            r: ((1.0 - fog_factor) * pp.color.r as f32 + fog_factor * self.fog_color.r as f32) as u8,
-            g: ((1.0 - fog_factor) * pp.color.g as f32 + fog_factor * self.fog_color.g as f32) as u8,
-            b: ((1.0 - fog_factor) * pp.color.b as f32 + fog_factor * self.fog_color.b as f32) as u8,
-            a: ((1.0 - fog_factor) * pp.color.a as f32 + fog_factor * self.fog_color.a as f32) as u8,
+            r: modulate(pp.color.r, texel.r),
+            g: modulate(pp.color.g, texel.g),
+            b: modulate(pp.color.b, texel.b),
+            a: modulate(pp.color.a, texel.a),
         };
     }
-}
\ No newline at end of file
+}
+
+// Like `TextureShader`, but texels with alpha below `cutoff` are discarded and
+// the rest drawn opaque, so cut-out sprites need no sorting
+pub struct AlphaTestShader<'a> {
+    pub texture: &'a Texture,
+    pub cutoff: u8,
+}
+
+impl<'a> AlphaTestShader<'a> {
+    pub fn new(texture: &'a Texture, cutoff: u8) -> Self {
+        AlphaTestShader { texture, cutoff }
+    }
+}
+
+impl PixelShader for AlphaTestShader<'_> {
+    fn process(&self, pp: &mut PixelPlacement, triangle: &Triangle) {
+        TextureShader::new(self.texture).process(pp, triangle);
+        pp.color.a = if pp.color.a < self.cutoff { 0 } else { 255 };
+    }
+}
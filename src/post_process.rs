@@ -0,0 +1,694 @@
+use std::any::Any;
+
+use crate::{
+    color::Color,
+    ordered_dither_shader::OrderedDitherShader,
+    palette::Palette,
+    screen::{Screen, SCREEN_HEIGHT, SCREEN_PIXEL_COUNT, SCREEN_WIDTH},
+    vec3::Vector3,
+};
+
+// Full-screen effects that run over the finished color and depth buffers,
+// after the game has drawn everything for the frame.
+pub trait PostProcessPass: Any {
+    fn process(&mut self, screen: &mut Screen);
+}
+
+struct PostProcessEntry {
+    name: String,
+    enabled: bool,
+    pass: Box<dyn PostProcessPass>,
+}
+
+#[derive(Default)]
+pub struct PostProcessChain {
+    entries: Vec<PostProcessEntry>,
+}
+
+impl PostProcessChain {
+    pub fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    pub fn push(&mut self, name: &str, pass: Box<dyn PostProcessPass>) {
+        self.entries.push(PostProcessEntry {
+            name: name.to_string(),
+            enabled: true,
+            pass,
+        });
+    }
+
+    #[allow(dead_code)]
+    pub fn insert(&mut self, index: usize, name: &str, pass: Box<dyn PostProcessPass>) {
+        let index = index.min(self.entries.len());
+        self.entries.insert(
+            index,
+            PostProcessEntry {
+                name: name.to_string(),
+                enabled: true,
+                pass,
+            },
+        );
+    }
+
+    #[allow(dead_code)]
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn PostProcessPass>> {
+        let index = self.entries.iter().position(|e| e.name == name)?;
+        Some(self.entries.remove(index).pass)
+    }
+
+    #[allow(dead_code)]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.name == name) {
+            entry.enabled = enabled;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn toggle(&mut self, name: &str) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.name == name) {
+            entry.enabled = !entry.enabled;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.entries.iter().any(|e| e.name == name && e.enabled)
+    }
+
+    // Gives access to a pass's settings so they can be tweaked while running
+    #[allow(dead_code)]
+    pub fn get_mut<T: PostProcessPass>(&mut self, name: &str) -> Option<&mut T> {
+        let entry = self.entries.iter_mut().find(|e| e.name == name)?;
+        (entry.pass.as_mut() as &mut dyn Any).downcast_mut::<T>()
+    }
+
+    #[allow(dead_code)]
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    pub fn process(&mut self, screen: &mut Screen) {
+        for entry in &mut self.entries {
+            if entry.enabled {
+                entry.pass.process(screen);
+            }
+        }
+    }
+}
+
+pub type Rgb = [f32; 3];
+
+pub fn to_rgb(color: &Color) -> Rgb {
+    [color.r as f32, color.g as f32, color.b as f32]
+}
+
+pub fn to_color(rgb: Rgb, a: u8) -> Color {
+    Color::new(
+        rgb[0].clamp(0.0, 255.0) as u8,
+        rgb[1].clamp(0.0, 255.0) as u8,
+        rgb[2].clamp(0.0, 255.0) as u8,
+        a,
+    )
+}
+
+pub fn read_rgb(screen: &Screen) -> Vec<Rgb> {
+    screen.pixels.iter().map(to_rgb).collect()
+}
+
+pub fn write_rgb(screen: &mut Screen, rgb: &[Rgb]) {
+    for (pixel, value) in screen.pixels.iter_mut().zip(rgb) {
+        *pixel = to_color(*value, pixel.a);
+    }
+}
+
+pub fn luminance(rgb: &Rgb) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+fn gaussian_kernel(radius: usize, sigma: f32) -> Vec<f32> {
+    let sigma = sigma.max(0.0001);
+    let mut kernel: Vec<f32> = (0..=radius * 2)
+        .map(|i| {
+            let d = i as f32 - radius as f32;
+            (-(d * d) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    for k in &mut kernel {
+        *k /= sum;
+    }
+    kernel
+}
+
+// Separable blur, clamping samples at the screen edges
+pub fn gaussian_blur(src: &[Rgb], radius: usize, sigma: f32) -> Vec<Rgb> {
+    if radius == 0 {
+        return src.to_vec();
+    }
+    let kernel = gaussian_kernel(radius, sigma);
+    let r = radius as isize;
+
+    let mut horizontal = vec![[0.0; 3]; SCREEN_PIXEL_COUNT];
+    for y in 0..SCREEN_HEIGHT {
+        for x in 0..SCREEN_WIDTH {
+            let mut acc = [0.0; 3];
+            for (i, k) in kernel.iter().enumerate() {
+                let sx = (x as isize + i as isize - r).clamp(0, SCREEN_WIDTH as isize - 1) as usize;
+                let s = &src[y * SCREEN_WIDTH + sx];
+                acc[0] += s[0] * k;
+                acc[1] += s[1] * k;
+                acc[2] += s[2] * k;
+            }
+            horizontal[y * SCREEN_WIDTH + x] = acc;
+        }
+    }
+
+    let mut out = vec![[0.0; 3]; SCREEN_PIXEL_COUNT];
+    for y in 0..SCREEN_HEIGHT {
+        for x in 0..SCREEN_WIDTH {
+            let mut acc = [0.0; 3];
+            for (i, k) in kernel.iter().enumerate() {
+                let sy = (y as isize + i as isize - r).clamp(0, SCREEN_HEIGHT as isize - 1) as usize;
+                let s = &horizontal[sy * SCREEN_WIDTH + x];
+                acc[0] += s[0] * k;
+                acc[1] += s[1] * k;
+                acc[2] += s[2] * k;
+            }
+            out[y * SCREEN_WIDTH + x] = acc;
+        }
+    }
+    out
+}
+
+pub struct GaussianBlurPass {
+    pub radius: usize,
+    pub sigma: f32,
+}
+
+impl GaussianBlurPass {
+    #[allow(dead_code)]
+    pub fn new(radius: usize, sigma: f32) -> Self {
+        GaussianBlurPass { radius, sigma }
+    }
+}
+
+impl PostProcessPass for GaussianBlurPass {
+    fn process(&mut self, screen: &mut Screen) {
+        let blurred = gaussian_blur(&read_rgb(screen), self.radius, self.sigma);
+        write_rgb(screen, &blurred);
+    }
+}
+
+pub struct BloomPass {
+    // Luminance (0-255) above which pixels start to glow
+    pub threshold: f32,
+    pub intensity: f32,
+    pub radius: usize,
+    pub sigma: f32,
+}
+
+impl BloomPass {
+    pub fn new(threshold: f32, intensity: f32) -> Self {
+        BloomPass {
+            threshold,
+            intensity,
+            radius: 8,
+            sigma: 4.0,
+        }
+    }
+}
+
+impl PostProcessPass for BloomPass {
+    fn process(&mut self, screen: &mut Screen) {
+        let src = read_rgb(screen);
+
+        // Keep only the part of each pixel that is brighter than the threshold
+        let bright: Vec<Rgb> = src
+            .iter()
+            .map(|rgb| {
+                let lum = luminance(rgb);
+                if lum <= self.threshold || lum <= 0.0 {
+                    [0.0; 3]
+                } else {
+                    let scale = (lum - self.threshold) / lum;
+                    [rgb[0] * scale, rgb[1] * scale, rgb[2] * scale]
+                }
+            })
+            .collect();
+
+        let glow = gaussian_blur(&bright, self.radius, self.sigma);
+
+        let combined: Vec<Rgb> = src
+            .iter()
+            .zip(&glow)
+            .map(|(s, g)| {
+                [
+                    s[0] + g[0] * self.intensity,
+                    s[1] + g[1] * self.intensity,
+                    s[2] + g[2] * self.intensity,
+                ]
+            })
+            .collect();
+        write_rgb(screen, &combined);
+    }
+}
+
+pub struct VignettePass {
+    pub color: Color,
+    // How dark the corners get, 0 = no effect, 1 = fully `color`
+    pub strength: f32,
+    // Normalized distance from the center where darkening begins
+    pub radius: f32,
+    pub softness: f32,
+}
+
+impl VignettePass {
+    pub fn new(strength: f32) -> Self {
+        VignettePass {
+            color: Color::new(0, 0, 0, 255),
+            strength,
+            radius: 0.5,
+            softness: 0.5,
+        }
+    }
+}
+
+impl PostProcessPass for VignettePass {
+    fn process(&mut self, screen: &mut Screen) {
+        let tint = to_rgb(&self.color);
+        let half_w = SCREEN_WIDTH as f32 / 2.0;
+        let half_h = SCREEN_HEIGHT as f32 / 2.0;
+        // Normalize so the corners sit at distance 1
+        let max_dist = (half_w * half_w + half_h * half_h).sqrt();
+
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                let dx = x as f32 + 0.5 - half_w;
+                let dy = y as f32 + 0.5 - half_h;
+                let dist = (dx * dx + dy * dy).sqrt() / max_dist;
+                let t = ((dist - self.radius) / self.softness.max(0.0001)).clamp(0.0, 1.0);
+                let amount = t * t * (3.0 - 2.0 * t) * self.strength;
+
+                let pixel = &mut screen.pixels[y * SCREEN_WIDTH + x];
+                let rgb = to_rgb(pixel);
+                *pixel = to_color(
+                    [
+                        rgb[0] + (tint[0] - rgb[0]) * amount,
+                        rgb[1] + (tint[1] - rgb[1]) * amount,
+                        rgb[2] + (tint[2] - rgb[2]) * amount,
+                    ],
+                    pixel.a,
+                );
+            }
+        }
+    }
+}
+
+// A size x size x size lookup table mapping input colors to graded colors.
+// Entries are stored red-fastest, values are in 0..1.
+pub struct ColorLut {
+    size: usize,
+    data: Vec<Rgb>,
+}
+
+impl ColorLut {
+    #[allow(dead_code)]
+    pub fn identity(size: usize) -> Self {
+        Self::from_fn(size, |r, g, b| [r, g, b])
+    }
+
+    pub fn from_fn(size: usize, f: impl Fn(f32, f32, f32) -> Rgb) -> Self {
+        let size = size.max(2);
+        let step = 1.0 / (size - 1) as f32;
+        let mut data = Vec::with_capacity(size * size * size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.push(f(r as f32 * step, g as f32 * step, b as f32 * step));
+                }
+            }
+        }
+        ColorLut { size, data }
+    }
+
+    // Loads an Adobe/Resolve style .cube file
+    #[allow(dead_code)]
+    pub fn from_cube_file(path: &str) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_cube_str(&text)
+    }
+
+    pub fn from_cube_str(text: &str) -> std::io::Result<Self> {
+        let invalid = |line: usize, msg: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("line {}: {}", line, msg),
+            )
+        };
+
+        let mut size = 0;
+        let mut data = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let first = parts.next().unwrap_or("");
+            if first == "LUT_3D_SIZE" {
+                size = parts
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| invalid(i + 1, "bad LUT_3D_SIZE"))?;
+            } else if first.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                // TITLE, DOMAIN_MIN, DOMAIN_MAX and friends are not needed
+                continue;
+            } else {
+                let values: Vec<f32> = line
+                    .split_whitespace()
+                    .map(|s| s.parse::<f32>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid(i + 1, "bad table entry"))?;
+                if values.len() != 3 {
+                    return Err(invalid(i + 1, "expected three values"));
+                }
+                data.push([values[0], values[1], values[2]]);
+            }
+        }
+
+        if size < 2 || data.len() != size * size * size {
+            return Err(invalid(0, "table size does not match LUT_3D_SIZE"));
+        }
+        Ok(ColorLut { size, data })
+    }
+
+    fn entry(&self, r: usize, g: usize, b: usize) -> Rgb {
+        self.data[(b * self.size + g) * self.size + r]
+    }
+
+    // Trilinear lookup, input and output in 0..1
+    pub fn sample(&self, rgb: Rgb) -> Rgb {
+        let max = (self.size - 1) as f32;
+        let scaled = [
+            rgb[0].clamp(0.0, 1.0) * max,
+            rgb[1].clamp(0.0, 1.0) * max,
+            rgb[2].clamp(0.0, 1.0) * max,
+        ];
+        let i0 = scaled.map(|v| (v.floor() as usize).min(self.size - 2));
+        let f = [
+            scaled[0] - i0[0] as f32,
+            scaled[1] - i0[1] as f32,
+            scaled[2] - i0[2] as f32,
+        ];
+
+        let mut out = [0.0; 3];
+        for corner in 0..8 {
+            let dr = corner & 1;
+            let dg = (corner >> 1) & 1;
+            let db = (corner >> 2) & 1;
+            let weight = (if dr == 1 { f[0] } else { 1.0 - f[0] })
+                * (if dg == 1 { f[1] } else { 1.0 - f[1] })
+                * (if db == 1 { f[2] } else { 1.0 - f[2] });
+            let e = self.entry(i0[0] + dr, i0[1] + dg, i0[2] + db);
+            out[0] += e[0] * weight;
+            out[1] += e[1] * weight;
+            out[2] += e[2] * weight;
+        }
+        out
+    }
+}
+
+pub struct ColorGradingPass {
+    pub lut: ColorLut,
+    // Blend between the original (0) and fully graded (1) image
+    pub strength: f32,
+}
+
+impl ColorGradingPass {
+    #[allow(dead_code)]
+    pub fn new(lut: ColorLut) -> Self {
+        ColorGradingPass { lut, strength: 1.0 }
+    }
+}
+
+impl PostProcessPass for ColorGradingPass {
+    fn process(&mut self, screen: &mut Screen) {
+        for pixel in screen.pixels.iter_mut() {
+            let rgb = to_rgb(pixel);
+            let graded = self.lut.sample([rgb[0] / 255.0, rgb[1] / 255.0, rgb[2] / 255.0]);
+            *pixel = to_color(
+                [
+                    rgb[0] + (graded[0] * 255.0 - rgb[0]) * self.strength,
+                    rgb[1] + (graded[1] * 255.0 - rgb[1]) * self.strength,
+                    rgb[2] + (graded[2] * 255.0 - rgb[2]) * self.strength,
+                ],
+                pixel.a,
+            );
+        }
+    }
+}
+
+pub struct ChromaticAberrationPass {
+    // Channel offset in pixels at the screen corners
+    pub strength: f32,
+}
+
+impl ChromaticAberrationPass {
+    #[allow(dead_code)]
+    pub fn new(strength: f32) -> Self {
+        ChromaticAberrationPass { strength }
+    }
+}
+
+impl PostProcessPass for ChromaticAberrationPass {
+    fn process(&mut self, screen: &mut Screen) {
+        let src = screen.pixels.to_vec();
+        let half_w = SCREEN_WIDTH as f32 / 2.0;
+        let half_h = SCREEN_HEIGHT as f32 / 2.0;
+
+        let fetch = |x: f32, y: f32| -> &Color {
+            let sx = (x as isize).clamp(0, SCREEN_WIDTH as isize - 1) as usize;
+            let sy = (y as isize).clamp(0, SCREEN_HEIGHT as isize - 1) as usize;
+            &src[sy * SCREEN_WIDTH + sx]
+        };
+
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                // Red is pushed outwards and blue inwards, growing towards the edges
+                let dx = (x as f32 - half_w) / half_w;
+                let dy = (y as f32 - half_h) / half_h;
+                let ox = dx * self.strength;
+                let oy = dy * self.strength;
+
+                let pixel = &mut screen.pixels[y * SCREEN_WIDTH + x];
+                pixel.r = fetch(x as f32 - ox, y as f32 - oy).r;
+                pixel.b = fetch(x as f32 + ox, y as f32 + oy).b;
+            }
+        }
+    }
+}
+
+pub struct SharpenPass {
+    pub amount: f32,
+}
+
+impl SharpenPass {
+    #[allow(dead_code)]
+    pub fn new(amount: f32) -> Self {
+        SharpenPass { amount }
+    }
+}
+
+impl PostProcessPass for SharpenPass {
+    fn process(&mut self, screen: &mut Screen) {
+        let src = read_rgb(screen);
+        let at = |x: isize, y: isize| -> &Rgb {
+            let sx = x.clamp(0, SCREEN_WIDTH as isize - 1) as usize;
+            let sy = y.clamp(0, SCREEN_HEIGHT as isize - 1) as usize;
+            &src[sy * SCREEN_WIDTH + sx]
+        };
+
+        let mut out = vec![[0.0; 3]; SCREEN_PIXEL_COUNT];
+        for y in 0..SCREEN_HEIGHT as isize {
+            for x in 0..SCREEN_WIDTH as isize {
+                // Unsharp mask against the 4-neighbour average
+                let c = at(x, y);
+                let n = [at(x - 1, y), at(x + 1, y), at(x, y - 1), at(x, y + 1)];
+                let mut v = [0.0; 3];
+                for ch in 0..3 {
+                    let avg = (n[0][ch] + n[1][ch] + n[2][ch] + n[3][ch]) / 4.0;
+                    v[ch] = c[ch] + (c[ch] - avg) * self.amount;
+                }
+                out[y as usize * SCREEN_WIDTH + x as usize] = v;
+            }
+        }
+        write_rgb(screen, &out);
+    }
+}
+
+// Ink lines where depth or surface direction changes sharply between neighbours
+pub struct OutlinePass {
+    pub color: Color,
+    // Relative depth change between neighbours that counts as an edge
+    pub depth_threshold: f32,
+    // Normal angle change (1 - cos) that counts as an edge, None to ignore normals
+    pub normal_threshold: Option<f32>,
+    // Line width in pixels
+    pub width: usize,
+}
+
+impl OutlinePass {
+    #[allow(dead_code)]
+    pub fn new(color: Color, depth_threshold: f32) -> Self {
+        OutlinePass {
+            color,
+            depth_threshold,
+            normal_threshold: None,
+            width: 1,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_normals(mut self, normal_threshold: f32) -> Self {
+        self.normal_threshold = Some(normal_threshold);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width.max(1);
+        self
+    }
+
+    fn is_edge(&self, screen: &Screen, a: usize, b: usize) -> bool {
+        let da = screen.linear_depth(a);
+        let db = screen.linear_depth(b);
+        match (da.is_finite(), db.is_finite()) {
+            (true, true) => {}
+            (false, false) => return false,
+            // Geometry against empty background
+            _ => return true,
+        }
+        if (da - db).abs() / da.min(db).max(0.0001) > self.depth_threshold {
+            return true;
+        }
+        match self.normal_threshold {
+            Some(threshold) => {
+                let na = &screen.normal_buffer[a];
+                let nb = &screen.normal_buffer[b];
+                1.0 - Vector3::dot(na, nb) > threshold
+            }
+            None => false,
+        }
+    }
+}
+
+impl PostProcessPass for OutlinePass {
+    fn process(&mut self, screen: &mut Screen) {
+        let mut edges = vec![false; SCREEN_PIXEL_COUNT];
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                let i = y * SCREEN_WIDTH + x;
+                edges[i] = (x + 1 < SCREEN_WIDTH && self.is_edge(screen, i, i + 1))
+                    || (y + 1 < SCREEN_HEIGHT && self.is_edge(screen, i, i + SCREEN_WIDTH));
+            }
+        }
+
+        // Thicken the one pixel edges into a round brush of the requested width
+        let reach = (self.width as isize - 1) / 2;
+        let extra = (self.width as isize - 1) - reach;
+        for y in 0..SCREEN_HEIGHT as isize {
+            for x in 0..SCREEN_WIDTH as isize {
+                if !edges[y as usize * SCREEN_WIDTH + x as usize] {
+                    continue;
+                }
+                for oy in -reach..=extra {
+                    for ox in -reach..=extra {
+                        let (px, py) = (x + ox, y + oy);
+                        if px < 0 || py < 0 || px >= SCREEN_WIDTH as isize || py >= SCREEN_HEIGHT as isize {
+                            continue;
+                        }
+                        if ox * ox + oy * oy > reach.max(extra) * reach.max(extra) + 1 {
+                            continue;
+                        }
+                        screen.pixels[py as usize * SCREEN_WIDTH + px as usize] = self.color;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The fragment shader version runs before fog, this one quantizes the final image
+impl PostProcessPass for OrderedDitherShader {
+    fn process(&mut self, screen: &mut Screen) {
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                let pixel = &mut screen.pixels[y * SCREEN_WIDTH + x];
+                *pixel = self.dither(x, y, pixel.r as f32, pixel.g as f32, pixel.b as f32, pixel.a);
+            }
+        }
+    }
+}
+
+pub struct FloydSteinbergPass {
+    pub palette: Palette,
+    // Alternate scan direction per row to avoid diagonal "worm" artifacts
+    pub serpentine: bool,
+}
+
+impl FloydSteinbergPass {
+    #[allow(dead_code)]
+    pub fn new(palette: Palette) -> Self {
+        FloydSteinbergPass {
+            palette,
+            serpentine: true,
+        }
+    }
+}
+
+impl PostProcessPass for FloydSteinbergPass {
+    fn process(&mut self, screen: &mut Screen) {
+        let mut work = read_rgb(screen);
+
+        for y in 0..SCREEN_HEIGHT {
+            let reverse = self.serpentine && y % 2 == 1;
+            let dir: isize = if reverse { -1 } else { 1 };
+
+            for i in 0..SCREEN_WIDTH {
+                let x = if reverse { SCREEN_WIDTH - 1 - i } else { i };
+                let index = y * SCREEN_WIDTH + x;
+                let old = work[index];
+                let a = screen.pixels[index].a;
+                let new = self.palette.nearest(old[0], old[1], old[2], a);
+                screen.pixels[index] = new;
+
+                let err = [
+                    old[0] - new.r as f32,
+                    old[1] - new.g as f32,
+                    old[2] - new.b as f32,
+                ];
+                let mut spread = |dx: isize, dy: usize, weight: f32| {
+                    let nx = x as isize + dx * dir;
+                    let ny = y + dy;
+                    if nx < 0 || nx >= SCREEN_WIDTH as isize || ny >= SCREEN_HEIGHT {
+                        return;
+                    }
+                    let target = &mut work[ny * SCREEN_WIDTH + nx as usize];
+                    target[0] += err[0] * weight;
+                    target[1] += err[1] * weight;
+                    target[2] += err[2] * weight;
+                };
+                spread(1, 0, 7.0 / 16.0);
+                spread(-1, 1, 3.0 / 16.0);
+                spread(0, 1, 5.0 / 16.0);
+                spread(1, 1, 1.0 / 16.0);
+            }
+        }
+    }
+}
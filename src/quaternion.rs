@@ -0,0 +1,135 @@
+use std::ops::Mul;
+
+use crate::vec3::Vector3;
+
+// Unit quaternion rotation, stored like glTF does: x, y, z then w
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quaternion {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Quaternion { x, y, z, w }
+    }
+
+    pub fn identity() -> Self {
+        Quaternion::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    // Angle in radians, counter-clockwise looking down the axis
+    #[allow(dead_code)]
+    pub fn from_axis_angle(axis: &Vector3, angle: f32) -> Self {
+        let axis = axis.normalize_v();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quaternion::new(axis.x * sin, axis.y * sin, axis.z * sin, cos)
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn normalize(&self) -> Self {
+        let len = self.dot(self).sqrt();
+        if len == 0.0 {
+            return Quaternion::identity();
+        }
+        Quaternion::new(self.x / len, self.y / len, self.z / len, self.w / len)
+    }
+
+    #[allow(dead_code)]
+    pub fn conjugate(&self) -> Self {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    #[allow(dead_code)]
+    pub fn rotate(&self, v: &Vector3) -> Vector3 {
+        // v + 2w(q x v) + 2q x (q x v)
+        let q = Vector3::new(self.x, self.y, self.z);
+        let t = q.cross(v) * 2.0;
+        *v + t * self.w + q.cross(&t)
+    }
+
+    // Takes the short way round, falls back to a normalized lerp when the
+    // two are almost the same
+    pub fn slerp(&self, other: &Quaternion, t: f32) -> Self {
+        let mut other = *other;
+        let mut cos = self.dot(&other);
+        if cos < 0.0 {
+            other = Quaternion::new(-other.x, -other.y, -other.z, -other.w);
+            cos = -cos;
+        }
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quaternion::new(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+        .normalize()
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    // Applies `rhs` first, then `self`
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn close(a: &Vector3, b: &Vector3) -> bool {
+        (*a - *b).length() < 1e-5
+    }
+
+    #[test]
+    fn rotates_counter_clockwise_about_the_axis() {
+        let q = Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 2.0), FRAC_PI_2);
+        assert!(close(&q.rotate(&Vector3::new(1.0, 0.0, 0.0)), &Vector3::new(0.0, 1.0, 0.0)));
+        assert!(close(&q.conjugate().rotate(&Vector3::new(0.0, 1.0, 0.0)), &Vector3::new(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn products_apply_the_right_hand_side_first() {
+        let about_z = Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), FRAC_PI_2);
+        let about_x = Quaternion::from_axis_angle(&Vector3::new(1.0, 0.0, 0.0), FRAC_PI_2);
+        let v = Vector3::new(1.0, 0.0, 0.0);
+        // x turns to y under Z, then y to z under X
+        assert!(close(&(about_x * about_z).rotate(&v), &Vector3::new(0.0, 0.0, 1.0)));
+        assert!(close(&(about_x * about_z).rotate(&v), &about_x.rotate(&about_z.rotate(&v))));
+    }
+
+    #[test]
+    fn slerp_takes_the_short_way() {
+        let a = Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), 0.2);
+        let b = Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), 0.6);
+        // The same rotation as b, from the other side of the sphere
+        let negated = Quaternion::new(-b.x, -b.y, -b.z, -b.w);
+        let expected = Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), 0.4);
+        for q in [a.slerp(&b, 0.5), a.slerp(&negated, 0.5)] {
+            assert!(q.dot(&expected).abs() > 1.0 - 1e-5);
+        }
+        assert_eq!(a.slerp(&a, 0.5), a.normalize());
+        assert_eq!(Quaternion::new(0.0, 0.0, 0.0, 0.0).normalize(), Quaternion::identity());
+    }
+}
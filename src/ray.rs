@@ -0,0 +1,130 @@
+use crate::{
+    bounds::{Aabb, BoundingSphere},
+    frustum::Plane,
+    triangle::Triangle,
+    vec3::Vector3,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vector3,
+    // Unit length, so hit distances are in world units
+    pub dir: Vector3,
+}
+
+// Where a ray crossed a triangle. `barycentrics` weight v1, v2 and v3.
+#[derive(Clone, Copy, Debug)]
+pub struct TriangleHit {
+    pub distance: f32,
+    pub barycentrics: (f32, f32, f32),
+}
+
+const EPSILON: f32 = 1e-6;
+
+impl Ray {
+    pub fn new(origin: Vector3, dir: Vector3) -> Self {
+        Ray {
+            origin,
+            dir: dir.normalize_v(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn at(&self, distance: f32) -> Vector3 {
+        self.origin + self.dir * distance
+    }
+
+    // Möller–Trumbore, hits from either side of the triangle
+    #[allow(dead_code)]
+    pub fn intersect_triangle(&self, tri: &Triangle) -> Option<TriangleHit> {
+        self.intersect_corners(&tri.v1.pos, &tri.v2.pos, &tri.v3.pos)
+    }
+
+    // Same as `intersect_triangle`, for triangles that only exist as indices
+    // into a vertex list
+    pub fn intersect_corners(&self, a: &Vector3, b: &Vector3, c: &Vector3) -> Option<TriangleHit> {
+        let edge1 = *b - *a;
+        let edge2 = *c - *a;
+        let p = self.dir.cross(&edge2);
+        let det = Vector3::dot(&edge1, &p);
+        if det.abs() < EPSILON {
+            // Parallel to the triangle
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let s = self.origin - *a;
+        let u = Vector3::dot(&s, &p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&edge1);
+        let v = Vector3::dot(&self.dir, &q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = Vector3::dot(&edge2, &q) * inv_det;
+        if distance < EPSILON {
+            return None;
+        }
+        Some(TriangleHit {
+            distance,
+            barycentrics: (1.0 - u - v, u, v),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denom = Vector3::dot(&plane.normal, &self.dir);
+        if denom.abs() < EPSILON {
+            return None;
+        }
+        let distance = -plane.distance(&self.origin) / denom;
+        if distance >= 0.0 { Some(distance) } else { None }
+    }
+
+    // Slab test. Starting inside the box counts as a hit at distance 0.
+    #[allow(dead_code)]
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        let axes = [
+            (self.origin.x, self.dir.x, aabb.min.x, aabb.max.x),
+            (self.origin.y, self.dir.y, aabb.min.y, aabb.max.y),
+            (self.origin.z, self.dir.z, aabb.min.z, aabb.max.z),
+        ];
+        for (origin, dir, min, max) in axes {
+            if dir.abs() < EPSILON {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (min - origin) / dir;
+            let t2 = (max - origin) / dir;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        let to_center = sphere.center - self.origin;
+        let along = Vector3::dot(&to_center, &self.dir);
+        let dist_sq = Vector3::dot(&to_center, &to_center) - along * along;
+        let r_sq = sphere.radius * sphere.radius;
+        if dist_sq > r_sq {
+            return None;
+        }
+        let half_chord = (r_sq - dist_sq).sqrt();
+        if along + half_chord < 0.0 {
+            // Entirely behind the origin
+            return None;
+        }
+        Some((along - half_chord).max(0.0))
+    }
+}
@@ -1,5 +1,6 @@
 use crate::vec2::Vector2;
 
+#[derive(Clone, Copy)]
 pub struct Rect {
     pub pos: Vector2,
     pub size: Vector2,
@@ -42,4 +43,37 @@ impl Rect {
             ),
         }
     }
+
+    pub fn contains(&self, p: &Vector2) -> bool {
+        p.x >= self.pos.x
+            && p.y >= self.pos.y
+            && p.x < self.pos.x + self.size.x
+            && p.y < self.pos.y + self.size.y
+    }
+
+    // `count` equal rects side by side, left to right
+    pub fn split_horizontal(&self, count: usize) -> Vec<Rect> {
+        self.grid(count, 1)
+    }
+
+    // Row by row, starting top left
+    pub fn grid(&self, columns: usize, rows: usize) -> Vec<Rect> {
+        let cell = Vector2::new(
+            self.size.x / columns.max(1) as f32,
+            self.size.y / rows.max(1) as f32,
+        );
+        let mut cells = vec![];
+        for row in 0..rows {
+            for column in 0..columns {
+                cells.push(Rect::new(
+                    Vector2::new(
+                        self.pos.x + cell.x * column as f32,
+                        self.pos.y + cell.y * row as f32,
+                    ),
+                    cell,
+                ));
+            }
+        }
+        cells
+    }
 }
@@ -0,0 +1,73 @@
+use crate::{matrix4::Matrix4, quaternion::Quaternion, vec3::Vector3};
+
+// Node of a transform hierarchy. Its transform is relative to the parent.
+#[derive(Clone, Debug)]
+pub struct SceneNode {
+    #[allow(dead_code)]
+    pub name: String,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub scale: Vector3,
+    // Used instead of translation, rotation and scale when set. Nodes like
+    // this can't be animated.
+    pub matrix: Option<Matrix4>,
+    // Indexes into the owning scene's meshes and skins
+    pub mesh: Option<usize>,
+    pub skin: Option<usize>,
+}
+
+impl SceneNode {
+    pub fn new(name: &str) -> Self {
+        SceneNode {
+            name: name.to_string(),
+            parent: None,
+            children: vec![],
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Quaternion::identity(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+            matrix: None,
+            mesh: None,
+            skin: None,
+        }
+    }
+
+    pub fn local_matrix(&self) -> Matrix4 {
+        match &self.matrix {
+            Some(matrix) => *matrix,
+            None => Matrix4::from_trs(&self.translation, &self.rotation, &self.scale),
+        }
+    }
+}
+
+// Joints that deform a mesh, each with the inverse of its bind pose
+#[derive(Clone, Debug)]
+pub struct Skin {
+    #[allow(dead_code)]
+    pub name: String,
+    // Node indexes, what vertex joint indexes refer to
+    pub joints: Vec<usize>,
+    pub inverse_bind_matrices: Vec<Matrix4>,
+    // Common root of the joints, if the file names one
+    #[allow(dead_code)]
+    pub skeleton: Option<usize>,
+}
+
+impl Skin {
+    // Pose of each joint relative to its bind pose, given every node's world matrix
+    pub fn joint_matrices(&self, world: &[Matrix4]) -> Vec<Matrix4> {
+        self.joints
+            .iter()
+            .enumerate()
+            .map(|(i, &joint)| {
+                let inverse_bind = self
+                    .inverse_bind_matrices
+                    .get(i)
+                    .copied()
+                    .unwrap_or_else(Matrix4::identity);
+                world[joint] * inverse_bind
+            })
+            .collect()
+    }
+}
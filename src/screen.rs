@@ -1,5 +1,5 @@
 use crate::{
-    camera::Camera, color::Color, pixel_placement::PixelPlacement, pixel_shader::{DepthFogShader, PixelShader}, texture::Texture, triangle::Triangle
+    camera::Camera, color::Color, depth_encoding::DepthEncoding, fog::Fog, pixel_placement::PixelPlacement, pixel_shader::PixelShader, post_process::PostProcessChain, rect::Rect, texture::Texture, triangle::Triangle, vec2::Vector2, vec3::Vector3
 };
 
 pub const SCREEN_WIDTH: usize = 640;
@@ -8,8 +8,34 @@ pub const SCREEN_PIXEL_COUNT: usize = SCREEN_WIDTH * SCREEN_HEIGHT;
 
 pub struct Screen {
     pub pixels: Box<[Color]>,
+    // View depth of each pixel, stored as `depth_encoding` says
     pub depth_buffer: Box<[f32]>,
-    pub fog_shader: DepthFogShader,
+    pub depth_encoding: DepthEncoding,
+    // Near and far plane of the camera being drawn with, for the encoding
+    pub depth_range: (f32, f32),
+    // World-space surface normal of each visible pixel, zero where nothing was drawn
+    pub normal_buffer: Box<[Vector3]>,
+    pub fog: Fog,
+    pub clear_color: Color,
+    pub post_process: PostProcessChain,
+    // Reset by `clear`, so it covers one frame
+    pub cull_stats: CullStats,
+    // Per-pixel object ids for picking, only written while `render_ids` is set
+    pub id_buffer: Box<[u32]>,
+    pub render_ids: bool,
+    // Id given to pixels drawn from now on, 0 means no object
+    pub object_id: u32,
+    // Part of the screen that projection and rasterization target, whole
+    // pixels inside the screen, see `set_viewport`
+    viewport: Rect,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CullStats {
+    pub objects_submitted: usize,
+    pub objects_culled: usize,
+    pub triangles_submitted: usize,
+    pub triangles_drawn: usize,
 }
 
 impl Screen {
@@ -17,13 +43,93 @@ impl Screen {
         Screen {
             pixels: vec![Color::new(0, 0, 0, 255); SCREEN_PIXEL_COUNT].into_boxed_slice(),
             depth_buffer: vec![f32::INFINITY; SCREEN_PIXEL_COUNT].into_boxed_slice(),
-            fog_shader: DepthFogShader::new(Color::new(255, 255, 255, 80), 12.0, 17.0),
+            depth_encoding: DepthEncoding::Linear,
+            depth_range: (0.01, 60.0),
+            normal_buffer: vec![Vector3::new(0.0, 0.0, 0.0); SCREEN_PIXEL_COUNT].into_boxed_slice(),
+            fog: Fog::off(),
+            clear_color: Color::new(0, 0, 0, 255),
+            post_process: PostProcessChain::new(),
+            cull_stats: CullStats::default(),
+            id_buffer: vec![0; SCREEN_PIXEL_COUNT].into_boxed_slice(),
+            render_ids: false,
+            object_id: 0,
+            viewport: Self::full_rect(),
         }
     }
 
+    // The whole screen, the default viewport
+    pub fn full_rect() -> Rect {
+        Rect::new(Vector2::new(0.0, 0.0), Vector2::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32))
+    }
+
+    // Size of the current viewport, which is what cameras project into
+    pub fn size(&self) -> (usize, usize) {
+        let (x0, y0, x1, y1) = self.viewport_bounds();
+        (x1 - x0, y1 - y0)
+    }
+
+    // Snapped to whole pixels and kept on the screen. An empty rect is
+    // allowed, nothing gets drawn until the viewport changes again.
+    pub fn set_viewport(&mut self, rect: Rect) {
+        let x0 = rect.pos.x.round().clamp(0.0, SCREEN_WIDTH as f32);
+        let y0 = rect.pos.y.round().clamp(0.0, SCREEN_HEIGHT as f32);
+        let x1 = (rect.pos.x + rect.size.x).round().clamp(x0, SCREEN_WIDTH as f32);
+        let y1 = (rect.pos.y + rect.size.y).round().clamp(y0, SCREEN_HEIGHT as f32);
+        self.viewport = Rect::new(Vector2::new(x0, y0), Vector2::new(x1 - x0, y1 - y0));
+    }
+
+    pub fn reset_viewport(&mut self) {
+        self.viewport = Self::full_rect();
+    }
+
+    pub fn viewport(&self) -> Rect {
+        self.viewport
+    }
+
+    // Pixel range of the viewport as (x0, y0, x1, y1), ends exclusive
+    pub fn viewport_bounds(&self) -> (usize, usize, usize, usize) {
+        let v = &self.viewport;
+        (
+            v.pos.x as usize,
+            v.pos.y as usize,
+            (v.pos.x + v.size.x) as usize,
+            (v.pos.y + v.size.y) as usize,
+        )
+    }
+
+    // Id of the object visible at a pixel, needs `render_ids` on while drawing
+    #[allow(dead_code)]
+    pub fn object_id_at(&self, x: usize, y: usize) -> u32 {
+        if x >= SCREEN_WIDTH || y >= SCREEN_HEIGHT {
+            return 0;
+        }
+        self.id_buffer[y * SCREEN_WIDTH + x]
+    }
+
     pub fn clear(&mut self, clear_color: &Color) {
+        self.clear_color = *clear_color;
         self.pixels.fill(*clear_color);
-        self.depth_buffer.fill(f32::INFINITY);
+        self.depth_buffer.fill(self.depth_encoding.clear_value());
+        self.normal_buffer.fill(Vector3::new(0.0, 0.0, 0.0));
+        self.cull_stats = CullStats::default();
+        if self.render_ids {
+            self.id_buffer.fill(0);
+        }
+    }
+
+    // Like `clear`, but only inside the viewport, stats are left alone
+    pub fn clear_viewport(&mut self, clear_color: &Color) {
+        self.clear_color = *clear_color;
+        let (x0, y0, x1, y1) = self.viewport_bounds();
+        for y in y0..y1 {
+            let row = y * SCREEN_WIDTH;
+            self.pixels[row + x0..row + x1].fill(*clear_color);
+            self.depth_buffer[row + x0..row + x1].fill(self.depth_encoding.clear_value());
+            self.normal_buffer[row + x0..row + x1].fill(Vector3::new(0.0, 0.0, 0.0));
+            if self.render_ids {
+                self.id_buffer[row + x0..row + x1].fill(0);
+            }
+        }
     }
 
     pub fn draw_pixel(
@@ -35,19 +141,44 @@ impl Screen {
         if pp.depth < 0.0 {
             return
         }
+        let (near, far) = self.depth_range;
+        let encoded = self.depth_encoding.encode(pp.depth, near, far);
         let pixel_depth = self.depth_buffer[pp.y * SCREEN_WIDTH + pp.x];
-        if pp.depth < pixel_depth {
+        if self.depth_encoding.is_closer(encoded, pixel_depth) {
             let mut pp = pp.clone();
             shader.process(&mut pp, &triangle);
-            self.fog_shader.process(&mut pp, &triangle);
+            self.fog.apply(&mut pp, &self.clear_color);
             if pp.color.a > 0 {
                 self.pixels[pp.y * SCREEN_WIDTH + pp.x] = pp.color;
-                self.depth_buffer[pp.y * SCREEN_WIDTH + pp.x] = pp.depth
+                self.depth_buffer[pp.y * SCREEN_WIDTH + pp.x] = encoded;
+                self.normal_buffer[pp.y * SCREEN_WIDTH + pp.x] = pp.normal;
+                if self.render_ids {
+                    self.id_buffer[pp.y * SCREEN_WIDTH + pp.x] = self.object_id;
+                }
             }
         }
     }
 
+    // View depth at a buffer index whatever the encoding, infinite where
+    // nothing was drawn
+    pub fn linear_depth(&self, index: usize) -> f32 {
+        let (near, far) = self.depth_range;
+        self.depth_encoding.decode(self.depth_buffer[index], near, far)
+    }
+
+    // Runs the post-process chain over the finished frame
+    pub fn apply_post_process(&mut self) {
+        let mut chain = std::mem::take(&mut self.post_process);
+        chain.process(self);
+        self.post_process = chain;
+    }
+
+    #[allow(dead_code)]
     pub fn draw_triangle(&mut self, tri: &Triangle, cam: &Camera, shader: &dyn PixelShader, texture: &Texture) {
-        tri.project_and_fill(self, cam, shader, texture);
+        self.cull_stats.triangles_submitted += 1;
+        self.depth_range = (cam.near_plane, cam.far_plane);
+        if tri.project_and_fill(self, cam, shader, texture) {
+            self.cull_stats.triangles_drawn += 1;
+        }
     }
 }
@@ -1,7 +1,7 @@
 use crate::mouse_button::MouseButton;
 use crate::mouse_event::MouseEvent;
 use crate::screen::{SCREEN_HEIGHT, SCREEN_WIDTH};
-use crate::{game::Game, key_event::KeyEvent, keycode::KeyCode, screen::Screen, window::Window};
+use crate::{debug_draw::DebugDraw, game::Game, key_event::KeyEvent, keycode::KeyCode, screen::Screen, window::Window};
 
 pub struct SDL2Window;
 
@@ -26,9 +26,17 @@ impl Window for SDL2Window {
             .unwrap();
         let mut event_pump = sdl_context.event_pump().unwrap();
 
+        let mouse = sdl_context.mouse();
+
         'running: loop {
             let frame_start = std::time::Instant::now();
 
+            // Captured mouse for look controls, only reports relative motion
+            let relative = game.wants_relative_mouse();
+            if mouse.relative_mouse_mode() != relative {
+                mouse.set_relative_mouse_mode(relative);
+            }
+
             for event in event_pump.poll_iter() {
                 use sdl2::event::Event;
 
@@ -79,10 +87,12 @@ impl Window for SDL2Window {
                             },
                         });
                     }
-                    Event::MouseMotion { timestamp: _, window_id: _, which: _, mousestate: _, x, y, xrel: _, yrel: _ } => {
+                    Event::MouseMotion { timestamp: _, window_id: _, which: _, mousestate: _, x, y, xrel, yrel } => {
                         game.mouse_event(&MouseEvent::NewPosition {
                             x: x as u32,
                             y: y as u32,
+                            xrel,
+                            yrel,
                         });
                     }
                     Event::MouseWheel { timestamp: _, window_id:_, which:_, x:_, y, direction:_, precise_x:_, precise_y:_ } => {
@@ -94,6 +104,8 @@ impl Window for SDL2Window {
 
             game.update_tick();
             game.render_tick(screen);
+            DebugDraw::flush(screen, &game.debug_views());
+            screen.apply_post_process();
 
             texture
                 .with_lock(None, |pixels: &mut [u8], pitch: usize| {
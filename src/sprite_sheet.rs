@@ -0,0 +1,88 @@
+use crate::{texture::Texture, vec2::Vector2};
+
+// A texture cut into a grid of equally sized frames, numbered row by row
+// from the top left
+#[derive(Clone)]
+pub struct SpriteSheet {
+    pub texture: Texture,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl SpriteSheet {
+    pub fn new(texture: Texture, columns: u32, rows: u32) -> Self {
+        SpriteSheet {
+            texture,
+            columns: columns.max(1),
+            rows: rows.max(1),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from_png_file(path: &str, columns: u32, rows: u32) -> std::io::Result<Self> {
+        Ok(Self::new(Texture::from_png_file(path)?, columns, rows))
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    // Top left and bottom right texture coordinates of a frame, wrapping
+    // past the last one. Pulled in half a texel so nearest sampling never
+    // picks up the neighbouring frame.
+    pub fn frame_uv(&self, frame: usize) -> (Vector2, Vector2) {
+        let frame = (frame % self.frame_count()) as u32;
+        let (column, row) = (frame % self.columns, frame / self.columns);
+        let (frame_w, frame_h) = (1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        let inset_u = 0.5 / self.texture.width.max(1) as f32;
+        let inset_v = 0.5 / self.texture.height.max(1) as f32;
+        let min = Vector2::new(column as f32 * frame_w, row as f32 * frame_h);
+        (
+            Vector2::new(min.x + inset_u, min.y + inset_v),
+            Vector2::new(min.x + frame_w - inset_u, min.y + frame_h - inset_v),
+        )
+    }
+}
+
+// A run of consecutive frames played at a fixed rate
+#[derive(Clone, Copy, Debug)]
+pub struct SpriteAnimation {
+    pub first_frame: usize,
+    pub frame_count: usize,
+    pub fps: f32,
+    // Otherwise it stops on the last frame
+    pub looping: bool,
+}
+
+impl SpriteAnimation {
+    pub fn new(first_frame: usize, frame_count: usize, fps: f32) -> Self {
+        SpriteAnimation {
+            first_frame,
+            frame_count: frame_count.max(1),
+            fps,
+            looping: true,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    // Sheet frame showing `time` seconds after the animation started
+    pub fn frame_at(&self, time: f32) -> usize {
+        let step = (time.max(0.0) * self.fps) as usize;
+        let step = if self.looping {
+            step % self.frame_count
+        } else {
+            step.min(self.frame_count - 1)
+        };
+        self.first_frame + step
+    }
+
+    #[allow(dead_code)]
+    pub fn duration(&self) -> f32 {
+        self.frame_count as f32 / self.fps
+    }
+}
@@ -0,0 +1,473 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::{
+    bounds::BoundingSphere, camera::Camera, color::Color, draw_list::DrawList, frustum::Frustum, heightmap::Heightmap,
+    mesh::Mesh, pixel_shader::PixelShader, screen::Screen, texture::Texture, triangle::Triangle, vec2::Vector2,
+    vec3::Vector3, vertex::Vertex,
+};
+
+// A heightmap laid out as a grid of quads on the XZ plane, split into square
+// chunks that are culled and given a level of detail on their own
+pub struct Terrain {
+    pub heightmap: Heightmap,
+    // World position of sample (0, 0), heights are added to its y
+    pub origin: Vector3,
+    // Distance between neighbouring samples
+    pub cell_size: f32,
+    // Cells along each side of a chunk. A power of two lets every LOD step
+    // divide it evenly.
+    pub chunk_cells: usize,
+    // Distance from the eye to a chunk's center past which each coarser LOD
+    // is used. LOD n keeps every 2^n-th sample.
+    pub lod_distances: Vec<f32>,
+    pub color: Color,
+    // Meshes `draw` has built, by chunk x and z, LOD and neighbour LODs.
+    // Stale once the fields above change, see `clear_mesh_cache`.
+    mesh_cache: RefCell<HashMap<ChunkKey, (Mesh, BoundingSphere)>>,
+}
+
+type ChunkKey = (usize, usize, usize, [usize; 4]);
+
+// Order of the `neighbor_lods` passed to `chunk_mesh`
+pub const NEIGHBOR_MIN_X: usize = 0;
+pub const NEIGHBOR_MAX_X: usize = 1;
+pub const NEIGHBOR_MIN_Z: usize = 2;
+pub const NEIGHBOR_MAX_Z: usize = 3;
+
+impl Terrain {
+    pub fn new(heightmap: Heightmap, origin: Vector3, cell_size: f32) -> Self {
+        Terrain {
+            heightmap,
+            origin,
+            cell_size,
+            chunk_cells: 16,
+            lod_distances: vec![20.0, 40.0, 80.0],
+            color: Color::new(255, 255, 255, 255),
+            mesh_cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_chunk_cells(mut self, chunk_cells: usize) -> Self {
+        self.chunk_cells = chunk_cells.max(1);
+        self
+    }
+
+    pub fn with_lod_distances(mut self, lod_distances: Vec<f32>) -> Self {
+        self.lod_distances = lod_distances;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    // World size along x and z
+    #[allow(dead_code)]
+    pub fn size(&self) -> (f32, f32) {
+        (
+            (self.heightmap.width - 1) as f32 * self.cell_size,
+            (self.heightmap.depth - 1) as f32 * self.cell_size,
+        )
+    }
+
+    // Chunks along x and z, the last ones may be smaller
+    pub fn chunk_count(&self) -> (usize, usize) {
+        (
+            (self.heightmap.width - 1).div_ceil(self.chunk_cells),
+            (self.heightmap.depth - 1).div_ceil(self.chunk_cells),
+        )
+    }
+
+    // Height of the surface as drawn at full detail, None outside the terrain
+    pub fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        let (cell_x, cell_z, fx, fz) = self.cell_at(x, z)?;
+        let h = |dx: usize, dz: usize| self.heightmap.get(cell_x + dx, cell_z + dz);
+        // Same split as the quads in `chunk_mesh`, along the a-c diagonal
+        let local = if fx >= fz {
+            h(0, 0) + (h(1, 0) - h(0, 0)) * fx + (h(1, 1) - h(1, 0)) * fz
+        } else {
+            h(0, 0) + (h(0, 1) - h(0, 0)) * fz + (h(1, 1) - h(0, 1)) * fx
+        };
+        Some(self.origin.y + local)
+    }
+
+    // Normal of the triangle under a point, None outside the terrain
+    #[allow(dead_code)]
+    pub fn normal_at(&self, x: f32, z: f32) -> Option<Vector3> {
+        let (cell_x, cell_z, fx, fz) = self.cell_at(x, z)?;
+        let h = |dx: usize, dz: usize| self.heightmap.get(cell_x + dx, cell_z + dz);
+        let (slope_x, slope_z) = if fx >= fz {
+            (h(1, 0) - h(0, 0), h(1, 1) - h(1, 0))
+        } else {
+            (h(1, 1) - h(0, 1), h(0, 1) - h(0, 0))
+        };
+        Some(Vector3::new(-slope_x, self.cell_size, -slope_z).normalize_v())
+    }
+
+    // Cell containing a point and where in it the point is, 0..1 on each axis
+    fn cell_at(&self, x: f32, z: f32) -> Option<(usize, usize, f32, f32)> {
+        let gx = (x - self.origin.x) / self.cell_size;
+        let gz = (z - self.origin.z) / self.cell_size;
+        let (max_x, max_z) = ((self.heightmap.width - 1) as f32, (self.heightmap.depth - 1) as f32);
+        if !(0.0..=max_x).contains(&gx) || !(0.0..=max_z).contains(&gz) {
+            return None;
+        }
+        // The far edge belongs to the last cell
+        let cell_x = (gx.floor() as usize).min(self.heightmap.width - 2);
+        let cell_z = (gz.floor() as usize).min(self.heightmap.depth - 2);
+        Some((cell_x, cell_z, gx - cell_x as f32, gz - cell_z as f32))
+    }
+
+    // Smooth normal at a sample, from the slope to its neighbours
+    fn sample_normal(&self, x: usize, z: usize) -> Vector3 {
+        let hm = &self.heightmap;
+        let (left, right) = (x.saturating_sub(1), (x + 1).min(hm.width - 1));
+        let (back, front) = (z.saturating_sub(1), (z + 1).min(hm.depth - 1));
+        let dx = (hm.get(right, z) - hm.get(left, z)) / (right - left) as f32;
+        let dz = (hm.get(x, front) - hm.get(x, back)) / (front - back) as f32;
+        Vector3::new(-dx, self.cell_size, -dz).normalize_v()
+    }
+
+    // Range of samples a chunk covers on each axis, inclusive
+    fn chunk_range(&self, chunk_x: usize, chunk_z: usize) -> ((usize, usize), (usize, usize)) {
+        let x0 = chunk_x * self.chunk_cells;
+        let z0 = chunk_z * self.chunk_cells;
+        (
+            (x0, (x0 + self.chunk_cells).min(self.heightmap.width - 1)),
+            (z0, (z0 + self.chunk_cells).min(self.heightmap.depth - 1)),
+        )
+    }
+
+    // Coarsest LOD, where a chunk is a single quad
+    pub fn max_lod(&self) -> usize {
+        self.chunk_cells.max(1).ilog2() as usize
+    }
+
+    // LOD a chunk gets when seen from `eye`
+    pub fn chunk_lod(&self, chunk_x: usize, chunk_z: usize, eye: &Vector3) -> usize {
+        let ((x0, x1), (z0, z1)) = self.chunk_range(chunk_x, chunk_z);
+        let center_x = self.origin.x + (x0 + x1) as f32 * 0.5 * self.cell_size;
+        let center_z = self.origin.z + (z0 + z1) as f32 * 0.5 * self.cell_size;
+        let center_y = self.height_at(center_x, center_z).unwrap_or(self.origin.y);
+        let distance = (Vector3::new(center_x, center_y, center_z) - *eye).length();
+        let lod = self.lod_distances.iter().filter(|&&d| distance > d).count();
+        lod.min(self.max_lod())
+    }
+
+    // One chunk at a LOD. Edges next to a coarser neighbour get their extra
+    // vertices moved onto the neighbour's edge, so the two meet without
+    // cracks. `neighbor_lods` is indexed by the NEIGHBOR_* constants; chunks
+    // at the terrain's border can pass their own LOD.
+    pub fn chunk_mesh(&self, chunk_x: usize, chunk_z: usize, lod: usize, neighbor_lods: [usize; 4]) -> Mesh {
+        let ((x0, x1), (z0, z1)) = self.chunk_range(chunk_x, chunk_z);
+        let step = 1 << lod.min(self.max_lod());
+        let neighbor_step = neighbor_lods.map(|l| 1 << l.min(self.max_lod()));
+        let xs = axis_samples(x0, x1, step);
+        let zs = axis_samples(z0, z1, step);
+
+        let height = |x: usize, z: usize| {
+            let on_edge = [x == x0, x == x1, z == z0, z == z1];
+            for side in 0..4 {
+                if on_edge[side] && neighbor_step[side] > step {
+                    let along_z = side == NEIGHBOR_MIN_X || side == NEIGHBOR_MAX_X;
+                    let (start, end) = if along_z { (z0, z1) } else { (x0, x1) };
+                    return self.edge_height(x, z, along_z, start, end, neighbor_step[side]);
+                }
+            }
+            self.heightmap.get(x, z)
+        };
+        let (u_scale, v_scale) = (
+            1.0 / (self.heightmap.width - 1) as f32,
+            1.0 / (self.heightmap.depth - 1) as f32,
+        );
+        let vertex = |x: usize, z: usize| {
+            let pos = self.origin
+                + Vector3::new(x as f32 * self.cell_size, height(x, z), z as f32 * self.cell_size);
+            // Texture coordinates span the whole terrain, for splat maps
+            let uv = Vector2::new(x as f32 * u_scale, z as f32 * v_scale);
+            Vertex::new(&pos, &uv, &self.color).with_normal(&self.sample_normal(x, z))
+        };
+
+        let mut mesh = Mesh::new();
+        for &z in &zs {
+            for &x in &xs {
+                mesh.vertices.push(vertex(x, z));
+            }
+        }
+        let row = xs.len() as u32;
+        for j in 0..zs.len() as u32 - 1 {
+            for i in 0..row - 1 {
+                let a = j * row + i;
+                let (b, c, d) = (a + 1, a + row + 1, a + row);
+                // Counter-clockwise from above, so face normals point up
+                mesh.indices.extend([a, c, b, a, d, c]);
+            }
+        }
+        mesh
+    }
+
+    #[allow(dead_code)]
+    pub fn chunk_triangles(&self, chunk_x: usize, chunk_z: usize, lod: usize, neighbor_lods: [usize; 4]) -> Vec<Triangle> {
+        self.chunk_mesh(chunk_x, chunk_z, lod, neighbor_lods).to_triangles()
+    }
+
+    // Height at a point on a chunk edge as seen by a neighbour keeping every
+    // `step`-th sample along it: a straight line between those samples
+    fn edge_height(&self, x: usize, z: usize, along_z: bool, start: usize, end: usize, step: usize) -> f32 {
+        let p = if along_z { z } else { x };
+        let lo = start + (p - start) / step * step;
+        let hi = (lo + step).min(end);
+        let sample = |q: usize| if along_z { self.heightmap.get(x, q) } else { self.heightmap.get(q, z) };
+        if hi == lo {
+            return sample(lo);
+        }
+        let t = (p - lo) as f32 / (hi - lo) as f32;
+        sample(lo) + (sample(hi) - sample(lo)) * t
+    }
+
+    // Every chunk with the LOD its distance from `eye` calls for and the
+    // LODs of its neighbours, as `chunk_mesh` takes them
+    fn chunk_lods(&self, eye: &Vector3) -> Vec<ChunkKey> {
+        let (chunks_x, chunks_z) = self.chunk_count();
+        let lods: Vec<usize> = (0..chunks_z)
+            .flat_map(|cz| (0..chunks_x).map(move |cx| (cx, cz)))
+            .map(|(cx, cz)| self.chunk_lod(cx, cz, eye))
+            .collect();
+        let lod_of = |cx: usize, cz: usize| lods[cz * chunks_x + cx];
+
+        let mut chunks = Vec::with_capacity(lods.len());
+        for cz in 0..chunks_z {
+            for cx in 0..chunks_x {
+                let lod = lod_of(cx, cz);
+                let mut neighbors = [lod; 4];
+                if cx > 0 {
+                    neighbors[NEIGHBOR_MIN_X] = lod_of(cx - 1, cz);
+                }
+                if cx + 1 < chunks_x {
+                    neighbors[NEIGHBOR_MAX_X] = lod_of(cx + 1, cz);
+                }
+                if cz > 0 {
+                    neighbors[NEIGHBOR_MIN_Z] = lod_of(cx, cz - 1);
+                }
+                if cz + 1 < chunks_z {
+                    neighbors[NEIGHBOR_MAX_Z] = lod_of(cx, cz + 1);
+                }
+                chunks.push((cx, cz, lod, neighbors));
+            }
+        }
+        chunks
+    }
+
+    // Every chunk at the LOD its distance from `eye` calls for, one draw
+    // list item per chunk so they're frustum culled separately
+    #[allow(dead_code)]
+    pub fn draw_list(&self, eye: &Vector3) -> DrawList {
+        let mut draw_list = DrawList::new();
+        for (cx, cz, lod, neighbors) in self.chunk_lods(eye) {
+            draw_list.add_mesh(self.chunk_mesh(cx, cz, lod, neighbors));
+        }
+        draw_list
+    }
+
+    // Like drawing `draw_list(&cam.pos)`, but each chunk mesh is only built
+    // the first time its LOD and its neighbours' come up, then reused
+    pub fn draw(&self, screen: &mut Screen, cam: &Camera, shader: &dyn PixelShader, texture: &Texture) {
+        let (width, height) = screen.size();
+        let frustum = Frustum::from_camera(cam, width as f32 / height.max(1) as f32);
+        let mut cache = self.mesh_cache.borrow_mut();
+        screen.object_id = 0;
+        for key in self.chunk_lods(&cam.pos) {
+            let (mesh, bounds) = cache.entry(key).or_insert_with(|| {
+                let (cx, cz, lod, neighbors) = key;
+                let mesh = self.chunk_mesh(cx, cz, lod, neighbors);
+                let bounds = mesh.bounds();
+                (mesh, bounds)
+            });
+            screen.cull_stats.objects_submitted += 1;
+            if !frustum.intersects_sphere(bounds) {
+                screen.cull_stats.objects_culled += 1;
+                screen.cull_stats.triangles_submitted += mesh.triangle_count();
+                continue;
+            }
+            mesh.draw(screen, cam, shader, texture);
+        }
+    }
+
+    // Needed after changing the heightmap or anything else the meshes are
+    // built from
+    #[allow(dead_code)]
+    pub fn clear_mesh_cache(&self) {
+        self.mesh_cache.borrow_mut().clear();
+    }
+
+    // Full detail, one draw list item per chunk, for collision and picking
+    pub fn full_detail(&self) -> DrawList {
+        let (chunks_x, chunks_z) = self.chunk_count();
+        let mut draw_list = DrawList::new();
+        for cz in 0..chunks_z {
+            for cx in 0..chunks_x {
+                draw_list.add_mesh(self.chunk_mesh(cx, cz, 0, [0; 4]));
+            }
+        }
+        draw_list
+    }
+
+    // Splat weights from the shape of the terrain: red where it's low and
+    // flat, green on slopes, blue above `snow_height` (relative to the
+    // origin, fading in over one unit). One texel per sample, ready for
+    // `TerrainSplat`.
+    pub fn auto_splat_weights(&self, snow_height: f32, steep_slope: f32) -> Texture {
+        let hm = &self.heightmap;
+        let mut weights = Texture::new(hm.width as u32, hm.depth as u32);
+        for z in 0..hm.depth {
+            for x in 0..hm.width {
+                let normal = self.sample_normal(x, z);
+                let slope = (1.0 - normal.y).clamp(0.0, 1.0);
+                let rock = ((slope / steep_slope.max(1e-6)) - 0.5).clamp(0.0, 1.0);
+                let snow = (hm.get(x, z) - snow_height).clamp(0.0, 1.0) * (1.0 - rock);
+                let grass = (1.0 - rock - snow).max(0.0);
+                *weights.get_pixel_mut(x as u32, z as u32) =
+                    Color::new((grass * 255.0) as u8, (rock * 255.0) as u8, (snow * 255.0) as u8, 0);
+            }
+        }
+        weights
+    }
+}
+
+// Every `step`-th position from `start`, always ending on `end`
+fn axis_samples(start: usize, end: usize, step: usize) -> Vec<usize> {
+    let mut samples: Vec<usize> = (start..end).step_by(step.max(1)).collect();
+    samples.push(end);
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::Perlin;
+
+    // 3x3 chunks of 8 cells, the last row and column only 4 cells wide
+    fn hills() -> Terrain {
+        let heightmap = Heightmap::from_noise(21, 21, &Perlin::new(3), 0.15, 3, 4.0);
+        Terrain::new(heightmap, Vector3::new(-5.0, 1.0, 7.0), 0.5).with_chunk_cells(8)
+    }
+
+    // Height of the triangles under (x, z), interpolated across the one
+    // containing it
+    fn mesh_height(triangles: &[Triangle], x: f32, z: f32) -> Option<f32> {
+        triangles.iter().find_map(|t| {
+            let [a, b, c] = [t.v1.pos, t.v2.pos, t.v3.pos];
+            let det = (b.x - a.x) * (c.z - a.z) - (c.x - a.x) * (b.z - a.z);
+            let u = ((x - a.x) * (c.z - a.z) - (c.x - a.x) * (z - a.z)) / det;
+            let v = ((b.x - a.x) * (z - a.z) - (x - a.x) * (b.z - a.z)) / det;
+            let inside = u >= -1e-5 && v >= -1e-5 && u + v <= 1.0 + 1e-5;
+            inside.then_some(a.y + (b.y - a.y) * u + (c.y - a.y) * v)
+        })
+    }
+
+    #[test]
+    fn height_at_matches_the_full_detail_mesh() {
+        let terrain = hills();
+        let (chunks_x, chunks_z) = terrain.chunk_count();
+        let triangles: Vec<Triangle> = (0..chunks_z)
+            .flat_map(|cz| (0..chunks_x).map(move |cx| (cx, cz)))
+            .flat_map(|(cx, cz)| terrain.chunk_triangles(cx, cz, 0, [0; 4]))
+            .collect();
+        let (width, depth) = terrain.size();
+        let mut seed = 12345u32;
+        let mut random = || {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 8) as f32 / (1 << 24) as f32
+        };
+        for _ in 0..500 {
+            let x = terrain.origin.x + random() * width;
+            let z = terrain.origin.z + random() * depth;
+            let expected = mesh_height(&triangles, x, z).unwrap();
+            assert!((terrain.height_at(x, z).unwrap() - expected).abs() < 1e-4, "at {} {}", x, z);
+        }
+        // Both far corners are on the terrain, anything past them isn't
+        let far_x = terrain.origin.x + width;
+        let far_z = terrain.origin.z + depth;
+        assert!(terrain.height_at(far_x, far_z).is_some());
+        assert!(terrain.height_at(far_x + 0.01, far_z).is_none());
+        assert!(terrain.height_at(terrain.origin.x, terrain.origin.z - 0.01).is_none());
+    }
+
+    // Heights along a chunk's edge, as (position along it, height) pairs
+    fn edge(mesh: &Mesh, along_z: bool, at: f32) -> Vec<(f32, f32)> {
+        let mut points: Vec<(f32, f32)> = mesh
+            .vertices
+            .iter()
+            .filter(|v| (if along_z { v.pos.x } else { v.pos.z } - at).abs() < 1e-4)
+            .map(|v| (if along_z { v.pos.z } else { v.pos.x }, v.pos.y))
+            .collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points
+    }
+
+    fn height_along(points: &[(f32, f32)], p: f32) -> f32 {
+        let i = points.partition_point(|&(q, _)| q <= p).clamp(1, points.len() - 1);
+        let ((p0, h0), (p1, h1)) = (points[i - 1], points[i]);
+        h0 + (h1 - h0) * (p - p0) / (p1 - p0)
+    }
+
+    #[test]
+    fn neighbouring_lods_meet_without_cracks() {
+        let terrain = hills();
+        let max_lod = terrain.max_lod();
+        for (chunk, along_z) in [((0, 1), true), ((1, 0), false), ((1, 1), true), ((1, 1), false)] {
+            let (cx, cz) = chunk;
+            let next = if along_z { (cx + 1, cz) } else { (cx, cz + 1) };
+            let (here_side, next_side) =
+                if along_z { (NEIGHBOR_MAX_X, NEIGHBOR_MIN_X) } else { (NEIGHBOR_MAX_Z, NEIGHBOR_MIN_Z) };
+            let ((_, x1), (_, z1)) = terrain.chunk_range(cx, cz);
+            let at = if along_z {
+                terrain.origin.x + x1 as f32 * terrain.cell_size
+            } else {
+                terrain.origin.z + z1 as f32 * terrain.cell_size
+            };
+
+            for lod in 0..=max_lod {
+                for next_lod in 0..=max_lod {
+                    let mut neighbors = [lod; 4];
+                    neighbors[here_side] = next_lod;
+                    let here = edge(&terrain.chunk_mesh(cx, cz, lod, neighbors), along_z, at);
+                    let mut neighbors = [next_lod; 4];
+                    neighbors[next_side] = lod;
+                    let there = edge(&terrain.chunk_mesh(next.0, next.1, next_lod, neighbors), along_z, at);
+
+                    // Same ends, and every vertex of each lies on the other's edge
+                    assert_eq!(here.first().unwrap().0, there.first().unwrap().0);
+                    assert_eq!(here.last().unwrap().0, there.last().unwrap().0);
+                    for &(p, h) in &here {
+                        assert!((height_along(&there, p) - h).abs() < 1e-4, "{:?} lod {} {}", chunk, lod, next_lod);
+                    }
+                    for &(p, h) in &there {
+                        assert!((height_along(&here, p) - h).abs() < 1e-4, "{:?} lod {} {}", chunk, lod, next_lod);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn meshes_are_cached_per_lod() {
+        let terrain = hills();
+        let mut screen = Screen::new();
+        let mut cam = Camera::new();
+        cam.pos = Vector3::new(0.0, 10.0, 0.0);
+        cam.pointing_at = Vector3::new(0.0, 0.0, 12.0);
+        let texture = Texture::new(1, 1);
+        let shader = crate::dummy_passthru_shader::DummyPassthruShader;
+        terrain.draw(&mut screen, &cam, &shader, &texture);
+        let built = terrain.mesh_cache.borrow().len();
+        assert_eq!(built, 9);
+        terrain.draw(&mut screen, &cam, &shader, &texture);
+        assert_eq!(terrain.mesh_cache.borrow().len(), built);
+        terrain.clear_mesh_cache();
+        assert!(terrain.mesh_cache.borrow().is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{
+    color::Color, pixel_placement::PixelPlacement, pixel_shader::PixelShader, texture::Texture, triangle::Triangle,
+};
+
+// Blends up to four tiled layer textures by a weight map stretched over the
+// whole terrain. The weight map's red, green, blue and alpha channels are the
+// weights of layers 0 to 3 and don't need to add up to anything.
+pub struct TerrainSplat {
+    pub weights: Texture,
+    pub layers: Vec<Texture>,
+    // Times each layer repeats across the terrain
+    pub tiling: f32,
+}
+
+impl TerrainSplat {
+    pub fn new(weights: Texture, layers: Vec<Texture>, tiling: f32) -> Self {
+        TerrainSplat {
+            weights,
+            layers,
+            tiling,
+        }
+    }
+
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        // Clamped rather than wrapped, the weight map covers the terrain once
+        let w = self.weights.sample(u.clamp(0.0, 0.9999), v.clamp(0.0, 0.9999));
+        let channel_weights = [w.r, w.g, w.b, w.a];
+
+        let (mut r, mut g, mut b, mut total) = (0.0, 0.0, 0.0, 0.0);
+        for (layer, &weight) in self.layers.iter().zip(&channel_weights) {
+            if weight == 0 {
+                continue;
+            }
+            let weight = weight as f32;
+            let texel = layer.sample(u * self.tiling, v * self.tiling);
+            r += texel.r as f32 * weight;
+            g += texel.g as f32 * weight;
+            b += texel.b as f32 * weight;
+            total += weight;
+        }
+        if total == 0.0 {
+            // Nothing painted here, show the first layer
+            return match self.layers.first() {
+                Some(layer) => layer.sample(u * self.tiling, v * self.tiling),
+                None => Color::new(255, 255, 255, 255),
+            };
+        }
+        Color::new((r / total) as u8, (g / total) as u8, (b / total) as u8, 255)
+    }
+}
+
+// Multiplies the vertex color by the blended layers, expects the texture
+// coordinates `Terrain` gives its vertices
+impl PixelShader for TerrainSplat {
+    fn process(&self, pp: &mut PixelPlacement, _triangle: &Triangle) {
+        let texel = self.sample(pp.uv.x, pp.uv.y);
+        let modulate = |a: u8, b: u8| (a as u16 * b as u16 / 255) as u8;
+        pp.color = Color {
+            r: modulate(pp.color.r, texel.r),
+            g: modulate(pp.color.g, texel.g),
+            b: modulate(pp.color.b, texel.b),
+            a: pp.color.a,
+        };
+    }
+}
@@ -1,6 +1,8 @@
+use std::io::{Error, ErrorKind};
+
 use crate::color::Color;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Texture {
     pub width: u32,
     pub height: u32,
@@ -25,4 +27,80 @@ impl Texture {
         let index = (y * self.width + x) as usize;
         &self.pixels[index]
     }
+
+    // Nearest texel, u and v wrap around. v = 0 is the top row.
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return Color::new(255, 255, 255, 255);
+        }
+        let x = ((u.rem_euclid(1.0) * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v.rem_euclid(1.0) * self.height as f32) as u32).min(self.height - 1);
+        *self.get_pixel(x, y)
+    }
+
+    pub fn from_png_file(path: &str) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_png_bytes(&bytes)
+    }
+
+    pub fn from_png_bytes(bytes: &[u8]) -> std::io::Result<Self> {
+        let mut decoder = png::Decoder::new(bytes);
+        // Expand palettes and low bit depths, drop 16 bit precision
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder
+            .read_info()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buf)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        let channels = match info.color_type {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            png::ColorType::Rgb => 3,
+            png::ColorType::Rgba => 4,
+            png::ColorType::Indexed => {
+                return Err(Error::new(ErrorKind::InvalidData, "unexpanded indexed png"));
+            }
+        };
+
+        let mut pixels = Vec::with_capacity((info.width * info.height) as usize);
+        for y in 0..info.height as usize {
+            let row = &buf[y * info.line_size..];
+            for x in 0..info.width as usize {
+                let p = &row[x * channels..x * channels + channels];
+                pixels.push(match channels {
+                    1 => Color::new(p[0], p[0], p[0], 255),
+                    2 => Color::new(p[0], p[0], p[0], p[1]),
+                    3 => Color::new(p[0], p[1], p[2], 255),
+                    _ => Color::new(p[0], p[1], p[2], p[3]),
+                });
+            }
+        }
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    // RGBA, 8 bits per channel
+    pub fn to_png_bytes(&self) -> std::io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let data: Vec<u8> = self.pixels.iter().flat_map(|c| [c.r, c.g, c.b, c.a]).collect();
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&data))
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(bytes)
+    }
+
+    pub fn save_png_file(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_png_bytes()?)
+    }
 }
@@ -0,0 +1,53 @@
+use crate::vec3::Vector3;
+
+// Placement of an entity in the world. Camera rigs follow one of these, so
+// anything that moves can be tracked by keeping its transform up to date.
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    pub position: Vector3,
+    // Radians around the Y axis, 0 faces -Z
+    pub yaw: f32,
+    // Radians above the horizon
+    pub pitch: f32,
+    pub scale: f32,
+}
+
+impl Transform {
+    pub fn new(position: Vector3) -> Self {
+        Transform {
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            scale: 1.0,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rotation(mut self, yaw: f32, pitch: f32) -> Self {
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn forward(&self) -> Vector3 {
+        Vector3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+
+    pub fn right(&self) -> Vector3 {
+        Vector3::new(self.yaw.cos(), 0.0, self.yaw.sin())
+    }
+
+    pub fn up(&self) -> Vector3 {
+        self.right().cross(&self.forward())
+    }
+
+    // Local point (x right, y up, z forward) to world space
+    pub fn transform_point(&self, p: &Vector3) -> Vector3 {
+        self.position
+            + (self.right() * p.x + self.up() * p.y + self.forward() * p.z) * self.scale
+    }
+}
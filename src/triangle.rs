@@ -1,8 +1,7 @@
-use crate::screen::{SCREEN_HEIGHT, SCREEN_WIDTH};
 use crate::texture::Texture;
 use crate::{
     camera::Camera, color::Color, pixel_placement::PixelPlacement, pixel_shader::PixelShader,
-    screen::Screen, vec2::Vector2, vec3::Vector3, vertex::Vertex,
+    rect::Rect, screen::Screen, vec2::Vector2, vec3::Vector3, vertex::Vertex,
 };
 
 #[derive(Clone)]
@@ -30,16 +29,30 @@ impl Triangle {
         a * v1.y + b * v2.y + c * v3.y
     }*/
         
-    pub fn fill(&self, screen: &mut Screen, shader: &dyn PixelShader, texture: &Texture) {
+    // `self` is the projected triangle, `world` the original it was projected from
+    // and `clip_w` the projective divisor of each corner, for perspective-correct
+    // interpolation of world attributes
+    pub fn fill(&self, world: &Triangle, clip_w: &[f32; 3], screen: &mut Screen, shader: &dyn PixelShader, texture: &Texture) {
+        // Only pixels inside the viewport get touched
+        let (view_x0, view_y0, view_x1, view_y1) = screen.viewport_bounds();
+        if view_x1 <= view_x0 || view_y1 <= view_y0 {
+            return;
+        }
+
         let min_x = self.v1.pos.x.min(self.v2.pos.x).min(self.v3.pos.x).floor() as usize;
         let max_x = self.v1.pos.x.max(self.v2.pos.x).max(self.v3.pos.x).ceil() as usize;
         let min_y = self.v1.pos.y.min(self.v2.pos.y).min(self.v3.pos.y).floor() as usize;
         let max_y = self.v1.pos.y.max(self.v2.pos.y).max(self.v3.pos.y).ceil() as usize;
 
-        let min_x = min_x.clamp(0, SCREEN_WIDTH - 1);
-        let max_x = max_x.clamp(0, SCREEN_WIDTH - 1);
-        let min_y = min_y.clamp(0, SCREEN_HEIGHT - 1);
-        let max_y = max_y.clamp(0, SCREEN_HEIGHT - 1);
+        let min_x = min_x.clamp(view_x0, view_x1 - 1);
+        let max_x = max_x.clamp(view_x0, view_x1 - 1);
+        let min_y = min_y.clamp(view_y0, view_y1 - 1);
+        let max_y = max_y.clamp(view_y0, view_y1 - 1);
+
+        let face_normal = self.viewer_facing_normal(world);
+        let has_vertex_normals = world.v1.normal.length() > 0.0
+            && world.v2.normal.length() > 0.0
+            && world.v3.normal.length() > 0.0;
 
         for y in min_y..=max_y {
             for x in min_x..=max_x {
@@ -49,12 +62,20 @@ impl Triangle {
                 let (alpha, beta, gamma) = self.barycentric_coords(px, py);
 
                 if alpha >= 0.0 && beta >= 0.0 && gamma >= 0.0 {
-                    // Interpolate depth using z coordinates
-                    let depth =
-                        alpha * self.v1.pos.z + beta * self.v2.pos.z + gamma * self.v3.pos.z;
-
                     let color = self.interpolate_color(alpha, beta, gamma);
-                    let pixel = PixelPlacement { x, y, color, depth };
+                    let weights = Self::perspective_weights(clip_w, alpha, beta, gamma);
+                    // View depth, perspective-correct so it stays exact far from the camera
+                    let depth = weights[0] * self.v1.pos.z
+                        + weights[1] * self.v2.pos.z
+                        + weights[2] * self.v3.pos.z;
+                    let world_pos = Self::interpolate_world_pos(world, &weights);
+                    let normal = if has_vertex_normals {
+                        Self::interpolate_normal(world, &weights)
+                    } else {
+                        face_normal
+                    };
+                    let uv = Self::interpolate_uv(world, &weights);
+                    let pixel = PixelPlacement { x, y, color, depth, world_pos, normal, uv };
                     screen.draw_pixel(&pixel, shader, &self);
                 }
             }
@@ -81,6 +102,47 @@ impl Triangle {
         (alpha, beta, gamma)
     }
 
+    // Screen-space barycentrics turned into world-space ones by weighting
+    // each corner with 1 / w
+    fn perspective_weights(clip_w: &[f32; 3], alpha: f32, beta: f32, gamma: f32) -> [f32; 3] {
+        let w1 = alpha / clip_w[0];
+        let w2 = beta / clip_w[1];
+        let w3 = gamma / clip_w[2];
+        let sum = w1 + w2 + w3;
+        [w1 / sum, w2 / sum, w3 / sum]
+    }
+
+    fn interpolate_world_pos(world: &Triangle, weights: &[f32; 3]) -> Vector3 {
+        world.v1.pos * weights[0] + world.v2.pos * weights[1] + world.v3.pos * weights[2]
+    }
+
+    fn interpolate_normal(world: &Triangle, weights: &[f32; 3]) -> Vector3 {
+        (world.v1.normal * weights[0] + world.v2.normal * weights[1] + world.v3.normal * weights[2]).normalize_v()
+    }
+
+    fn interpolate_uv(world: &Triangle, weights: &[f32; 3]) -> Vector2 {
+        let (t1, t2, t3) = (&world.v1.texture_coord, &world.v2.texture_coord, &world.v3.texture_coord);
+        Vector2::new(
+            t1.x * weights[0] + t2.x * weights[1] + t3.x * weights[2],
+            t1.y * weights[0] + t2.y * weights[1] + t3.y * weights[2],
+        )
+    }
+
+    pub fn face_normal(&self) -> Vector3 {
+        let edge1 = self.v2.pos - self.v1.pos;
+        let edge2 = self.v3.pos - self.v1.pos;
+        edge1.cross(&edge2).normalize_v()
+    }
+
+    // Face normal of `world`, flipped towards the camera. A triangle whose
+    // normal faces the viewer winds clockwise on screen, since screen y points down.
+    fn viewer_facing_normal(&self, world: &Triangle) -> Vector3 {
+        let signed_area = (self.v2.pos.x - self.v1.pos.x) * (self.v3.pos.y - self.v1.pos.y)
+            - (self.v3.pos.x - self.v1.pos.x) * (self.v2.pos.y - self.v1.pos.y);
+        let normal = world.face_normal();
+        if signed_area > 0.0 { normal * -1.0 } else { normal }
+    }
+
     fn interpolate_color(&self, alpha: f32, beta: f32, gamma: f32) -> Color {
         let r = (self.v1.color.r as f32 * alpha
             + self.v2.color.r as f32 * beta
@@ -97,64 +159,89 @@ impl Triangle {
         Color { r, g, b, a: 255 }
     }
 
-    pub fn project_and_fill(&self, screen: &mut Screen, camera: &Camera, shader: &dyn PixelShader, texture: &Texture) {
-        let projected_triangle = self.with_applied_perspective(camera, SCREEN_WIDTH, SCREEN_HEIGHT);
-        if let Some(triangle) = projected_triangle {
-            triangle.fill(screen, shader, texture);
+    // Returns false if the triangle was rejected before rasterization
+    pub fn project_and_fill(&self, screen: &mut Screen, camera: &Camera, shader: &dyn PixelShader, texture: &Texture) -> bool {
+        let viewport = screen.viewport();
+        let projected_triangle = self.with_applied_perspective(camera, &viewport);
+        if let Some((triangle, clip_w)) = projected_triangle {
+            triangle.fill(self, &clip_w, screen, shader, texture);
+            return true;
         }
+        false
     }
 
     fn with_applied_perspective(
         &self,
         camera: &Camera,
-        screen_width: usize,
-        screen_height: usize,
-    ) -> Option<Triangle> {
-        let forward = Vector3::normalize_v(&(camera.pointing_at - camera.pos));
-        let right = Vector3::normalize_v(&forward.cross(&Vector3::new(0.0, 1.0, 0.0)));
-        let up = Vector3::cross(&right, &forward);
+        viewport: &Rect,
+    ) -> Option<(Triangle, [f32; 3])> {
+        let projector = VertexProjector::new(camera, viewport)?;
+        let (projected_v1, w1) = projector.project(&self.v1)?;
+        let (projected_v2, w2) = projector.project(&self.v2)?;
+        let (projected_v3, w3) = projector.project(&self.v3)?;
+
+        Some((Triangle::new(projected_v1, projected_v2, projected_v3), [w1, w2, w3]))
+    }
+}
 
-        let aspect_ratio = screen_width as f32 / screen_height as f32;
-        let fov_radians = camera.fov.to_radians();
-        let tan_half_fov = (fov_radians / 2.0).tan();
+// Takes world-space vertices to screen space for one camera and viewport,
+// with the per-camera setup done once
+pub struct VertexProjector<'a> {
+    camera: &'a Camera,
+    viewport: Rect,
+    forward: Vector3,
+    right: Vector3,
+    up: Vector3,
+    aspect_ratio: f32,
+}
 
-        const NEAR_PLANE: f32 = 0.01;
-        const FAR_PLANE: f32 = 60.0;
+impl<'a> VertexProjector<'a> {
+    // None for an empty viewport, where nothing can be drawn
+    pub fn new(camera: &'a Camera, viewport: &Rect) -> Option<Self> {
+        if viewport.size.x <= 0.0 || viewport.size.y <= 0.0 {
+            return None;
+        }
+        let (forward, right, up) = camera.basis();
+        Some(VertexProjector {
+            camera,
+            viewport: *viewport,
+            forward,
+            right,
+            up,
+            aspect_ratio: viewport.size.x / viewport.size.y,
+        })
+    }
 
-        let project_vertex = |vertex: &Vertex| -> Option<Vertex> {
-            let relative_pos = Vector3::subtract(&vertex.pos, &camera.pos);
+    // Screen position with view depth as z, and the projective divisor for
+    // perspective-correct interpolation. None outside the near and far planes.
+    pub fn project(&self, vertex: &Vertex) -> Option<(Vertex, f32)> {
+        let camera = self.camera;
+        let viewport = &self.viewport;
+        let relative_pos = Vector3::subtract(&vertex.pos, &camera.pos);
 
-            let camera_x = Vector3::dot(&relative_pos, &right);
-            let camera_y = Vector3::dot(&relative_pos, &up);
-            let camera_z = Vector3::dot(&relative_pos, &forward);
+        let camera_x = Vector3::dot(&relative_pos, &self.right);
+        let camera_y = Vector3::dot(&relative_pos, &self.up);
+        let camera_z = Vector3::dot(&relative_pos, &self.forward);
 
-            if camera_z < NEAR_PLANE || camera_z > FAR_PLANE {
-                return None;
-            }
+        if camera_z < camera.near_plane || camera_z > camera.far_plane {
+            return None;
+        }
 
-            let ndc_x = camera_x / (tan_half_fov * camera_z);
-            let ndc_y = camera_y / (tan_half_fov * camera_z / aspect_ratio);
+        // Blends between perspective and orthographic, see Camera::view_half_extents
+        let (half_w, half_h) = camera.view_half_extents(camera_z, self.aspect_ratio);
+        let ndc_x = camera_x / half_w;
+        let ndc_y = camera_y / half_h;
 
-            let screen_x = (ndc_x * 0.5 + 0.5) * screen_width as f32;
-            let screen_y = (1.0 - (ndc_y * 0.5 + 0.5)) * screen_height as f32;
+        let screen_x = viewport.pos.x + (ndc_x * 0.5 + 0.5) * viewport.size.x;
+        let screen_y = viewport.pos.y + (1.0 - (ndc_y * 0.5 + 0.5)) * viewport.size.y;
 
-            Some(Vertex::new(
+        Some((
+            Vertex::new(
                 &Vector3::new(screen_x, screen_y, camera_z),
                 &vertex.texture_coord,
                 &vertex.color,
-            ))
-        };
-
-        let projected_v1 = project_vertex(&self.v1);
-        let projected_v2 = project_vertex(&self.v2);
-        let projected_v3 = project_vertex(&self.v3);
-        if projected_v1.is_none() || projected_v2.is_none() || projected_v3.is_none() {
-            return None;
-        }
-        let projected_v1 = projected_v1.unwrap();
-        let projected_v2 = projected_v2.unwrap();
-        let projected_v3 = projected_v3.unwrap();
-
-        Some(Triangle::new(projected_v1, projected_v2, projected_v3))
+            ),
+            half_w,
+        ))
     }
 }
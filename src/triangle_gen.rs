@@ -1,3 +1,5 @@
+use std::f32::consts::{PI, TAU};
+
 use crate::{camera::Camera, color::Color, triangle::Triangle, vec2::Vector2, vec3::Vector3, vertex::Vertex};
 
 pub struct TriangleGen;
@@ -94,6 +96,7 @@ impl TriangleGen {
             ),
         ]
     }
+    #[allow(dead_code)]
     pub fn create_3d_line(
         start: &Vector3,
         end: &Vector3,
@@ -102,13 +105,18 @@ impl TriangleGen {
         end_color: &Color,
         thickness: f32,
     ) -> Vec<Triangle> {
-        // Get camera right vector for billboarding
-        let forward = Vector3::normalize_v(&(camera.pointing_at - camera.pos));
-        let right = Vector3::normalize_v(&forward.cross(&Vector3::new(0.0, 1.0, 0.0)));
-        
+        // Widen sideways to both the line and the view of it, so the quad
+        // faces the camera from any angle. Looking straight down the line
+        // there is no such side, so fall back to the camera's right.
+        let (_forward, right, _up) = camera.basis();
+        let midpoint = Vector3::scale(&Vector3::add(start, end), 0.5);
+        let to_camera = Vector3::subtract(&camera.pos, &midpoint);
+        let side = Vector3::subtract(end, start).cross(&to_camera);
+        let side = if side.length() > 1e-6 { side.normalize_v() } else { right };
+
         // Calculate half-thickness offset vector
         let half_thickness = thickness * 0.5;
-        let offset = Vector3::scale(&right, half_thickness);
+        let offset = Vector3::scale(&side, half_thickness);
 
         // Calculate the four corners of the line quad
         let start_left = Vector3::subtract(start, &offset);
@@ -130,4 +138,306 @@ impl TriangleGen {
             ),
         ]
     }
+
+    // The solid generators below wind every triangle counter-clockwise seen
+    // from outside, give each vertex a unit normal and map UVs 0..1 with
+    // v = 0 at the top. Round shapes stand on the Y axis through `center`.
+
+    // Keeps the triangle counter-clockwise seen from the side its vertex
+    // normals point to, None when it has no area (like at a sphere's pole)
+    fn outward(tri: Triangle) -> Option<Triangle> {
+        let face = (tri.v2.pos - tri.v1.pos).cross(&(tri.v3.pos - tri.v1.pos));
+        if face.length() < 1e-9 {
+            return None;
+        }
+        let normal = tri.v1.normal + tri.v2.normal + tri.v3.normal;
+        if Vector3::dot(&face, &normal) < 0.0 {
+            Some(Triangle::new(tri.v1, tri.v3, tri.v2))
+        } else {
+            Some(tri)
+        }
+    }
+
+    // Two triangles per quad of a (cols + 1) x (rows + 1) vertex grid
+    fn quad_grid(cols: usize, rows: usize, vertex: impl Fn(usize, usize) -> Vertex) -> Vec<Triangle> {
+        let mut triangles = vec![];
+        for j in 0..rows {
+            for i in 0..cols {
+                let (a, b) = (vertex(i, j), vertex(i + 1, j));
+                let (c, d) = (vertex(i + 1, j + 1), vertex(i, j + 1));
+                triangles.extend(Self::outward(Triangle::new(a.clone(), b, c.clone())));
+                triangles.extend(Self::outward(Triangle::new(a, c, d)));
+            }
+        }
+        triangles
+    }
+
+    // Grid of `cols` x `rows` quads over a surface. `surface(u, v)` gives the
+    // offset from `center` and the normal for u and v in 0..1.
+    fn parametric(
+        center: &Vector3,
+        cols: usize,
+        rows: usize,
+        color: &Color,
+        surface: impl Fn(f32, f32) -> (Vector3, Vector3),
+    ) -> Vec<Triangle> {
+        let (cols, rows) = (cols.max(1), rows.max(1));
+        Self::quad_grid(cols, rows, |i, j| {
+            let (u, v) = (i as f32 / cols as f32, j as f32 / rows as f32);
+            let (offset, normal) = surface(u, v);
+            Vertex::new(&(*center + offset), &Vector2::new(u, v), color).with_normal(&normal.normalize_v())
+        })
+    }
+
+    // Flat disc facing `normal`, UVs mapped across it
+    fn disc(center: &Vector3, radius: f32, segments: usize, up: bool, color: &Color) -> Vec<Triangle> {
+        let segments = segments.max(3);
+        let normal = Vector3::new(0.0, if up { 1.0 } else { -1.0 }, 0.0);
+        let rim = |k: usize| -> Vertex {
+            let a = k as f32 / segments as f32 * TAU;
+            let (sin, cos) = a.sin_cos();
+            Vertex::new(
+                &(*center + Vector3::new(cos * radius, 0.0, sin * radius)),
+                &Vector2::new(0.5 + cos * 0.5, 0.5 + sin * 0.5),
+                color,
+            )
+            .with_normal(&normal)
+        };
+        let middle = Vertex::new(center, &Vector2::new(0.5, 0.5), color).with_normal(&normal);
+        (0..segments)
+            .filter_map(|k| Self::outward(Triangle::new(middle.clone(), rim(k), rim(k + 1))))
+            .collect()
+    }
+
+    // Flat grid on the XZ plane facing +Y
+    #[allow(dead_code)]
+    pub fn create_plane_grid(
+        center: &Vector3,
+        width: f32,
+        depth: f32,
+        subdivisions_x: usize,
+        subdivisions_z: usize,
+        color: &Color,
+    ) -> Vec<Triangle> {
+        Self::parametric(center, subdivisions_x, subdivisions_z, color, |u, v| {
+            (Vector3::new((u - 0.5) * width, 0.0, (v - 0.5) * depth), Vector3::new(0.0, 1.0, 0.0))
+        })
+    }
+
+    // Axis-aligned box, each face mapped to the full texture
+    pub fn create_box(center: &Vector3, size: &Vector3, color: &Color) -> Vec<Triangle> {
+        let half = *size * 0.5;
+        // Normal, then the axes u and v run along on that face
+        let faces = [
+            (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, -1.0, 0.0)),
+            (Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, -1.0, 0.0)),
+            (Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Vector3::new(0.0, -1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0)),
+            (Vector3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+            (Vector3::new(0.0, 0.0, -1.0), Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+        ];
+        let scaled = |a: &Vector3| Vector3::new(a.x * half.x, a.y * half.y, a.z * half.z);
+        faces
+            .iter()
+            .flat_map(|(normal, u_axis, v_axis)| {
+                Self::parametric(center, 1, 1, color, |u, v| {
+                    let offset = scaled(normal) + scaled(u_axis) * (u * 2.0 - 1.0) + scaled(v_axis) * (v * 2.0 - 1.0);
+                    (offset, *normal)
+                })
+            })
+            .collect()
+    }
+
+    // Closed version of `create_wall`: a slab along the same base line,
+    // `thickness` deep and centered on it, for cutting openings into with
+    // `Csg`
+    pub fn create_wall_block(
+        bottom_left: &Vector3,
+        length: f32,
+        height: f32,
+        thickness: f32,
+        rotation_deg: f32,
+        color: &Color,
+    ) -> Vec<Triangle> {
+        let (sin, cos) = rotation_deg.to_radians().sin_cos();
+        // Local x runs along the wall, z through it. Turning about Y keeps
+        // the winding.
+        let along = Vector3::new(cos, 0.0, sin);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let through = Vector3::new(-sin, 0.0, cos);
+        let place = |v: &Vector3| along * v.x + up * v.y + through * v.z;
+        let local_center = Vector3::new(length / 2.0, height / 2.0, 0.0);
+
+        Self::create_box(&Vector3::new(0.0, 0.0, 0.0), &Vector3::new(length, height, thickness), color)
+            .into_iter()
+            .map(|tri| {
+                let corner = |v: Vertex| {
+                    let normal = place(&v.normal);
+                    let mut moved = v.with_normal(&normal);
+                    moved.pos = *bottom_left + place(&(moved.pos + local_center));
+                    moved
+                };
+                Triangle::new(corner(tri.v1), corner(tri.v2), corner(tri.v3))
+            })
+            .collect()
+    }
+
+    // Latitude/longitude sphere. UVs wrap once around, poles at v = 0 and 1.
+    #[allow(dead_code)]
+    pub fn create_uv_sphere(center: &Vector3, radius: f32, segments: usize, rings: usize, color: &Color) -> Vec<Triangle> {
+        Self::parametric(center, segments.max(3), rings.max(2), color, |u, v| {
+            let (theta, phi) = (u * TAU, v * PI);
+            let n = Vector3::new(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+            (n * radius, n)
+        })
+    }
+
+    // Subdivided icosahedron, evenly spread triangles without pole pinching.
+    // Spherical UVs, fixed up where triangles straddle the seam.
+    #[allow(dead_code)]
+    pub fn create_icosphere(center: &Vector3, radius: f32, subdivisions: usize, color: &Color) -> Vec<Triangle> {
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let corners = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ]
+        .map(|(x, y, z)| Vector3::new(x, y, z).normalize_v());
+        let faces = [
+            (0, 11, 5), (0, 5, 1), (0, 1, 7), (0, 7, 10), (0, 10, 11),
+            (1, 5, 9), (5, 11, 4), (11, 10, 2), (10, 7, 6), (7, 1, 8),
+            (3, 9, 4), (3, 4, 2), (3, 2, 6), (3, 6, 8), (3, 8, 9),
+            (4, 9, 5), (2, 4, 11), (6, 2, 10), (8, 6, 7), (9, 8, 1),
+        ];
+        let mut directions: Vec<[Vector3; 3]> = faces
+            .iter()
+            .map(|&(a, b, c)| [corners[a], corners[b], corners[c]])
+            .collect();
+        for _ in 0..subdivisions {
+            directions = directions
+                .iter()
+                .flat_map(|[a, b, c]| {
+                    let ab = (*a + *b).normalize_v();
+                    let bc = (*b + *c).normalize_v();
+                    let ca = (*c + *a).normalize_v();
+                    [[*a, ab, ca], [ab, *b, bc], [ca, bc, *c], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        let uv_of = |n: &Vector3| Vector2::new(0.5 + n.z.atan2(n.x) / TAU, n.y.clamp(-1.0, 1.0).acos() / PI);
+        directions
+            .iter()
+            .filter_map(|dirs| {
+                let mut uvs = dirs.map(|n| uv_of(&n));
+                // Pull corners that wrapped past the seam back next to the others
+                let max_u = uvs.iter().fold(0.0f32, |m, uv| m.max(uv.x));
+                for uv in &mut uvs {
+                    if max_u - uv.x > 0.5 {
+                        uv.x += 1.0;
+                    }
+                }
+                let vertex = |k: usize| {
+                    Vertex::new(&(*center + dirs[k] * radius), &uvs[k], color).with_normal(&dirs[k])
+                };
+                Self::outward(Triangle::new(vertex(0), vertex(1), vertex(2)))
+            })
+            .collect()
+    }
+
+    pub fn create_cylinder(
+        center: &Vector3,
+        radius: f32,
+        height: f32,
+        segments: usize,
+        capped: bool,
+        color: &Color,
+    ) -> Vec<Triangle> {
+        let half = height / 2.0;
+        let mut triangles = Self::parametric(center, segments.max(3), 1, color, |u, v| {
+            let (sin, cos) = (u * TAU).sin_cos();
+            (Vector3::new(cos * radius, half - v * height, sin * radius), Vector3::new(cos, 0.0, sin))
+        });
+        if capped {
+            triangles.extend(Self::disc(&(*center + Vector3::new(0.0, half, 0.0)), radius, segments, true, color));
+            triangles.extend(Self::disc(&(*center + Vector3::new(0.0, -half, 0.0)), radius, segments, false, color));
+        }
+        triangles
+    }
+
+    // Base centered `height / 2` below `center`, apex the same distance above
+    #[allow(dead_code)]
+    pub fn create_cone(center: &Vector3, radius: f32, height: f32, segments: usize, color: &Color) -> Vec<Triangle> {
+        let half = height / 2.0;
+        let slope = radius / height.max(1e-6);
+        let segments = segments.max(3);
+        let mut triangles = Self::quad_grid(segments, 1, |i, j| {
+            let u = i as f32 / segments as f32;
+            // The apex takes the normal of the middle of its segment, so
+            // shading stays smooth instead of pinching to one direction
+            let angle = if j == 0 { (i as f32 + 0.5) / segments as f32 } else { u } * TAU;
+            let (sin, cos) = angle.sin_cos();
+            let r = radius * j as f32;
+            let pos = *center + Vector3::new(cos * r, half - j as f32 * height, sin * r);
+            Vertex::new(&pos, &Vector2::new(u, j as f32), color)
+                .with_normal(&Vector3::new(cos, slope, sin).normalize_v())
+        });
+        triangles.extend(Self::disc(&(*center + Vector3::new(0.0, -half, 0.0)), radius, segments, false, color));
+        triangles
+    }
+
+    // Ring around the Y axis. u runs around the ring, v around the tube.
+    #[allow(dead_code)]
+    pub fn create_torus(
+        center: &Vector3,
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: usize,
+        minor_segments: usize,
+        color: &Color,
+    ) -> Vec<Triangle> {
+        Self::parametric(center, major_segments.max(3), minor_segments.max(3), color, |u, v| {
+            let (sin_u, cos_u) = (u * TAU).sin_cos();
+            let (sin_v, cos_v) = (v * TAU).sin_cos();
+            let n = Vector3::new(cos_u * cos_v, sin_v, sin_u * cos_v);
+            let ring = Vector3::new(cos_u * major_radius, 0.0, sin_u * major_radius);
+            (ring + n * minor_radius, n)
+        })
+    }
+
+    // Cylinder of `height` with a hemisphere on each end, so the whole thing
+    // is height + 2 * radius tall. `rings` is per hemisphere.
+    #[allow(dead_code)]
+    pub fn create_capsule(
+        center: &Vector3,
+        radius: f32,
+        height: f32,
+        segments: usize,
+        rings: usize,
+        color: &Color,
+    ) -> Vec<Triangle> {
+        let half = height / 2.0;
+        let (segments, rings) = (segments.max(3), rings.max(1));
+        // Angle down from the top and height of each row's center: the top
+        // hemisphere, then the bottom one, with the body spanning the gap
+        let rows: Vec<(f32, f32)> = (0..=rings)
+            .map(|r| (r as f32 / rings as f32 * PI / 2.0, half))
+            .chain((0..=rings).map(|r| (PI / 2.0 + r as f32 / rings as f32 * PI / 2.0, -half)))
+            .collect();
+        // v follows arc length so textures don't stretch over the body
+        let total = PI * radius + height;
+        let v_of = |row: usize| -> f32 {
+            let (phi, _) = rows[row];
+            let along = phi * radius + if row > rings { height } else { 0.0 };
+            along / total
+        };
+        Self::quad_grid(segments, rows.len() - 1, |i, j| {
+            let u = i as f32 / segments as f32;
+            let (sin, cos) = (u * TAU).sin_cos();
+            let (phi, y) = rows[j];
+            let n = Vector3::new(phi.sin() * cos, phi.cos(), phi.sin() * sin);
+            Vertex::new(&(*center + n * radius + Vector3::new(0.0, y, 0.0)), &Vector2::new(u, v_of(j)), color)
+                .with_normal(&n)
+        })
+    }
 }
@@ -1,6 +1,6 @@
 use std::ops::{Mul, Sub};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct Vector2 {
     pub x: f32,
     pub y: f32,
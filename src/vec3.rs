@@ -14,6 +14,13 @@ impl std::ops::SubAssign for Vector3 {
         self.z -= rhs.z;
     }
 }
+impl std::ops::AddAssign for Vector3 {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
 impl Mul<f32> for Vector3 {
     type Output = Vector3;
 
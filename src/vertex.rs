@@ -5,6 +5,12 @@ pub struct Vertex {
     pub pos: Vector3,
     pub texture_coord: Vector2,
     pub color: Color,
+    // Zero when the vertex has no normal of its own
+    pub normal: Vector3,
+    // Direction of increasing u along the surface, zero until generated.
+    // The bitangent is normal x tangent times `tangent_sign`.
+    pub tangent: Vector3,
+    pub tangent_sign: f32,
 }
 
 impl Vertex {
@@ -25,6 +31,14 @@ impl Vertex {
                 b: color.b,
                 a: color.a,
             },
+            normal: Vector3::new(0.0, 0.0, 0.0),
+            tangent: Vector3::new(0.0, 0.0, 0.0),
+            tangent_sign: 1.0,
         }
     }
+
+    pub fn with_normal(mut self, normal: &Vector3) -> Self {
+        self.normal = *normal;
+        self
+    }
 }
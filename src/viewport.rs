@@ -0,0 +1,91 @@
+use crate::{
+    camera::{Camera, Projection},
+    color::Color,
+    rect::Rect,
+    screen::Screen,
+    vec2::Vector2,
+    vec3::Vector3,
+};
+
+// A part of the screen seen through its own camera. Several of them make
+// split-screen, picture-in-picture or editor layouts in a single frame.
+pub struct Viewport {
+    pub rect: Rect,
+    pub camera: Camera,
+    // Cleared to this by `begin`, None draws over whatever is there
+    pub clear_color: Option<Color>,
+}
+
+impl Viewport {
+    pub fn new(rect: Rect, camera: Camera) -> Self {
+        Viewport {
+            rect,
+            camera,
+            clear_color: None,
+        }
+    }
+
+    pub fn with_clear_color(mut self, color: Color) -> Self {
+        self.clear_color = Some(color);
+        self
+    }
+
+    pub fn full_screen(camera: Camera) -> Self {
+        Viewport::new(Screen::full_rect(), camera)
+    }
+
+    // Points the screen at this viewport, draw with `self.camera` afterwards
+    // and call `Screen::reset_viewport` when done with viewports
+    pub fn begin(&self, screen: &mut Screen) {
+        screen.set_viewport(self.rect);
+        if let Some(color) = &self.clear_color {
+            screen.clear_viewport(color);
+        }
+    }
+
+    // For routing mouse input to the viewport under the cursor
+    #[allow(dead_code)]
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        self.rect.contains(&Vector2::new(x, y))
+    }
+
+    // One viewport per camera, side by side
+    #[allow(dead_code)]
+    pub fn split_screen(cameras: Vec<Camera>) -> Vec<Viewport> {
+        Screen::full_rect()
+            .split_horizontal(cameras.len())
+            .into_iter()
+            .zip(cameras)
+            .map(|(rect, camera)| Viewport::new(rect, camera))
+            .collect()
+    }
+
+    // Editor layout: top, front, side and perspective views of `target`,
+    // top left to bottom right. The three flat views are orthographic and
+    // show `extent` world units top to bottom.
+    pub fn four_view(target: Vector3, distance: f32, extent: f32) -> Vec<Viewport> {
+        let ortho = |offset: Vector3| -> Camera {
+            let mut camera = Camera::new();
+            camera.pointing_at = target;
+            camera.pos = target + offset * distance;
+            camera.projection = Projection::Orthographic;
+            camera.projection_blend = 1.0;
+            camera.ortho_height = extent;
+            camera
+        };
+        let top = ortho(Vector3::new(0.0, 1.0, 0.0));
+        let front = ortho(Vector3::new(0.0, 0.0, -1.0));
+        let side = ortho(Vector3::new(1.0, 0.0, 0.0));
+
+        let mut perspective = Camera::new();
+        perspective.pointing_at = target;
+        perspective.pos = target + Vector3::new(1.0, 0.8, -1.0).normalize_v() * distance;
+
+        Screen::full_rect()
+            .grid(2, 2)
+            .into_iter()
+            .zip([top, front, side, perspective])
+            .map(|(rect, camera)| Viewport::new(rect, camera))
+            .collect()
+    }
+}